@@ -0,0 +1,190 @@
+// Circuit breaker wrapping an EsploraClient.
+//
+// After `threshold` consecutive backend failures (connection errors or 5xx
+// responses) requests to that backend fail immediately for `cooldown`. The
+// first request after the cooldown is let through as a probe: success closes
+// the circuit, failure opens it again. Client errors such as 404 mean the
+// backend is up and count as successes.
+use crate::esplora::{EsploraClient, is_backend_failure};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct BackendState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+pub struct CircuitBreakerClient {
+    inner: Arc<dyn EsploraClient>,
+    threshold: u32,
+    cooldown: Duration,
+    // Keyed by origin so that one unreachable instance doesn't block the others
+    backends: Mutex<HashMap<String, BackendState>>,
+}
+
+// Requests refused while the circuit is open
+pub fn is_circuit_open(err: &str) -> bool {
+    err.starts_with("Backend ") && err.contains(" unavailable")
+}
+
+// scheme://host[:port] of a URL
+fn origin(url: &str) -> &str {
+    let host_start = url.find("://").map_or(0, |i| i + 3);
+    match url[host_start..].find('/') {
+        Some(i) => &url[..host_start + i],
+        None => url,
+    }
+}
+
+impl CircuitBreakerClient {
+    pub fn new(inner: Arc<dyn EsploraClient>, threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreakerClient {
+            inner,
+            threshold,
+            cooldown,
+            backends: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Decide whether a request may go through, marking it as the probe when
+    // the cooldown has elapsed
+    fn admit(&self, origin: &str) -> Result<(), String> {
+        let mut backends = self.backends.lock().unwrap();
+        let state = backends.entry(origin.to_string()).or_default();
+        let Some(open_until) = state.open_until else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if now < open_until {
+            let retry_in = (open_until - now).as_secs() + 1;
+            return Err(format!(
+                "Backend {origin} unavailable after {} consecutive failures, retry in {retry_in}s",
+                state.consecutive_failures
+            ));
+        }
+        if state.probing {
+            return Err(format!(
+                "Backend {origin} unavailable, waiting for a probe request to complete"
+            ));
+        }
+        state.probing = true;
+        Ok(())
+    }
+
+    fn record(&self, origin: &str, failed: bool) {
+        let mut backends = self.backends.lock().unwrap();
+        let state = backends.entry(origin.to_string()).or_default();
+        if !failed {
+            *state = BackendState::default();
+            return;
+        }
+        state.consecutive_failures += 1;
+        if state.probing || state.consecutive_failures >= self.threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+            state.probing = false;
+        }
+    }
+
+    fn guarded<T>(
+        &self,
+        url: &str,
+        request: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        if self.threshold == 0 {
+            return request();
+        }
+        let origin = origin(url);
+        self.admit(origin)?;
+        let result = request();
+        let failed = matches!(&result, Err(e) if is_backend_failure(e));
+        self.record(origin, failed);
+        result
+    }
+}
+
+impl EsploraClient for CircuitBreakerClient {
+    fn get(&self, url: &str) -> Result<String, String> {
+        self.guarded(url, || self.inner.get(url))
+    }
+
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        self.guarded(url, || self.inner.get_bytes(url))
+    }
+
+    fn post(&self, url: &str, body: &str) -> Result<String, String> {
+        self.guarded(url, || self.inner.post(url, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    // Client failing with a connection error until told to recover
+    #[derive(Default)]
+    struct FlakyClient {
+        up: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    impl EsploraClient for FlakyClient {
+        fn get(&self, url: &str) -> Result<String, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if url.ends_with("/missing") {
+                Err(format!("HTTP request failed: {url}: status code 404"))
+            } else if self.up.load(Ordering::SeqCst) {
+                Ok("ok".to_string())
+            } else {
+                Err("HTTP request failed: Connection refused".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(
+            origin("https://blockstream.info/api/tx/ab"),
+            "https://blockstream.info"
+        );
+        assert_eq!(origin("http://127.0.0.1:3000"), "http://127.0.0.1:3000");
+    }
+
+    #[test]
+    fn test_opens_after_threshold_and_recovers_after_probe() {
+        let inner = Arc::new(FlakyClient::default());
+        let breaker = CircuitBreakerClient::new(inner.clone(), 2, Duration::from_millis(50));
+
+        assert!(breaker.get("http://e/a").is_err());
+        assert!(breaker.get("http://e/a").is_err());
+        let err = breaker.get("http://e/a").unwrap_err();
+        assert!(err.contains("unavailable"), "unexpected error: {err}");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        // Other backends are unaffected
+        assert!(
+            !breaker
+                .get("http://other/a")
+                .unwrap_err()
+                .contains("unavailable")
+        );
+
+        std::thread::sleep(Duration::from_millis(60));
+        inner.up.store(true, Ordering::SeqCst);
+        assert_eq!(breaker.get("http://e/a").unwrap(), "ok");
+        assert_eq!(breaker.get("http://e/a").unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_not_found_does_not_trip() {
+        let inner = Arc::new(FlakyClient::default());
+        let breaker = CircuitBreakerClient::new(inner.clone(), 1, Duration::from_secs(60));
+        for _ in 0..3 {
+            assert!(breaker.get("http://e/missing").unwrap_err().contains("404"));
+        }
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,442 @@
+// Response cache wrapping an EsploraClient.
+//
+// Immutable responses are kept until evicted, volatile ones for a TTL, and the
+// rest is always fetched. Entries live in a `CacheBackend`, in memory or also
+// on disk with CACHE_DIR.
+use crate::esplora::EsploraClient;
+use rmcp::serde_json::{self, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Upper bound on the number of cached responses
+const MAX_ENTRIES: usize = 1000;
+
+// How long Liquid asset and registry responses are kept
+const ASSET_TTL: Duration = Duration::from_secs(600);
+
+// Entries buffered by the disk cache before being written out
+const DISK_WRITE_BATCH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+enum CachePolicy {
+    Forever,
+    // Kept forever once the transaction is confirmed, never before
+    ForeverIfConfirmed,
+    Ttl(Duration),
+    NoCache,
+}
+
+// Storage of cached responses. Entries without an expiry are immutable and
+// may be persisted, the others are only meant to absorb bursts.
+pub trait CacheBackend: Send + Sync {
+    fn lookup(&self, url: &str) -> Option<String>;
+    fn store(&self, url: &str, body: &str, expires_at: Option<Instant>);
+
+    // Persist buffered entries, returning how many were written
+    fn flush(&self) -> usize {
+        0
+    }
+}
+
+struct Entry {
+    body: String,
+    expires_at: Option<Instant>,
+    last_used: Instant,
+}
+
+// In-memory LRU cache, lost on restart
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+pub struct CachingClient {
+    inner: Arc<dyn EsploraClient>,
+    volatile_ttl: Duration,
+    backend: Arc<dyn CacheBackend>,
+}
+
+// /tx/{txid} itself, not one of its sub-resources
+fn is_tx_url(url: &str) -> bool {
+    url.rsplit_once("/tx/")
+        .is_some_and(|(_, txid)| txid.len() == 64 && txid.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+impl CachingClient {
+    pub fn new(
+        inner: Arc<dyn EsploraClient>,
+        volatile_ttl: Duration,
+        backend: Arc<dyn CacheBackend>,
+    ) -> Self {
+        CachingClient {
+            inner,
+            volatile_ttl,
+            backend,
+        }
+    }
+
+    fn policy(&self, url: &str) -> CachePolicy {
+        if url.contains("/mempool")
+            || url.ends_with("/fee-estimates")
+            || url.ends_with("/blocks/tip/height")
+            || url.ends_with("/blocks/tip/hash")
+        {
+            if self.volatile_ttl.is_zero() {
+                CachePolicy::NoCache
+            } else {
+                CachePolicy::Ttl(self.volatile_ttl)
+            }
+        } else if url.contains("/block/") && !url.ends_with("/status") {
+            // Blocks are addressed by hash, so their content never changes
+            CachePolicy::Forever
+        } else if is_tx_url(url) {
+            CachePolicy::ForeverIfConfirmed
+        } else if url.contains("/asset/") || url.contains("/assets/registry") {
+            CachePolicy::Ttl(ASSET_TTL)
+        } else {
+            CachePolicy::NoCache
+        }
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn lookup(&self, url: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        let entry = entries.get_mut(url)?;
+        if entry.expires_at.is_some_and(|expires_at| now >= expires_at) {
+            entries.remove(url);
+            return None;
+        }
+        entry.last_used = now;
+        Some(entry.body.clone())
+    }
+
+    fn store(&self, url: &str, body: &str, expires_at: Option<Instant>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(url) {
+            // Evict the least recently used entry
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            url.to_string(),
+            Entry {
+                body: body.to_string(),
+                expires_at,
+                last_used: Instant::now(),
+            },
+        );
+    }
+}
+
+// Memory cache backed by one file per immutable entry in a directory, named
+// after the SHA256 of the URL. Volatile entries stay in memory only, and
+// immutable ones are written out in batches, on flush and when dropped.
+pub struct DiskCache {
+    dir: PathBuf,
+    memory: MemoryCache,
+    pending: Mutex<Vec<(PathBuf, String)>>,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(DiskCache {
+            dir,
+            memory: MemoryCache::default(),
+            pending: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        use bitcoin::hashes::{Hash, sha256};
+        let hash = sha256::Hash::hash(url.as_bytes());
+        self.dir.join(hash.to_string())
+    }
+}
+
+impl CacheBackend for DiskCache {
+    fn lookup(&self, url: &str) -> Option<String> {
+        if let Some(body) = self.memory.lookup(url) {
+            return Some(body);
+        }
+        let body = std::fs::read_to_string(self.path(url)).ok()?;
+        self.memory.store(url, &body, None);
+        Some(body)
+    }
+
+    fn store(&self, url: &str, body: &str, expires_at: Option<Instant>) {
+        self.memory.store(url, body, expires_at);
+        if expires_at.is_some() {
+            return;
+        }
+        let batch_full = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.push((self.path(url), body.to_string()));
+            pending.len() >= DISK_WRITE_BATCH
+        };
+        if batch_full {
+            self.flush();
+        }
+    }
+
+    fn flush(&self) -> usize {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let mut written = 0;
+        for (path, body) in pending {
+            // Written aside then renamed, so that a crash never leaves a truncated entry
+            let partial = path.with_extension("partial");
+            match std::fs::write(&partial, body).and_then(|()| std::fs::rename(&partial, &path)) {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), "Failed to persist cache entry: {e}")
+                }
+            }
+        }
+        written
+    }
+}
+
+impl Drop for DiskCache {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl EsploraClient for CachingClient {
+    fn get(&self, url: &str) -> Result<String, String> {
+        let policy = self.policy(url);
+        let expires_at = match policy {
+            CachePolicy::NoCache => return self.inner.get(url),
+            CachePolicy::Forever | CachePolicy::ForeverIfConfirmed => None,
+            CachePolicy::Ttl(ttl) => Some(Instant::now() + ttl),
+        };
+        if let Some(body) = self.backend.lookup(url) {
+            return Ok(body);
+        }
+        let body = self.inner.get(url)?;
+        let cacheable = policy != CachePolicy::ForeverIfConfirmed
+            || serde_json::from_str::<Value>(&body)
+                .is_ok_and(|tx| tx["status"]["confirmed"] == true);
+        if cacheable {
+            self.backend.store(url, &body, expires_at);
+        }
+        Ok(body)
+    }
+
+    // Binary responses are rare and never cached
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        self.inner.get_bytes(url)
+    }
+
+    fn post(&self, url: &str, body: &str) -> Result<String, String> {
+        self.inner.post(url, body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Client counting the number of upstream requests
+    #[derive(Default)]
+    struct CountingClient {
+        calls: AtomicUsize,
+    }
+
+    impl EsploraClient for CountingClient {
+        fn get(&self, _url: &str) -> Result<String, String> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("response {n}"))
+        }
+    }
+
+    #[test]
+    fn test_policy() {
+        let cache = CachingClient::new(
+            Arc::new(CountingClient::default()),
+            Duration::from_secs(10),
+            Arc::new(MemoryCache::default()),
+        );
+        let ttl = CachePolicy::Ttl(Duration::from_secs(10));
+        assert_eq!(cache.policy("http://e/fee-estimates"), ttl);
+        assert_eq!(cache.policy("http://e/mempool"), ttl);
+        assert_eq!(cache.policy("http://e/mempool/txids"), ttl);
+        assert_eq!(cache.policy("http://e/block/00ff"), CachePolicy::Forever);
+        assert_eq!(
+            cache.policy("http://e/block/00ff/status"),
+            CachePolicy::NoCache
+        );
+        assert_eq!(cache.policy("http://e/blocks/tip/height"), ttl);
+        assert_eq!(cache.policy("http://e/blocks/tip/hash"), ttl);
+        assert_eq!(cache.policy("http://e/blocks/840000"), CachePolicy::NoCache);
+        let txid = "ab".repeat(32);
+        assert_eq!(
+            cache.policy(&format!("http://e/tx/{txid}")),
+            CachePolicy::ForeverIfConfirmed
+        );
+        assert_eq!(
+            cache.policy(&format!("http://e/tx/{txid}/status")),
+            CachePolicy::NoCache
+        );
+        assert_eq!(cache.policy("http://e/tx/abcd"), CachePolicy::NoCache);
+        assert_eq!(
+            cache.policy("http://e/asset/00ff"),
+            CachePolicy::Ttl(ASSET_TTL)
+        );
+
+        let disabled = CachingClient::new(
+            Arc::new(CountingClient::default()),
+            Duration::ZERO,
+            Arc::new(MemoryCache::default()),
+        );
+        assert_eq!(
+            disabled.policy("http://e/fee-estimates"),
+            CachePolicy::NoCache
+        );
+    }
+
+    #[test]
+    fn test_fee_estimates_cached_within_ttl() {
+        let inner = Arc::new(CountingClient::default());
+        let cache = CachingClient::new(
+            inner.clone(),
+            Duration::from_millis(50),
+            Arc::new(MemoryCache::default()),
+        );
+
+        let first = cache.get("http://e/fee-estimates").unwrap();
+        let second = cache.get("http://e/fee-estimates").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        std::thread::sleep(Duration::from_millis(60));
+        let third = cache.get("http://e/fee-estimates").unwrap();
+        assert_ne!(first, third);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    // Client answering transactions as confirmed or not depending on the txid
+    #[derive(Default)]
+    struct TxClient {
+        calls: AtomicUsize,
+    }
+
+    impl EsploraClient for TxClient {
+        fn get(&self, url: &str) -> Result<String, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let confirmed = url.ends_with(&"cc".repeat(32));
+            Ok(format!(r#"{{"status":{{"confirmed":{confirmed}}}}}"#))
+        }
+    }
+
+    #[test]
+    fn test_only_confirmed_txs_are_cached() {
+        let inner = Arc::new(TxClient::default());
+        let cache = CachingClient::new(
+            inner.clone(),
+            Duration::from_secs(10),
+            Arc::new(MemoryCache::default()),
+        );
+        let confirmed = format!("http://e/tx/{}", "cc".repeat(32));
+        let unconfirmed = format!("http://e/tx/{}", "00".repeat(32));
+        for _ in 0..2 {
+            cache.get(&confirmed).unwrap();
+            cache.get(&unconfirmed).unwrap();
+        }
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+
+    // A fresh directory under the system temporary directory
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("bitcoin-data-mcp-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn check_backend(backend: &dyn CacheBackend) {
+        assert_eq!(backend.lookup("http://e/block/00"), None);
+        backend.store("http://e/block/00", "block", None);
+        assert_eq!(
+            backend.lookup("http://e/block/00").as_deref(),
+            Some("block")
+        );
+        backend.store("http://e/mempool", "old", Some(Instant::now()));
+        assert_eq!(backend.lookup("http://e/mempool"), None);
+    }
+
+    #[test]
+    fn test_backends() {
+        check_backend(&MemoryCache::default());
+        let dir = temp_dir("backends");
+        check_backend(&DiskCache::new(dir.clone()).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_disk_cache_survives_restart() {
+        let dir = temp_dir("restart");
+        let cache = DiskCache::new(dir.clone()).unwrap();
+        cache.store("http://e/block/00", "block", None);
+        cache.store(
+            "http://e/mempool",
+            "mempool",
+            Some(Instant::now() + Duration::from_secs(60)),
+        );
+
+        drop(cache);
+
+        let restarted = DiskCache::new(dir.clone()).unwrap();
+        assert_eq!(
+            restarted.lookup("http://e/block/00").as_deref(),
+            Some("block")
+        );
+        // Volatile entries are not persisted
+        assert_eq!(restarted.lookup("http://e/mempool"), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_disk_cache_flush() {
+        let dir = temp_dir("flush");
+        let cache = DiskCache::new(dir.clone()).unwrap();
+        cache.store("http://e/block/00", "block 0", None);
+        cache.store("http://e/block/01", "block 1", None);
+        // Nothing is written until flushed
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        assert_eq!(cache.flush(), 2);
+        assert_eq!(cache.flush(), 0);
+
+        let reopened = DiskCache::new(dir.clone()).unwrap();
+        assert_eq!(
+            reopened.lookup("http://e/block/01").as_deref(),
+            Some("block 1")
+        );
+        assert_eq!(MemoryCache::default().flush(), 0);
+        drop(cache);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_uncached_endpoints_always_fetch() {
+        let inner = Arc::new(CountingClient::default());
+        let cache = CachingClient::new(
+            inner.clone(),
+            Duration::from_secs(10),
+            Arc::new(MemoryCache::default()),
+        );
+        cache.get("http://e/address/bc1q/utxo").unwrap();
+        cache.get("http://e/address/bc1q/utxo").unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,108 @@
+// Command-line mode invoking a single tool without an MCP client:
+//
+//     bitcoin-data-mcp call get_bitcoin_tx --txid <txid> --network testnet
+//
+// Each `--name value` flag becomes a tool argument. Values that look like
+// integers, booleans, arrays, or objects are passed as JSON, anything else as
+// a string. A flag without a value is passed as `true`.
+use crate::MyServer;
+use rmcp::model::{CallToolRequestParam, RawContent};
+use rmcp::serde_json::{self, Map, Value};
+
+pub fn parse_call_args(args: &[String]) -> Result<CallToolRequestParam, String> {
+    let (tool, flags) = args
+        .split_first()
+        .ok_or("Usage: bitcoin-data-mcp call <tool> [--name value]...")?;
+    let mut arguments = Map::new();
+    let mut flags = flags.iter().peekable();
+    while let Some(flag) = flags.next() {
+        let name = flag
+            .strip_prefix("--")
+            .ok_or_else(|| format!("Expected a --flag, got '{flag}'"))?;
+        let value = match flags.next_if(|value| !value.starts_with("--")) {
+            Some(value) => parse_value(value),
+            None => Value::Bool(true),
+        };
+        arguments.insert(name.to_string(), value);
+    }
+    Ok(CallToolRequestParam {
+        name: tool.clone().into(),
+        arguments: Some(arguments),
+    })
+}
+
+fn parse_value(value: &str) -> Value {
+    match serde_json::from_str::<Value>(value) {
+        // Long hex strings made of digits only would otherwise become lossy floats
+        Ok(Value::Number(n)) if n.is_u64() || n.is_i64() => Value::Number(n),
+        Ok(parsed @ (Value::Bool(_) | Value::Array(_) | Value::Object(_))) => parsed,
+        _ => Value::String(value.to_string()),
+    }
+}
+
+// Run the tool and print its result, returning the process exit code
+pub fn run(server: &MyServer, args: &[String]) -> i32 {
+    let result = parse_call_args(args)
+        .and_then(|params| server.run_tool(params).map_err(|e| e.message.into_owned()));
+    match result {
+        Ok(result) => {
+            for content in &result.content {
+                if let RawContent::Text(text) = &content.raw {
+                    println!("{}", text.text);
+                }
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_call_args() {
+        let params = parse_call_args(&args(&[
+            "get_bitcoin_tx",
+            "--txid",
+            "abcd",
+            "--vout",
+            "1",
+            "--verbose",
+            "--network",
+            "testnet",
+        ]))
+        .unwrap();
+        assert_eq!(params.name, "get_bitcoin_tx");
+        let arguments = Value::Object(params.arguments.unwrap());
+        assert_eq!(
+            arguments,
+            serde_json::json!({
+                "txid": "abcd",
+                "vout": 1,
+                "verbose": true,
+                "network": "testnet",
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_value_keeps_numeric_hashes_as_strings() {
+        let hash = "1".repeat(64);
+        assert_eq!(parse_value(&hash), Value::String(hash));
+    }
+
+    #[test]
+    fn test_parse_call_args_errors() {
+        assert!(parse_call_args(&[]).is_err());
+        assert!(parse_call_args(&args(&["get_bitcoin_tx", "abcd"])).is_err());
+    }
+}
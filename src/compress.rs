@@ -0,0 +1,84 @@
+// Optional compression of large tool results.
+//
+// When COMPRESS_RESULTS_ABOVE_BYTES is set, every text payload larger than the
+// threshold is gzipped and base64-encoded, and replaced by an envelope telling
+// the client how to get the original back. This is about the MCP payload
+// itself, whatever the transport.
+use bitcoin::base64::Engine;
+use bitcoin::base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use rmcp::model::{CallToolResult, RawContent};
+use rmcp::serde_json::json;
+use std::io::Write;
+
+// The `compressed` marker of an envelope, naming the encoding to undo
+const ENCODING: &str = "gzip+base64";
+
+fn gzip_base64(text: &str) -> std::io::Result<String> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes())?;
+    Ok(STANDARD.encode(encoder.finish()?))
+}
+
+// Replace each text payload over `threshold` bytes by a compressed envelope,
+// unless compressing it doesn't make it any shorter
+pub fn compress_result(mut result: CallToolResult, threshold: usize) -> CallToolResult {
+    // Errors are left readable
+    if result.is_error == Some(true) {
+        return result;
+    }
+    for content in result.content.iter_mut() {
+        if let RawContent::Text(text) = &mut content.raw {
+            if text.text.len() <= threshold {
+                continue;
+            }
+            match gzip_base64(&text.text) {
+                Ok(data) if data.len() < text.text.len() => {
+                    text.text = json!({
+                        "compressed": ENCODING,
+                        "original_bytes": text.text.len(),
+                        "compressed_bytes": data.len(),
+                        "data": data,
+                    })
+                    .to_string();
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to compress a tool result: {e}"),
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use rmcp::model::Content;
+    use rmcp::serde_json::{self, Value};
+    use std::io::Read;
+
+    #[test]
+    fn test_compress_result() {
+        let payload = format!("[{}]", vec![r#"{"txid":"aa","vout":0}"#; 100].join(","));
+        let result = CallToolResult::success(vec![Content::text(payload.clone())]);
+        let result = compress_result(result, 1000);
+        let envelope: Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(envelope["compressed"], ENCODING);
+        assert_eq!(envelope["original_bytes"], payload.len());
+        let data = envelope["data"].as_str().unwrap();
+        assert_eq!(envelope["compressed_bytes"], data.len());
+        let mut decompressed = String::new();
+        GzDecoder::new(STANDARD.decode(data).unwrap().as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, payload);
+
+        // Payloads up to the threshold are left alone
+        let result = CallToolResult::success(vec![Content::text(payload.clone())]);
+        let result = compress_result(result, payload.len());
+        assert_eq!(result.content[0].as_text().unwrap().text, payload);
+    }
+}
@@ -0,0 +1,372 @@
+// Runtime configuration, read from the environment at startup
+use crate::render::Locale;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// The chains this server can serve, selectable via the ENABLED_NETWORKS env var
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Chain {
+    Bitcoin,
+    Liquid,
+}
+
+impl Chain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Chain::Bitcoin => "bitcoin",
+            Chain::Liquid => "liquid",
+        }
+    }
+}
+
+// How MCP clients connect to the server, selected via the MCP_TRANSPORT env var
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transport {
+    Stdio,
+    WebSocket,
+}
+
+impl Transport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transport::Stdio => "stdio",
+            Transport::WebSocket => "websocket",
+        }
+    }
+}
+
+pub struct Config {
+    pub enabled_chains: Vec<Chain>,
+    // How long mempool and fee-estimate responses are cached, zero disables it
+    pub volatile_cache_ttl: Duration,
+    // Fetch the chain tip at startup, so that the first call using it is served from the cache
+    pub prefetch_tip: bool,
+    // Directory persisting immutable cache entries across restarts, in memory only if unset
+    pub cache_dir: Option<PathBuf>,
+    // Wrap tool results in an envelope describing the upstream requests
+    pub include_metadata: bool,
+    // Re-serialize JSON tool results with sorted keys
+    pub sort_output_keys: bool,
+    // Text results over this many bytes are gzipped and base64-encoded, zero disables it
+    pub compress_results_above: usize,
+    // Consecutive backend failures before requests fail fast, zero disables it
+    pub breaker_threshold: u32,
+    // How long requests fail fast once the threshold is reached
+    pub breaker_cooldown: Duration,
+    // Upper bound on the size of potentially huge tool results
+    pub max_response_bytes: usize,
+    // Caps on the size of a single request, reported in errors when exceeded
+    pub max_blocks_range: u32,
+    pub max_ancestry_depth: u32,
+    pub max_interval_blocks: u32,
+    pub max_list_limit: usize,
+    pub max_batch_addresses: usize,
+    pub max_history_pages: usize,
+    pub max_block_tx_pages: usize,
+    pub max_spend_lookups: usize,
+    pub max_batch_calls: usize,
+    pub max_fee_stats_blocks: usize,
+    // Overall deadline of a tool call, whatever upstream requests it makes, zero disables it
+    pub tool_timeout: Duration,
+    // Upstream requests allowed in flight at once, zero means unlimited
+    pub max_concurrent_requests: usize,
+    // How long a request waits for a free slot before failing as busy, zero waits forever
+    pub max_queue_wait: Duration,
+    // Redirects followed per upstream request, and whether they may leave the origin
+    pub max_redirects: u32,
+    pub cross_origin_redirects: bool,
+    // Skip TLS certificate verification, for self-signed test instances only
+    pub insecure_tls: bool,
+    // Backend URL lists with more than one entry, primary first
+    pub fallback_backends: Vec<Vec<String>>,
+    // Block explorers linked to by get_bitcoin_explorer_links, as (name, base URL)
+    pub explorers: Vec<(String, String)>,
+    // Fiat values of amounts from an external price API, only with both set
+    pub fiat_prices: bool,
+    pub price_api_url: Option<String>,
+    // How long a fetched price is reused
+    pub price_cache_ttl: Duration,
+    // Tools advertised and accepted, all of them if unset, minus the disabled ones
+    pub tools_enabled: Option<Vec<String>>,
+    pub tools_disabled: Vec<String>,
+    // Number formatting of text results
+    pub locale: Locale,
+    pub transport: Transport,
+    // Address the network transports listen on
+    pub bind_addr: String,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let default = Config::default();
+        let enabled_chains = match std::env::var("ENABLED_NETWORKS") {
+            Ok(value) => value
+                .split(',')
+                .filter_map(|name| match name.trim().to_lowercase().as_str() {
+                    "bitcoin" => Some(Chain::Bitcoin),
+                    "liquid" => Some(Chain::Liquid),
+                    _ => None,
+                })
+                .collect(),
+            Err(_) => default.enabled_chains,
+        };
+        let volatile_cache_ttl = std::env::var("CACHE_VOLATILE_TTL_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(default.volatile_cache_ttl);
+        let prefetch_tip = std::env::var("PREFETCH_TIP")
+            .map(|value| matches!(value.as_str(), "1" | "true"))
+            .unwrap_or(default.prefetch_tip);
+        let cache_dir = std::env::var_os("CACHE_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+        let include_metadata = std::env::var("INCLUDE_METADATA")
+            .map(|value| matches!(value.as_str(), "1" | "true"))
+            .unwrap_or(default.include_metadata);
+        let sort_output_keys = std::env::var("SORT_OUTPUT_KEYS")
+            .map(|value| matches!(value.as_str(), "1" | "true"))
+            .unwrap_or(default.sort_output_keys);
+        let compress_results_above = std::env::var("COMPRESS_RESULTS_ABOVE_BYTES")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.compress_results_above);
+        let tool_timeout = std::env::var("TOOL_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(default.tool_timeout);
+        let breaker_threshold = std::env::var("BREAKER_FAILURE_THRESHOLD")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.breaker_threshold);
+        let breaker_cooldown = std::env::var("BREAKER_COOLDOWN_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(default.breaker_cooldown);
+        let max_response_bytes = std::env::var("MAX_RESPONSE_BYTES")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_response_bytes);
+        let max_blocks_range = std::env::var("MAX_BLOCKS_RANGE")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_blocks_range);
+        let max_ancestry_depth = std::env::var("MAX_ANCESTRY_DEPTH")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_ancestry_depth);
+        let max_interval_blocks = std::env::var("MAX_INTERVAL_BLOCKS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_interval_blocks);
+        let max_list_limit = std::env::var("MAX_LIST_LIMIT")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_list_limit);
+        let max_batch_addresses = std::env::var("MAX_BATCH_ADDRESSES")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_batch_addresses);
+        let max_history_pages = std::env::var("MAX_HISTORY_PAGES")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_history_pages);
+        let max_block_tx_pages = std::env::var("MAX_BLOCK_TX_PAGES")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_block_tx_pages);
+        let max_spend_lookups = std::env::var("MAX_SPEND_LOOKUPS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_spend_lookups);
+        let max_batch_calls = std::env::var("MAX_BATCH_CALLS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_batch_calls);
+        let max_fee_stats_blocks = std::env::var("MAX_FEE_STATS_BLOCKS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_fee_stats_blocks);
+        let max_concurrent_requests = std::env::var("MAX_CONCURRENT_REQUESTS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_concurrent_requests);
+        let max_queue_wait = std::env::var("MAX_QUEUE_WAIT_MS")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(default.max_queue_wait);
+        let max_redirects = std::env::var("MAX_REDIRECTS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.max_redirects);
+        let cross_origin_redirects = std::env::var("ALLOW_CROSS_ORIGIN_REDIRECTS")
+            .map(|value| matches!(value.as_str(), "1" | "true"))
+            .unwrap_or(default.cross_origin_redirects);
+        let insecure_tls = std::env::var("ESPLORA_INSECURE_TLS")
+            .map(|value| matches!(value.as_str(), "1" | "true"))
+            .unwrap_or(default.insecure_tls);
+        // name=url pairs, e.g. "mempool=https://mempool.space,local=http://localhost:8080"
+        let explorers = match std::env::var("EXPLORER_URLS") {
+            Ok(value) => value
+                .split(',')
+                .filter_map(|entry| {
+                    let (name, url) = entry.split_once('=')?;
+                    let (name, url) = (name.trim(), url.trim().trim_end_matches('/'));
+                    (!name.is_empty() && !url.is_empty())
+                        .then(|| (name.to_string(), url.to_string()))
+                })
+                .collect(),
+            Err(_) => default.explorers,
+        };
+        let fiat_prices = std::env::var("FIAT_PRICES")
+            .map(|value| matches!(value.as_str(), "1" | "true"))
+            .unwrap_or(default.fiat_prices);
+        let price_api_url = std::env::var("PRICE_API_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+        let price_cache_ttl = std::env::var("PRICE_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(default.price_cache_ttl);
+        let tools_enabled = std::env::var("TOOLS_ENABLED")
+            .ok()
+            .map(|value| tool_names(&value));
+        let tools_disabled = std::env::var("TOOLS_DISABLED")
+            .map(|value| tool_names(&value))
+            .unwrap_or(default.tools_disabled);
+        let transport = match std::env::var("MCP_TRANSPORT").as_deref() {
+            Ok("websocket") | Ok("ws") => Transport::WebSocket,
+            Ok("stdio") => Transport::Stdio,
+            _ => default.transport,
+        };
+        let locale = std::env::var("LOCALE")
+            .ok()
+            .and_then(|name| Locale::parse(&name))
+            .unwrap_or(default.locale);
+        let bind_addr = std::env::var("MCP_BIND_ADDR").unwrap_or(default.bind_addr);
+        Config {
+            enabled_chains,
+            volatile_cache_ttl,
+            prefetch_tip,
+            cache_dir,
+            include_metadata,
+            sort_output_keys,
+            compress_results_above,
+            breaker_threshold,
+            breaker_cooldown,
+            tool_timeout,
+            max_response_bytes,
+            max_blocks_range,
+            max_ancestry_depth,
+            max_interval_blocks,
+            max_list_limit,
+            max_batch_addresses,
+            max_history_pages,
+            max_block_tx_pages,
+            max_spend_lookups,
+            max_batch_calls,
+            max_fee_stats_blocks,
+            max_concurrent_requests,
+            max_queue_wait,
+            max_redirects,
+            cross_origin_redirects,
+            insecure_tls,
+            fallback_backends: crate::esplora::fallback_backends_from_env(),
+            explorers,
+            fiat_prices,
+            price_api_url,
+            price_cache_ttl,
+            tools_enabled,
+            tools_disabled,
+            locale,
+            transport,
+            bind_addr,
+        }
+    }
+
+    pub fn is_enabled(&self, chain: Chain) -> bool {
+        self.enabled_chains.contains(&chain)
+    }
+
+    // The price API, when fiat prices are enabled
+    pub fn price_source(&self) -> Option<&str> {
+        self.price_api_url.as_deref().filter(|_| self.fiat_prices)
+    }
+
+    // Whether TOOLS_ENABLED and TOOLS_DISABLED let clients see and call a tool.
+    // get_btc_price needs fiat prices on top.
+    pub fn is_tool_enabled(&self, name: &str) -> bool {
+        if name == "get_btc_price" && self.price_source().is_none() {
+            return false;
+        }
+        let allowed = self
+            .tools_enabled
+            .as_ref()
+            .is_none_or(|enabled| enabled.iter().any(|tool| tool == name));
+        allowed && !self.tools_disabled.iter().any(|tool| tool == name)
+    }
+}
+
+// Comma-separated tool names, e.g. "broadcast_bitcoin_tx,get_bitcoin_blocks_range"
+fn tool_names(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled_chains: vec![Chain::Bitcoin, Chain::Liquid],
+            volatile_cache_ttl: Duration::from_secs(10),
+            prefetch_tip: false,
+            cache_dir: None,
+            include_metadata: false,
+            sort_output_keys: false,
+            compress_results_above: 0,
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+            tool_timeout: Duration::from_secs(30),
+            max_response_bytes: 1_000_000,
+            max_blocks_range: 50,
+            max_ancestry_depth: 20,
+            max_interval_blocks: 100,
+            max_list_limit: 1000,
+            max_batch_addresses: 20,
+            max_history_pages: 40,
+            max_block_tx_pages: 200,
+            max_spend_lookups: 25,
+            max_batch_calls: 10,
+            max_fee_stats_blocks: 10,
+            max_concurrent_requests: 8,
+            max_queue_wait: Duration::from_secs(10),
+            max_redirects: 3,
+            cross_origin_redirects: false,
+            insecure_tls: false,
+            fallback_backends: Vec::new(),
+            explorers: vec![
+                (
+                    "blockstream".to_string(),
+                    "https://blockstream.info".to_string(),
+                ),
+                ("mempool".to_string(), "https://mempool.space".to_string()),
+            ],
+            fiat_prices: false,
+            price_api_url: None,
+            price_cache_ttl: Duration::from_secs(60),
+            tools_enabled: None,
+            tools_disabled: Vec::new(),
+            locale: Locale::Plain,
+            transport: Transport::Stdio,
+            bind_addr: "127.0.0.1:8765".to_string(),
+        }
+    }
+}
@@ -0,0 +1,490 @@
+// Access to the Esplora HTTP API: supported networks, the HTTP client, and
+// the basic fetch helpers shared by the tools
+use rmcp::schemars;
+use rmcp::serde_json::{self, Value};
+use serde::{Deserialize, Serialize};
+
+// Esplora API base URLs for all supported networks
+const BITCOIN_MAINNET_API: &str = "https://blockstream.info/api";
+const BITCOIN_TESTNET_API: &str = "https://blockstream.info/testnet/api";
+const BITCOIN_SIGNET_API: &str = "https://blockstream.info/signet/api";
+const LIQUID_MAINNET_API: &str = "https://blockstream.info/liquid/api";
+const LIQUID_TESTNET_API: &str = "https://blockstream.info/liquidtestnet/api";
+
+// Esplora returns block transactions in pages of this size
+pub const BLOCK_TXS_PAGE_SIZE: usize = 25;
+
+// Esplora returns the confirmed history of an address in pages of this size
+pub const ADDRESS_CHAIN_TXS_PAGE_SIZE: usize = 25;
+
+// Env vars overriding the base URL of each network
+const API_BASE_VARS: [&str; 5] = [
+    "ESPLORA_BITCOIN_MAINNET_URL",
+    "ESPLORA_BITCOIN_TESTNET_URL",
+    "ESPLORA_BITCOIN_SIGNET_URL",
+    "ESPLORA_LIQUID_MAINNET_URL",
+    "ESPLORA_LIQUID_TESTNET_URL",
+];
+
+// A comma-separated list of backend URLs, primary first
+fn backends_from_env(var: &str) -> Option<Vec<String>> {
+    let value = std::env::var(var).ok()?;
+    let urls: Vec<String> = value
+        .split(',')
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    (!urls.is_empty()).then_some(urls)
+}
+
+// Base URLs can be overridden via environment variables, e.g. to point at a
+// self-hosted Esplora instance or a local mock server in tests. Requests are
+// built against the first URL of a list, the others are fallbacks.
+fn api_base_from_env(var: &str, default: &str) -> String {
+    backends_from_env(var)
+        .and_then(|urls| urls.into_iter().next())
+        .unwrap_or_else(|| default.to_string())
+}
+
+// The backend lists configured with at least one fallback
+pub fn fallback_backends_from_env() -> Vec<Vec<String>> {
+    API_BASE_VARS
+        .iter()
+        .filter_map(|var| backends_from_env(var))
+        .filter(|urls| urls.len() > 1)
+        .collect()
+}
+
+// Network types for Bitcoin (mainnet, testnet, signet)
+#[derive(Deserialize, Serialize, schemars::JsonSchema, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum BitcoinNetwork {
+    #[default]
+    Mainnet,
+    Testnet,
+    Signet,
+}
+
+impl BitcoinNetwork {
+    pub fn api_base(&self) -> String {
+        match self {
+            BitcoinNetwork::Mainnet => {
+                api_base_from_env("ESPLORA_BITCOIN_MAINNET_URL", BITCOIN_MAINNET_API)
+            }
+            BitcoinNetwork::Testnet => {
+                api_base_from_env("ESPLORA_BITCOIN_TESTNET_URL", BITCOIN_TESTNET_API)
+            }
+            BitcoinNetwork::Signet => {
+                api_base_from_env("ESPLORA_BITCOIN_SIGNET_URL", BITCOIN_SIGNET_API)
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BitcoinNetwork::Mainnet => "mainnet",
+            BitcoinNetwork::Testnet => "testnet",
+            BitcoinNetwork::Signet => "signet",
+        }
+    }
+
+    pub fn to_bitcoin_network(self) -> bitcoin::Network {
+        match self {
+            BitcoinNetwork::Mainnet => bitcoin::Network::Bitcoin,
+            BitcoinNetwork::Testnet => bitcoin::Network::Testnet,
+            BitcoinNetwork::Signet => bitcoin::Network::Signet,
+        }
+    }
+}
+
+// Network types for Liquid (mainnet, testnet)
+#[derive(Deserialize, Serialize, schemars::JsonSchema, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum LiquidNetwork {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl LiquidNetwork {
+    pub fn api_base(&self) -> String {
+        match self {
+            LiquidNetwork::Mainnet => {
+                api_base_from_env("ESPLORA_LIQUID_MAINNET_URL", LIQUID_MAINNET_API)
+            }
+            LiquidNetwork::Testnet => {
+                api_base_from_env("ESPLORA_LIQUID_TESTNET_URL", LIQUID_TESTNET_API)
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiquidNetwork::Mainnet => "mainnet",
+            LiquidNetwork::Testnet => "testnet",
+        }
+    }
+}
+
+// The HTTP backend used to reach Esplora
+pub trait EsploraClient: Send + Sync {
+    fn get(&self, url: &str) -> Result<String, String>;
+
+    // Binary endpoints such as /tx/:txid/raw, which aren't valid UTF-8
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        self.get(url).map(String::into_bytes)
+    }
+
+    // Submissions such as POST /tx, never cached. Error messages carry the
+    // response body, which holds the reason the backend refused the request.
+    fn post(&self, url: &str, _body: &str) -> Result<String, String> {
+        Err(format!(
+            "Submitting to {url} is not supported by this client"
+        ))
+    }
+}
+
+// Same limit ureq enforces when reading a response into a string; like
+// ureq, bodies past it are an error rather than truncated
+const MAX_BINARY_RESPONSE_BYTES: u64 = 10 * 1024 * 1024;
+
+// Reads one byte past `limit` so an oversized body is told apart from one
+// of exactly `limit` bytes
+fn read_limited(reader: impl std::io::Read, limit: u64) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    let mut bytes = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read response: {e}"))?;
+    if bytes.len() as u64 > limit {
+        return Err(format!("Response is larger than {limit} bytes"));
+    }
+    Ok(bytes)
+}
+
+// Default client performing real HTTP requests with ureq
+pub struct UreqClient {
+    agent: ureq::Agent,
+    max_redirects: u32,
+    cross_origin_redirects: bool,
+}
+
+impl UreqClient {
+    // Requests go through the proxy named by ALL_PROXY, HTTPS_PROXY, or
+    // HTTP_PROXY (checked in this order) when one is set. socks5:// proxies
+    // resolve hostnames remotely, so .onion backends work through Tor.
+    pub fn new(max_redirects: u32, cross_origin_redirects: bool, insecure_tls: bool) -> Self {
+        // Redirects are followed by `call` instead, to check where they lead
+        let mut builder = ureq::AgentBuilder::new()
+            .try_proxy_from_env(true)
+            .redirects(0);
+        if insecure_tls {
+            builder = builder.tls_config(crate::tls::insecure_config());
+        }
+        let agent = builder.build();
+        UreqClient {
+            agent,
+            max_redirects,
+            cross_origin_redirects,
+        }
+    }
+
+    // Send a GET request, following up to `max_redirects` redirects. Unless
+    // enabled, redirects may only lead to the same origin or upgrade it to https.
+    fn call(&self, url: &str) -> Result<ureq::Response, String> {
+        let mut current = url::Url::parse(url).map_err(|e| format!("Invalid URL {url}: {e}"))?;
+        let mut redirects = 0;
+        loop {
+            let response = self
+                .agent
+                .request_url("GET", &current)
+                .call()
+                .map_err(|e| {
+                    tracing::debug!(url = %current, error = %e, "upstream request failed");
+                    match e {
+                        ureq::Error::Status(status, response) => {
+                            status_error(current.as_str(), status, &response)
+                        }
+                        e => format!("HTTP request failed: {e}"),
+                    }
+                })?;
+            tracing::debug!(url = %current, status = response.status(), "upstream request");
+            if !(300..400).contains(&response.status()) {
+                return Ok(response);
+            }
+            if redirects == self.max_redirects {
+                return Err(format!(
+                    "HTTP request failed: {url}: more than {} redirects",
+                    self.max_redirects
+                ));
+            }
+            let next = response
+                .header("location")
+                .and_then(|location| current.join(location).ok())
+                .ok_or_else(|| {
+                    format!("HTTP request failed: {current}: redirect without a valid Location")
+                })?;
+            let same_host = next.host_str() == current.host_str();
+            let upgrade = same_host && current.scheme() == "http" && next.scheme() == "https";
+            if next.origin() != current.origin() && !upgrade && !self.cross_origin_redirects {
+                return Err(format!(
+                    "HTTP request failed: {current} redirects to another origin ({next}), set ALLOW_CROSS_ORIGIN_REDIRECTS=1 to follow it"
+                ));
+            }
+            current = next;
+            redirects += 1;
+        }
+    }
+}
+
+// "HTTP request failed: <url>: status code <code>", as ureq puts it, plus the
+// delay the backend asks clients to wait when it sets Retry-After, e.g. on 429
+fn status_error(url: &str, status: u16, response: &ureq::Response) -> String {
+    let mut err = format!("HTTP request failed: {url}: status code {status}");
+    if let Some(retry_after) = response.header("retry-after") {
+        let retry_after = retry_after.trim();
+        if retry_after.bytes().all(|b| b.is_ascii_digit()) {
+            err += &format!(", retry after {retry_after}s");
+        } else {
+            err += &format!(", retry after {retry_after}");
+        }
+    }
+    err
+}
+
+// A misconfigured backend or a captive portal answers with an HTML page,
+// report it instead of failing later on a confusing parse error
+fn non_json_error(url: &str, status: u16) -> String {
+    format!(
+        "Unexpected non-JSON response from backend: {url}: status code {status}, check the configured Esplora URL"
+    )
+}
+
+impl EsploraClient for UreqClient {
+    // Redirects aren't followed, a backend redirecting submissions is misconfigured
+    fn post(&self, url: &str, body: &str) -> Result<String, String> {
+        let response = match self
+            .agent
+            .post(url)
+            .set("Content-Type", "text/plain")
+            .send_string(body)
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                tracing::debug!(%url, status, "upstream submission rejected");
+                let err = status_error(url, status, &response);
+                let reason = response.into_string().unwrap_or_default();
+                return Err(format!("{err}: {}", reason.trim()));
+            }
+            Err(e) => return Err(format!("HTTP request failed: {e}")),
+        };
+        let status = response.status();
+        tracing::debug!(%url, status, "upstream submission");
+        if (300..400).contains(&status) {
+            return Err(format!(
+                "HTTP request failed: {url}: status code {status}: redirects are not followed for submissions"
+            ));
+        }
+        response
+            .into_string()
+            .map_err(|e| format!("Failed to read response: {e}"))
+    }
+
+    fn get(&self, url: &str) -> Result<String, String> {
+        let response = self.call(url)?;
+        let status = response.status();
+        let is_html = response.content_type() == "text/html";
+        let body = response
+            .into_string()
+            .map_err(|e| format!("Failed to read response: {e}"))?;
+        if is_html || body.trim_start().starts_with('<') {
+            return Err(non_json_error(url, status));
+        }
+        Ok(body)
+    }
+
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        let response = self.call(url)?;
+        if response.content_type() == "text/html" {
+            return Err(non_json_error(url, response.status()));
+        }
+        read_limited(response.into_reader(), MAX_BINARY_RESPONSE_BYTES)
+            .map_err(|e| format!("{url}: {e}"))
+    }
+}
+
+// Fetch data from Esplora API
+pub fn fetch_esplora(client: &dyn EsploraClient, url: &str) -> Result<String, String> {
+    client.get(url)
+}
+
+// Fetch binary data from Esplora API
+pub fn fetch_esplora_bytes(client: &dyn EsploraClient, url: &str) -> Result<Vec<u8>, String> {
+    client.get_bytes(url)
+}
+
+// Fetch data from Esplora API and parse it as JSON
+pub fn fetch_json(client: &dyn EsploraClient, url: &str) -> Result<Value, String> {
+    let body = fetch_esplora(client, url)?;
+    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+}
+
+// ureq reports HTTP errors as "<url>: status code <code>"
+pub fn is_not_found(err: &str) -> bool {
+    err.contains("status code 404")
+}
+
+// Connection errors and 5xx responses mean the backend itself is in trouble,
+// unlike client errors such as 404 or the server's own overload
+pub fn is_backend_failure(err: &str) -> bool {
+    !crate::limit::is_server_busy(err) && status_from_error(err).is_none_or(|status| status >= 500)
+}
+
+// The HTTP status of a failed request, None for transport errors
+pub fn status_from_error(err: &str) -> Option<u16> {
+    let (_, code) = err.rsplit_once("status code ")?;
+    code.split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+// Seconds to wait before retrying, when the backend said so with Retry-After
+pub fn retry_after_from_error(err: &str) -> Option<u64> {
+    let (_, delay) = err.split_once(", retry after ")?;
+    delay
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+pub fn fetch_transaction(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    fetch_esplora(client, &format!("{base_url}/tx/{txid}"))
+}
+
+pub fn fetch_block(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+) -> Result<String, String> {
+    fetch_esplora(client, &format!("{base_url}/block/{hash}"))
+}
+
+pub fn fetch_tip_height(client: &dyn EsploraClient, base_url: &str) -> Result<String, String> {
+    fetch_esplora(client, &format!("{base_url}/blocks/tip/height"))
+}
+
+pub fn fetch_mempool(client: &dyn EsploraClient, base_url: &str) -> Result<String, String> {
+    fetch_esplora(client, &format!("{base_url}/mempool"))
+}
+
+#[cfg(test)]
+pub mod testing {
+    use super::EsploraClient;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    // Client returning canned responses and recording every requested URL
+    #[derive(Default)]
+    pub struct MockClient {
+        responses: HashMap<String, String>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl MockClient {
+        pub fn with(mut self, url: &str, body: &str) -> Self {
+            self.responses.insert(url.to_string(), body.to_string());
+            self
+        }
+
+        pub fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl EsploraClient for MockClient {
+        fn get(&self, url: &str) -> Result<String, String> {
+            self.requests.lock().unwrap().push(url.to_string());
+            self.responses
+                .get(url)
+                .cloned()
+                .ok_or_else(|| format!("HTTP request failed: {url}: status code 404"))
+        }
+
+        // Submissions are answered from the same canned responses
+        fn post(&self, url: &str, _body: &str) -> Result<String, String> {
+            self.get(url)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::MockClient;
+    use super::*;
+
+    #[test]
+    fn test_fetch_transaction_url() {
+        let client = MockClient::default().with("http://esplora/tx/abcd", "{}");
+        let result = fetch_transaction(&client, "http://esplora", "abcd");
+        assert_eq!(result.unwrap(), "{}");
+        assert_eq!(client.requests(), vec!["http://esplora/tx/abcd"]);
+    }
+
+    #[test]
+    fn test_fetch_block_url() {
+        let client = MockClient::default().with("http://esplora/block/00ff", "{}");
+        let result = fetch_block(&client, "http://esplora", "00ff");
+        assert_eq!(result.unwrap(), "{}");
+        assert_eq!(client.requests(), vec!["http://esplora/block/00ff"]);
+    }
+
+    #[test]
+    fn test_read_limited() {
+        assert_eq!(read_limited(&[1u8, 2, 3][..], 3).unwrap(), vec![1, 2, 3]);
+        let err = read_limited(&[1u8, 2, 3, 4][..], 3).unwrap_err();
+        assert!(
+            err.contains("larger than 3 bytes"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_fetch_error_is_propagated() {
+        let client = MockClient::default();
+        let err = fetch_transaction(&client, "http://esplora", "abcd").unwrap_err();
+        assert!(err.contains("404"), "unexpected error: {err}");
+        assert!(is_not_found(&err));
+    }
+
+    #[test]
+    fn test_status_from_error() {
+        assert_eq!(status_from_error("http://e/tx: status code 429"), Some(429));
+        assert_eq!(status_from_error("HTTP request failed: timeout"), None);
+        assert_eq!(
+            status_from_error("http://e/tx: status code 503, retry after 30s"),
+            Some(503)
+        );
+    }
+
+    #[test]
+    fn test_retry_after_from_error() {
+        assert_eq!(
+            retry_after_from_error("http://e/tx: status code 429, retry after 30s"),
+            Some(30)
+        );
+        assert_eq!(
+            retry_after_from_error(
+                "http://e/tx: status code 503, retry after Wed, 21 Oct 2026 07:28:00 GMT"
+            ),
+            None
+        );
+        assert_eq!(retry_after_from_error("http://e/tx: status code 429"), None);
+    }
+}
@@ -0,0 +1,137 @@
+// Failover to secondary backends.
+//
+// A network's base URL may be a comma-separated list: requests are built
+// against the first one, and when it fails with a connection error, a 5xx, or
+// a 429 (rate limiting) the same path is tried on the next backend of the list.
+use crate::esplora::{EsploraClient, is_backend_failure, status_from_error};
+use std::sync::Arc;
+
+pub struct FallbackClient {
+    inner: Arc<dyn EsploraClient>,
+    // Each list starts with the primary backend
+    backends: Vec<Vec<String>>,
+}
+
+fn should_fall_back(err: &str) -> bool {
+    is_backend_failure(err) || status_from_error(err) == Some(429)
+}
+
+impl FallbackClient {
+    pub fn new(inner: Arc<dyn EsploraClient>, backends: Vec<Vec<String>>) -> Self {
+        FallbackClient { inner, backends }
+    }
+
+    // The URL on each fallback backend, if `url` targets a primary backend
+    fn alternates(&self, url: &str) -> Vec<String> {
+        for urls in &self.backends {
+            let Some(path) = url.strip_prefix(urls[0].as_str()) else {
+                continue;
+            };
+            if path.is_empty() || path.starts_with('/') || path.starts_with('?') {
+                return urls[1..]
+                    .iter()
+                    .map(|base| format!("{base}{path}"))
+                    .collect();
+            }
+        }
+        Vec::new()
+    }
+
+    fn with_fallback<T>(
+        &self,
+        url: &str,
+        request: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut errors = Vec::new();
+        for url in std::iter::once(url.to_string()).chain(self.alternates(url)) {
+            match request(&url) {
+                Err(e) if should_fall_back(&e) => {
+                    tracing::debug!(%url, error = %e, "backend failed, trying the next one");
+                    errors.push(e);
+                }
+                result => return result,
+            }
+        }
+        // The last error comes last so that its status is the one reported
+        Err(errors.join("; "))
+    }
+}
+
+impl EsploraClient for FallbackClient {
+    fn get(&self, url: &str) -> Result<String, String> {
+        self.with_fallback(url, |url| self.inner.get(url))
+    }
+
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        self.with_fallback(url, |url| self.inner.get_bytes(url))
+    }
+
+    fn post(&self, url: &str, body: &str) -> Result<String, String> {
+        self.with_fallback(url, |url| self.inner.post(url, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esplora::testing::MockClient;
+
+    fn backends() -> Vec<Vec<String>> {
+        vec![vec![
+            "http://primary/api".to_string(),
+            "http://down/api".to_string(),
+            "http://secondary/api".to_string(),
+        ]]
+    }
+
+    #[test]
+    fn test_alternates() {
+        let client = FallbackClient::new(Arc::new(MockClient::default()), backends());
+        assert_eq!(
+            client.alternates("http://primary/api/tx/ab"),
+            ["http://down/api/tx/ab", "http://secondary/api/tx/ab"]
+        );
+        assert!(client.alternates("http://primary/api2/tx/ab").is_empty());
+        assert!(client.alternates("http://other/api/tx/ab").is_empty());
+    }
+
+    // Client failing for the primary and down backends
+    struct PartialOutageClient(MockClient);
+
+    impl EsploraClient for PartialOutageClient {
+        fn get(&self, url: &str) -> Result<String, String> {
+            let result = self.0.get(url);
+            if url.starts_with("http://primary") {
+                Err(format!("HTTP request failed: {url}: status code 503"))
+            } else if url.starts_with("http://down") {
+                Err("HTTP request failed: Connection refused".to_string())
+            } else {
+                result
+            }
+        }
+    }
+
+    #[test]
+    fn test_falls_back_in_order() {
+        let inner = Arc::new(PartialOutageClient(
+            MockClient::default().with("http://secondary/api/blocks/tip/height", "840000"),
+        ));
+        let client = FallbackClient::new(inner.clone(), backends());
+        assert_eq!(
+            client.get("http://primary/api/blocks/tip/height").unwrap(),
+            "840000"
+        );
+        assert_eq!(
+            inner.0.requests(),
+            [
+                "http://primary/api/blocks/tip/height",
+                "http://down/api/blocks/tip/height",
+                "http://secondary/api/blocks/tip/height",
+            ]
+        );
+
+        // A 404 from the last backend is reported as is
+        let err = client.get("http://primary/api/tx/ab").unwrap_err();
+        assert!(err.ends_with("status code 404"), "unexpected error: {err}");
+    }
+}
@@ -0,0 +1,216 @@
+// Opt-in fiat values of Bitcoin amounts.
+//
+// When FIAT_PRICES is set and PRICE_API_URL points at a price API, tools
+// returning amounts also accept a `fiat` argument naming a currency, taken out
+// of the arguments before the tool sees them: every satoshi amount of the
+// result then gets a sibling in that currency, e.g. value_usd_approx, and
+// object results a `fiat` field with the price used. The price comes from
+// outside Esplora and is cached for a short while, so these figures are
+// approximate.
+use crate::MyServer;
+use crate::esplora::fetch_json;
+use crate::tools::add_amount_siblings;
+use rmcp::model::{CallToolResult, ErrorData, JsonObject, RawContent};
+use rmcp::serde_json::{self, Value, json};
+use std::sync::Arc;
+use std::time::Instant;
+
+const FIAT_ARG: &str = "fiat";
+
+const FIAT_DESCRIPTION: &str = "Also report every satoshi amount in this fiat currency, e.g. 'USD', in a sibling field with a _<currency>_approx suffix (e.g. value_usd_approx), converted at the current BTC price of the server's price API. The conversion is approximate.";
+
+const DISABLED: &str =
+    "Fiat prices are disabled on this server, they need FIAT_PRICES and PRICE_API_URL";
+
+// Advertise `fiat` in the input schema of the tools returning amounts, the
+// ones taking `amounts_in_btc`
+pub fn with_fiat_option(mut schema: Arc<JsonObject>) -> Arc<JsonObject> {
+    let object = Arc::make_mut(&mut schema);
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        if properties.contains_key("amounts_in_btc") {
+            properties.insert(
+                FIAT_ARG.to_string(),
+                json!({ "type": "string", "description": FIAT_DESCRIPTION }),
+            );
+        }
+    }
+    schema
+}
+
+// A 3-letter currency code, uppercased as price APIs key them
+pub fn parse_currency(code: &str) -> Result<String, ErrorData> {
+    if code.len() == 3 && code.bytes().all(|b| b.is_ascii_alphabetic()) {
+        Ok(code.to_ascii_uppercase())
+    } else {
+        Err(ErrorData::invalid_request(
+            format!("Invalid currency {code:?}, expected a 3-letter code such as 'USD'"),
+            None,
+        ))
+    }
+}
+
+// Remove `fiat` from the arguments of a call, failing when it is given but
+// fiat prices aren't enabled
+pub fn take_fiat_currency(
+    arguments: &mut JsonObject,
+    enabled: bool,
+) -> Result<Option<String>, ErrorData> {
+    let currency = match arguments.remove(FIAT_ARG) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(code)) => parse_currency(&code)?,
+        Some(_) => {
+            return Err(ErrorData::invalid_request(
+                "fiat must be a currency code such as 'USD'",
+                None,
+            ));
+        }
+    };
+    if !enabled {
+        return Err(ErrorData::invalid_request(DISABLED, None));
+    }
+    Ok(Some(currency))
+}
+
+// The response of the price API, an object of BTC prices keyed by currency
+// code such as mempool.space's /api/v1/prices, cached for the configured TTL
+pub fn fetch_prices(server: &MyServer) -> Result<Value, String> {
+    let url = server.config.price_source().ok_or(DISABLED)?;
+    let key = format!("btc_price:{url}");
+    if let Some(cached) = server
+        .cache
+        .lookup(&key)
+        .and_then(|body| serde_json::from_str(&body).ok())
+    {
+        return Ok(cached);
+    }
+    let prices = fetch_json(server.client.as_ref(), url)?;
+    if !prices.is_object() {
+        return Err("Price API response is not a JSON object".to_string());
+    }
+    let ttl = server.config.price_cache_ttl;
+    if !ttl.is_zero() {
+        server
+            .cache
+            .store(&key, &prices.to_string(), Some(Instant::now() + ttl));
+    }
+    Ok(prices)
+}
+
+// The price of one BTC in `currency`
+pub fn btc_price(prices: &Value, currency: &str) -> Result<f64, String> {
+    prices[currency]
+        .as_f64()
+        .filter(|price| price.is_finite() && *price > 0.0)
+        .ok_or_else(|| {
+            let available: Vec<&str> = prices
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(code, price)| {
+                    price.is_number()
+                        && code.len() == 3
+                        && code.bytes().all(|b| b.is_ascii_uppercase())
+                })
+                .map(|(code, _)| code.as_str())
+                .collect();
+            format!(
+                "The price API has no BTC price in {currency}, it has: {}",
+                available.join(", ")
+            )
+        })
+}
+
+// Add the `currency` value of every satoshi amount of a result
+pub fn with_fiat_amounts(
+    server: &MyServer,
+    mut result: CallToolResult,
+    currency: &str,
+) -> Result<CallToolResult, ErrorData> {
+    if result.is_error == Some(true) {
+        return Ok(result);
+    }
+    let prices = fetch_prices(server).map_err(|e| ErrorData::internal_error(e, None))?;
+    let price = btc_price(&prices, currency).map_err(|e| ErrorData::invalid_request(e, None))?;
+    let unit = format!("{}_approx", currency.to_lowercase());
+    let convert = |sat: i64| json!((sat as f64 * price / 1_000_000.0).round() / 100.0);
+    for content in result.content.iter_mut() {
+        if let RawContent::Text(text) = &mut content.raw {
+            if let Ok(mut value @ (Value::Object(_) | Value::Array(_))) =
+                serde_json::from_str::<Value>(&text.text)
+            {
+                add_amount_siblings(&mut value, &unit, &convert);
+                if let Value::Object(map) = &mut value {
+                    map.insert(
+                        "fiat".to_string(),
+                        json!({ "currency": currency, "btc_price": price, "approximate": true }),
+                    );
+                }
+                text.text = value.to_string();
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::esplora::testing::MockClient;
+    use crate::{cache, stats};
+    use rmcp::model::Content;
+
+    #[test]
+    fn test_with_fiat_amounts() {
+        let client = Arc::new(MockClient::default().with(
+            "http://prices/v1/prices",
+            r#"{"time":1700000000,"USD":50000,"EUR":45000}"#,
+        ));
+        let server = MyServer {
+            client: client.clone(),
+            config: Arc::new(Config {
+                fiat_prices: true,
+                price_api_url: Some("http://prices/v1/prices".to_string()),
+                ..Config::default()
+            }),
+            stats: Arc::new(stats::ServerStats::new()),
+            cache: Arc::new(cache::MemoryCache::default()),
+        };
+        let result = || {
+            CallToolResult::success(vec![Content::text(
+                json!({ "fee": 1234, "vout": [{ "value": 100_000_000 }] }).to_string(),
+            )])
+        };
+        let converted = with_fiat_amounts(&server, result(), "USD").unwrap();
+        let value: Value =
+            serde_json::from_str(&converted.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(value["fee_usd_approx"], 0.62);
+        assert_eq!(value["vout"][0]["value_usd_approx"], 50000.0);
+        assert_eq!(value["fiat"]["approximate"], true);
+
+        // The price is cached
+        with_fiat_amounts(&server, result(), "EUR").unwrap();
+        assert_eq!(client.requests().len(), 1);
+
+        let err = with_fiat_amounts(&server, result(), "JPY").unwrap_err();
+        assert!(err.message.contains("EUR, USD"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_take_fiat_currency() {
+        let mut args = json!({ "fiat": "usd", "txid": "aa" })
+            .as_object()
+            .unwrap()
+            .clone();
+        assert_eq!(
+            take_fiat_currency(&mut args, true).unwrap().as_deref(),
+            Some("USD")
+        );
+        assert!(!args.contains_key(FIAT_ARG));
+        assert_eq!(take_fiat_currency(&mut args, false).unwrap(), None);
+        args.insert(FIAT_ARG.to_string(), json!("usd"));
+        assert!(take_fiat_currency(&mut args, false).is_err());
+        args.insert(FIAT_ARG.to_string(), json!("dollars"));
+        assert!(take_fiat_currency(&mut args, true).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// Bound on the number of upstream requests in flight at once, shared by all
+// tool calls so that a large batch can't open dozens of sockets. Callers over
+// the limit wait for a slot to free up, for at most `max_wait`: past it the
+// request fails with a "server busy" error instead of queueing unbounded.
+use crate::esplora::EsploraClient;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+const SERVER_BUSY: &str = "Server busy";
+
+pub struct ConcurrencyLimitClient {
+    inner: Arc<dyn EsploraClient>,
+    limit: usize,
+    // Zero waits as long as it takes
+    max_wait: Duration,
+    in_flight: Mutex<usize>,
+    slot_freed: Condvar,
+}
+
+// Overload is no sign of a backend failure, it shouldn't trip the circuit
+// breaker or fail over to another backend
+pub fn is_server_busy(err: &str) -> bool {
+    err.starts_with(SERVER_BUSY)
+}
+
+impl ConcurrencyLimitClient {
+    pub fn new(inner: Arc<dyn EsploraClient>, limit: usize, max_wait: Duration) -> Self {
+        ConcurrencyLimitClient {
+            inner,
+            limit,
+            max_wait,
+            in_flight: Mutex::new(0),
+            slot_freed: Condvar::new(),
+        }
+    }
+
+    fn limited<T>(&self, request: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+        if self.limit == 0 {
+            return request();
+        }
+        {
+            let in_flight = self.in_flight.lock().unwrap();
+            let is_full = |n: &mut usize| *n >= self.limit;
+            let mut in_flight = if self.max_wait.is_zero() {
+                self.slot_freed.wait_while(in_flight, is_full).unwrap()
+            } else {
+                let (in_flight, timeout) = self
+                    .slot_freed
+                    .wait_timeout_while(in_flight, self.max_wait, is_full)
+                    .unwrap();
+                if timeout.timed_out() {
+                    return Err(format!(
+                        "{SERVER_BUSY}: all {} upstream request slots stayed in use for {} ms, retry shortly",
+                        self.limit,
+                        self.max_wait.as_millis()
+                    ));
+                }
+                in_flight
+            };
+            *in_flight += 1;
+        }
+        let result = request();
+        *self.in_flight.lock().unwrap() -= 1;
+        self.slot_freed.notify_one();
+        result
+    }
+}
+
+impl EsploraClient for ConcurrencyLimitClient {
+    fn get(&self, url: &str) -> Result<String, String> {
+        self.limited(|| self.inner.get(url))
+    }
+
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        self.limited(|| self.inner.get_bytes(url))
+    }
+
+    fn post(&self, url: &str, body: &str) -> Result<String, String> {
+        self.limited(|| self.inner.post(url, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    // Client recording the highest number of requests it served at once
+    #[derive(Default)]
+    struct SlowClient {
+        current: AtomicUsize,
+        max_seen: AtomicUsize,
+    }
+
+    impl EsploraClient for SlowClient {
+        fn get(&self, _url: &str) -> Result<String, String> {
+            let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_seen.fetch_max(current, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(10));
+            self.current.fetch_sub(1, Ordering::SeqCst);
+            Ok("ok".to_string())
+        }
+    }
+
+    #[test]
+    fn test_concurrency_never_exceeds_limit() {
+        let inner = Arc::new(SlowClient::default());
+        let client = ConcurrencyLimitClient::new(inner.clone(), 3, Duration::ZERO);
+        std::thread::scope(|scope| {
+            for _ in 0..12 {
+                scope.spawn(|| assert_eq!(client.get("http://e/a").unwrap(), "ok"));
+            }
+        });
+        assert_eq!(inner.max_seen.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_busy_after_max_wait() {
+        let inner = Arc::new(SlowClient::default());
+        let client = ConcurrencyLimitClient::new(inner.clone(), 1, Duration::from_millis(20));
+        // Every slot is taken by a request that never completes
+        *client.in_flight.lock().unwrap() = 1;
+        let err = client.get("http://e/a").unwrap_err();
+        assert!(is_server_busy(&err), "unexpected error: {err}");
+        assert_eq!(inner.max_seen.load(Ordering::SeqCst), 0);
+    }
+}
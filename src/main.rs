@@ -1,183 +1,337 @@
+mod breaker;
+mod cache;
+mod cli;
+mod compress;
+mod config;
+mod esplora;
+mod fallback;
+mod fiat;
+mod limit;
+mod metadata;
+mod render;
+mod stats;
+mod stdio;
+mod tls;
+mod tools;
+mod ws;
+
 // Import necessary items from our dependencies
+use config::{Chain, Config, Transport};
+use esplora::{BitcoinNetwork, EsploraClient, LiquidNetwork, UreqClient};
+use rmcp::serde_json::{Value, json};
 use rmcp::{
     RoleServer,
-    ServiceExt,
     handler::server::ServerHandler,
     model::{
-        CallToolRequestParam, CallToolResult, Content, ErrorData, Implementation,
-        InitializeRequestParam, InitializeResult, ListToolsResult, PaginatedRequestParam,
-        ProtocolVersion, ServerCapabilities, Tool,
+        CallToolRequestParam, CallToolResult, ErrorData, Implementation, InitializeRequestParam,
+        InitializeResult, JsonObject, ListToolsResult, PaginatedRequestParam, ProtocolVersion,
+        RequestId, ServerCapabilities, Tool,
     },
-    schemars, // For generating the "menu"
     service::RequestContext,
-    transport::stdio, // The stdio communication channel
 };
-use serde::Deserialize; // For our tool's inputs
-
-// Esplora API base URLs for all supported networks
-const BITCOIN_MAINNET_API: &str = "https://blockstream.info/api";
-const BITCOIN_TESTNET_API: &str = "https://blockstream.info/testnet/api";
-const BITCOIN_SIGNET_API: &str = "https://blockstream.info/signet/api";
-const LIQUID_MAINNET_API: &str = "https://blockstream.info/liquid/api";
-const LIQUID_TESTNET_API: &str = "https://blockstream.info/liquidtestnet/api";
-
-// Network types for Bitcoin (mainnet, testnet, signet)
-#[derive(Deserialize, schemars::JsonSchema, Default, Clone, Copy)]
-#[serde(rename_all = "lowercase")]
-enum BitcoinNetwork {
-    #[default]
-    Mainnet,
-    Testnet,
-    Signet,
+use std::sync::Arc;
+
+// 2. DEFINE YOUR SERVER
+// This struct holds the state shared by all tool calls: the HTTP client
+// used to reach Esplora, injectable so tests can run without network access.
+#[derive(Clone)]
+pub struct MyServer {
+    client: Arc<dyn EsploraClient>,
+    config: Arc<Config>,
+    stats: Arc<stats::ServerStats>,
+    cache: Arc<dyn cache::CacheBackend>,
 }
 
-impl BitcoinNetwork {
-    fn api_base(&self) -> &'static str {
-        match self {
-            BitcoinNetwork::Mainnet => BITCOIN_MAINNET_API,
-            BitcoinNetwork::Testnet => BITCOIN_TESTNET_API,
-            BitcoinNetwork::Signet => BITCOIN_SIGNET_API,
+impl MyServer {
+    fn new() -> Self {
+        let config = Config::from_env();
+        if config.insecure_tls {
+            tracing::warn!(
+                "ESPLORA_INSECURE_TLS is set: TLS certificates of the Esplora backends are NOT verified, anyone on the network path can impersonate them. Never use this in production."
+            );
+        }
+        for name in config
+            .tools_enabled
+            .iter()
+            .flatten()
+            .chain(&config.tools_disabled)
+        {
+            if tools::find(name).is_none() {
+                tracing::warn!(tool = %name, "TOOLS_ENABLED or TOOLS_DISABLED names an unknown tool");
+            }
+        }
+        let client = limit::ConcurrencyLimitClient::new(
+            Arc::new(UreqClient::new(
+                config.max_redirects,
+                config.cross_origin_redirects,
+                config.insecure_tls,
+            )),
+            config.max_concurrent_requests,
+            config.max_queue_wait,
+        );
+        let client = breaker::CircuitBreakerClient::new(
+            Arc::new(client),
+            config.breaker_threshold,
+            config.breaker_cooldown,
+        );
+        let backend: Arc<dyn cache::CacheBackend> = match &config.cache_dir {
+            Some(dir) => match cache::DiskCache::new(dir.clone()) {
+                Ok(disk) => Arc::new(disk),
+                Err(e) => {
+                    tracing::warn!(
+                        dir = %dir.display(),
+                        "Cache directory unusable, caching in memory only: {e}"
+                    );
+                    Arc::new(cache::MemoryCache::default())
+                }
+            },
+            None => Arc::new(cache::MemoryCache::default()),
+        };
+        let client =
+            cache::CachingClient::new(Arc::new(client), config.volatile_cache_ttl, backend.clone());
+        MyServer {
+            client: Arc::new(client),
+            config: Arc::new(config),
+            stats: Arc::new(stats::ServerStats::new()),
+            cache: backend,
         }
     }
-}
-
-// Network types for Liquid (mainnet, testnet)
-#[derive(Deserialize, schemars::JsonSchema, Default, Clone, Copy)]
-#[serde(rename_all = "lowercase")]
-enum LiquidNetwork {
-    #[default]
-    Mainnet,
-    Testnet,
-}
 
-impl LiquidNetwork {
-    fn api_base(&self) -> &'static str {
-        match self {
-            LiquidNetwork::Mainnet => LIQUID_MAINNET_API,
-            LiquidNetwork::Testnet => LIQUID_TESTNET_API,
+    // Warm the cache with the tip of each enabled chain, in the background so
+    // that a slow or failing backend doesn't hold up the startup
+    fn prefetch_tip(&self) -> std::thread::JoinHandle<()> {
+        let mut bases = Vec::new();
+        if self.config.is_enabled(Chain::Bitcoin) {
+            bases.push((Chain::Bitcoin, BitcoinNetwork::Mainnet.api_base()));
+        }
+        if self.config.is_enabled(Chain::Liquid) {
+            bases.push((Chain::Liquid, LiquidNetwork::Mainnet.api_base()));
         }
+        let client = self.client.clone();
+        std::thread::spawn(move || {
+            for (chain, base) in bases {
+                let tip = client
+                    .get(&format!("{base}/blocks/tip/height"))
+                    .and_then(|height| {
+                        let hash = client.get(&format!("{base}/blocks/tip/hash"))?;
+                        Ok((height, hash))
+                    });
+                match tip {
+                    Ok((height, hash)) => tracing::info!(
+                        chain = chain.as_str(),
+                        height = height.trim(),
+                        hash = hash.trim(),
+                        "Prefetched chain tip"
+                    ),
+                    Err(e) => {
+                        tracing::warn!(chain = chain.as_str(), "Failed to prefetch chain tip: {e}")
+                    }
+                }
+            }
+        })
     }
-}
-
-// 1. DEFINE YOUR TOOL'S INPUT PARAMETERS
-// The AI will see this and know what to provide.
-// 'schemars::JsonSchema' automatically builds the "menu" for the AI.
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetBitcoinTxParams {
-    #[schemars(description = "The transaction ID (txid) hash to look up.")]
-    txid: String,
-    #[schemars(
-        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
-    )]
-    #[serde(default)]
-    network: BitcoinNetwork,
-}
 
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetLiquidTxParams {
-    #[schemars(description = "The transaction ID (txid) hash to look up.")]
-    txid: String,
-    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
-    #[serde(default)]
-    network: LiquidNetwork,
-}
-
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetBitcoinBlockParams {
-    #[schemars(description = "The block hash to look up.")]
-    hash: String,
-    #[schemars(
-        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
-    )]
-    #[serde(default)]
-    network: BitcoinNetwork,
+    // Persist the cache entries still buffered before exiting
+    fn shutdown(&self) {
+        let persisted = self.cache.flush();
+        if persisted > 0 {
+            tracing::info!(persisted, "Flushed cache entries to disk");
+        }
+    }
 }
 
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetLiquidBlockParams {
-    #[schemars(description = "The block hash to look up.")]
-    hash: String,
-    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
-    #[serde(default)]
-    network: LiquidNetwork,
-}
+impl MyServer {
+    // Reject calls for chains disabled via ENABLED_NETWORKS
+    fn require_chain(&self, chain: Chain) -> Result<(), ErrorData> {
+        if self.config.is_enabled(chain) {
+            Ok(())
+        } else {
+            Err(ErrorData::invalid_request(
+                format!(
+                    "The {} network is not enabled on this server",
+                    chain.as_str()
+                ),
+                None,
+            ))
+        }
+    }
 
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetBitcoinTipHeightParams {
-    #[schemars(
-        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
-    )]
-    #[serde(default)]
-    network: BitcoinNetwork,
-}
+    // Run a tool call, applying the configured post-processing to its result
+    fn run_tool(&self, mut params: CallToolRequestParam) -> Result<CallToolResult, ErrorData> {
+        let format = render::take_result_format(&mut params.arguments)?;
+        let result = self.run_tool_with_metadata(params)?;
+        let result = if self.config.sort_output_keys {
+            tools::canonicalize_json(result)
+        } else {
+            result
+        };
+        let result = render::render_result(result, format, self.config.locale)?;
+        // Last, so that the client gets back exactly what it would otherwise see
+        Ok(if self.config.compress_results_above > 0 {
+            compress::compress_result(result, self.config.compress_results_above)
+        } else {
+            result
+        })
+    }
 
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetLiquidTipHeightParams {
-    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
-    #[serde(default)]
-    network: LiquidNetwork,
-}
+    // Run a tool call off the async runtime, failing it once the configured
+    // deadline has passed
+    async fn run_tool_with_deadline(
+        &self,
+        params: CallToolRequestParam,
+        span: tracing::Span,
+    ) -> Result<CallToolResult, ErrorData> {
+        let server = self.clone();
+        let name = params.name.clone();
+        let call = tokio::task::spawn_blocking(move || {
+            let _span = span.entered();
+            server.run_tool(params)
+        });
+        let timeout = self.config.tool_timeout;
+        let joined = if timeout.is_zero() {
+            call.await
+        } else {
+            // A tool running late can't be interrupted: it finishes in the
+            // background, its result discarded
+            tokio::time::timeout(timeout, call).await.map_err(|_| {
+                tracing::warn!(tool = %name, "tool call timed out");
+                ErrorData::internal_error(
+                    format!(
+                        "Tool execution timed out after {}s: {name}",
+                        timeout.as_secs_f64()
+                    ),
+                    Some(json!({ "kind": "timeout", "timeout_secs": timeout.as_secs_f64() })),
+                )
+            })?
+        };
+        joined.map_err(|e| ErrorData::internal_error(format!("Tool call failed: {e}"), None))?
+    }
 
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetBitcoinMempoolParams {
-    #[schemars(
-        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
-    )]
-    #[serde(default)]
-    network: BitcoinNetwork,
-}
+    fn run_tool_with_metadata(
+        &self,
+        params: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        if !self.config.include_metadata {
+            return self
+                .with_fallback(self.client.clone())
+                .dispatch_tool(params);
+        }
+        // Route this call's upstream requests through a recorder to report them,
+        // below the fallback so that every backend tried is listed
+        let recorder = Arc::new(metadata::RecordingClient::new(self.client.clone()));
+        let server = self.with_fallback(recorder.clone());
+        let started = std::time::Instant::now();
+        let result = server.dispatch_tool(params)?;
+        Ok(metadata::wrap_with_metadata(
+            result,
+            recorder.take_requests(),
+            started.elapsed(),
+        ))
+    }
 
-#[derive(Deserialize, schemars::JsonSchema)]
-struct GetLiquidMempoolParams {
-    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
-    #[serde(default)]
-    network: LiquidNetwork,
-}
+    // A server whose tools reach Esplora through `client`, falling back to the
+    // secondary backends configured for a network when its primary fails
+    fn with_fallback(&self, client: Arc<dyn EsploraClient>) -> MyServer {
+        let client: Arc<dyn EsploraClient> = if self.config.fallback_backends.is_empty() {
+            client
+        } else {
+            Arc::new(fallback::FallbackClient::new(
+                client,
+                self.config.fallback_backends.clone(),
+            ))
+        };
+        MyServer {
+            client,
+            ..self.clone()
+        }
+    }
 
-// 2. DEFINE YOUR SERVER
-// This struct will hold any state your server needs (like API keys, etc.)
-// For "Hello World," it's empty.
-#[derive(Clone)]
-struct MyServer;
-
-// Helper function to create a schema map from a JsonSchema type
-fn make_schema<T: schemars::JsonSchema>()
--> Result<std::sync::Arc<rmcp::serde_json::Map<String, rmcp::serde_json::Value>>, ErrorData> {
-    use std::sync::Arc;
-    let schema = schemars::schema_for!(T);
-    let input_schema = rmcp::serde_json::to_value(schema)
-        .map_err(|e| ErrorData::internal_error(format!("Failed to serialize schema: {e}"), None))?;
-    if let rmcp::serde_json::Value::Object(map) = input_schema {
-        Ok(Arc::new(map))
-    } else {
-        Err(ErrorData::internal_error("Schema is not an object", None))
+    // The tools offered to clients: the enabled ones, for the enabled chains
+    fn listed_tools(&self) -> impl Iterator<Item = &'static tools::ToolDef> {
+        tools::registry().filter(|tool| {
+            self.config.is_tool_enabled(tool.name)
+                && tool.chain.is_none_or(|chain| self.config.is_enabled(chain))
+        })
     }
-}
 
-// Fetch data from Esplora API
-fn fetch_esplora(url: &str) -> Result<String, String> {
-    let response = ureq::get(url)
-        .call()
-        .map_err(|e| format!("HTTP request failed: {e}"))?;
-    response
-        .into_string()
-        .map_err(|e| format!("Failed to read response: {e}"))
-}
+    // A tool's input schema, with the arguments handled outside of the tool
+    fn input_schema(&self, tool: &tools::ToolDef) -> Result<Arc<JsonObject>, ErrorData> {
+        let schema = render::with_output_format((tool.schema)()?);
+        Ok(if self.config.price_source().is_some() {
+            fiat::with_fiat_option(schema)
+        } else {
+            schema
+        })
+    }
 
-fn fetch_transaction(base_url: &str, txid: &str) -> Result<String, String> {
-    fetch_esplora(&format!("{base_url}/tx/{txid}"))
+    // Run the tool named in the request and return its raw result
+    fn dispatch_tool(&self, params: CallToolRequestParam) -> Result<CallToolResult, ErrorData> {
+        let tool_name = params.name.as_ref();
+        // Former names of renamed tools still dispatch to the current one
+        let (tool, alias) = match tools::find(tool_name) {
+            Some(tool) => (tool, None),
+            None => {
+                let tool = tools::find_alias(tool_name).ok_or_else(|| {
+                    ErrorData::invalid_request(format!("Unknown tool: {tool_name}"), None)
+                })?;
+                (tool, Some(tool_name))
+            }
+        };
+        if !self.config.is_tool_enabled(tool.name) {
+            return Err(ErrorData::invalid_request(
+                format!("Tool {} is disabled on this server", tool.name),
+                None,
+            ));
+        }
+        if let Some(chain) = tool.chain {
+            self.require_chain(chain)?;
+        }
+        let mut args = params.arguments.unwrap_or_default();
+        let fiat = fiat::take_fiat_currency(&mut args, self.config.price_source().is_some())?;
+        tracing::info!(tool = tool.name, "tool call started");
+        let started = std::time::Instant::now();
+        let result = (tool.handler)(self, Value::Object(args)).and_then(|result| match &fiat {
+            Some(currency) => fiat::with_fiat_amounts(self, result, currency),
+            None => Ok(result),
+        });
+        let elapsed = started.elapsed();
+        match &result {
+            Ok(_) => tracing::info!(
+                elapsed_ms = elapsed.as_millis() as u64,
+                "tool call succeeded"
+            ),
+            Err(e) => tracing::info!(
+                elapsed_ms = elapsed.as_millis() as u64,
+                error = %e.message,
+                "tool call failed"
+            ),
+        }
+        self.stats.record(tool.name, elapsed, result.is_err());
+        match alias {
+            Some(alias) => result.map(|result| tools::add_alias_warning(result, alias, tool.name)),
+            None => result,
+        }
+    }
 }
 
-fn fetch_block(base_url: &str, hash: &str) -> Result<String, String> {
-    fetch_esplora(&format!("{base_url}/block/{hash}"))
+// Every log line of a call, including its upstream requests, carries the
+// JSON-RPC request id
+fn call_span(id: &RequestId) -> tracing::Span {
+    tracing::info_span!("call_tool", request_id = %id)
 }
 
-fn fetch_tip_height(base_url: &str) -> Result<String, String> {
-    fetch_esplora(&format!("{base_url}/blocks/tip/height"))
-}
+// MCP protocol versions this server speaks, the default one last
+const SUPPORTED_PROTOCOL_VERSIONS: &[ProtocolVersion] =
+    &[ProtocolVersion::V_2024_11_05, ProtocolVersion::V_2025_03_26];
 
-fn fetch_mempool(base_url: &str) -> Result<String, String> {
-    fetch_esplora(&format!("{base_url}/mempool"))
+// Answer with the client's version when supported, otherwise with ours and
+// let the client decide whether it can continue
+fn negotiate_protocol_version(requested: &ProtocolVersion) -> ProtocolVersion {
+    if SUPPORTED_PROTOCOL_VERSIONS.contains(requested) {
+        requested.clone()
+    } else {
+        ProtocolVersion::default()
+    }
 }
 
 // 3. IMPLEMENT THE TOOL HANDLER
@@ -189,81 +343,22 @@ impl ServerHandler for MyServer {
         _params: Option<PaginatedRequestParam>,
         _context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, ErrorData> {
-        Ok(ListToolsResult {
-            tools: vec![
-                Tool {
-                    name: "get_bitcoin_tx".into(),
-                    title: None,
-                    description: Some("Get a Bitcoin transaction by its txid from the Esplora API. Returns full transaction data including confirmation status and block height.".into()),
-                    input_schema: make_schema::<GetBitcoinTxParams>()?,
-                    output_schema: None,
-                    annotations: None,
-                    icons: None,
-                },
-                Tool {
-                    name: "get_liquid_tx".into(),
-                    title: None,
-                    description: Some("Get a Liquid transaction by its txid from the Esplora API. Returns full transaction data including confirmation status and block height.".into()),
-                    input_schema: make_schema::<GetLiquidTxParams>()?,
-                    output_schema: None,
-                    annotations: None,
-                    icons: None,
-                },
-                Tool {
-                    name: "get_bitcoin_block".into(),
-                    title: None,
-                    description: Some("Get a Bitcoin block by its hash from the Esplora API. Returns block data including height, timestamp, tx_count, size, and weight.".into()),
-                    input_schema: make_schema::<GetBitcoinBlockParams>()?,
-                    output_schema: None,
-                    annotations: None,
-                    icons: None,
-                },
-                Tool {
-                    name: "get_liquid_block".into(),
-                    title: None,
-                    description: Some("Get a Liquid block by its hash from the Esplora API. Returns block data including height, timestamp, tx_count, size, and weight.".into()),
-                    input_schema: make_schema::<GetLiquidBlockParams>()?,
-                    output_schema: None,
-                    annotations: None,
-                    icons: None,
-                },
-                Tool {
-                    name: "get_bitcoin_tip_height".into(),
-                    title: None,
-                    description: Some("Get the current Bitcoin blockchain tip height from the Esplora API.".into()),
-                    input_schema: make_schema::<GetBitcoinTipHeightParams>()?,
-                    output_schema: None,
-                    annotations: None,
-                    icons: None,
-                },
-                Tool {
-                    name: "get_liquid_tip_height".into(),
+        let tools = self
+            .listed_tools()
+            .map(|tool| {
+                Ok(Tool {
+                    name: tool.name.into(),
                     title: None,
-                    description: Some("Get the current Liquid blockchain tip height from the Esplora API.".into()),
-                    input_schema: make_schema::<GetLiquidTipHeightParams>()?,
+                    description: Some(tools::describe(tool).into()),
+                    input_schema: self.input_schema(tool)?,
                     output_schema: None,
                     annotations: None,
                     icons: None,
-                },
-                Tool {
-                    name: "get_bitcoin_mempool".into(),
-                    title: None,
-                    description: Some("Get Bitcoin mempool statistics from the Esplora API. Returns tx count, total vsize, total fees, and fee histogram.".into()),
-                    input_schema: make_schema::<GetBitcoinMempoolParams>()?,
-                    output_schema: None,
-                    annotations: None,
-                    icons: None,
-                },
-                Tool {
-                    name: "get_liquid_mempool".into(),
-                    title: None,
-                    description: Some("Get Liquid mempool statistics from the Esplora API. Returns tx count, total vsize, total fees, and fee histogram.".into()),
-                    input_schema: make_schema::<GetLiquidMempoolParams>()?,
-                    output_schema: None,
-                    annotations: None,
-                    icons: None,
-                },
-            ],
+                })
+            })
+            .collect::<Result<_, ErrorData>>()?;
+        Ok(ListToolsResult {
+            tools,
             next_cursor: None,
         })
     }
@@ -272,101 +367,27 @@ impl ServerHandler for MyServer {
     async fn call_tool(
         &self,
         params: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
-        let tool_name = params.name.as_ref();
-        let args = params.arguments.unwrap_or_default();
-        let args_value = rmcp::serde_json::Value::Object(args);
-
-        // This 'match' is how you handle multiple tools.
-        match tool_name {
-            "get_bitcoin_tx" => {
-                let tx_params: GetBitcoinTxParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                        ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                    })?;
-                let result = fetch_transaction(tx_params.network.api_base(), &tx_params.txid)
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            "get_liquid_tx" => {
-                let tx_params: GetLiquidTxParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                        ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                    })?;
-                let result = fetch_transaction(tx_params.network.api_base(), &tx_params.txid)
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            "get_bitcoin_block" => {
-                let block_params: GetBitcoinBlockParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                        ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                    })?;
-                let result = fetch_block(block_params.network.api_base(), &block_params.hash)
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            "get_liquid_block" => {
-                let block_params: GetLiquidBlockParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                    ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                })?;
-                let result = fetch_block(block_params.network.api_base(), &block_params.hash)
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            "get_bitcoin_tip_height" => {
-                let params: GetBitcoinTipHeightParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                        ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                    })?;
-                let result = fetch_tip_height(params.network.api_base())
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            "get_liquid_tip_height" => {
-                let params: GetLiquidTipHeightParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                        ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                    })?;
-                let result = fetch_tip_height(params.network.api_base())
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            "get_bitcoin_mempool" => {
-                let params: GetBitcoinMempoolParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                        ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                    })?;
-                let result = fetch_mempool(params.network.api_base())
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            "get_liquid_mempool" => {
-                let params: GetLiquidMempoolParams = rmcp::serde_json::from_value(args_value)
-                    .map_err(|e| {
-                        ErrorData::invalid_request(format!("Invalid parameters: {e}"), None)
-                    })?;
-                let result = fetch_mempool(params.network.api_base())
-                    .map_err(|e| ErrorData::internal_error(e, None))?;
-                Ok(CallToolResult::success(vec![Content::text(result)]))
-            }
-            _ => Err(ErrorData::invalid_request(
-                format!("Unknown tool: {tool_name}"),
-                None,
-            )),
-        }
+        self.run_tool_with_deadline(params, call_span(&context.id))
+            .await
     }
 
     // This function is called during initialization to set up the server
     async fn initialize(
         &self,
-        _params: InitializeRequestParam,
+        params: InitializeRequestParam,
         _context: RequestContext<RoleServer>,
     ) -> Result<InitializeResult, ErrorData> {
+        let protocol_version = negotiate_protocol_version(&params.protocol_version);
+        if protocol_version != params.protocol_version {
+            tracing::warn!(
+                requested = %params.protocol_version,
+                "Unsupported protocol version requested, offering {protocol_version}"
+            );
+        }
         Ok(InitializeResult {
-            protocol_version: ProtocolVersion::default(),
+            protocol_version,
             capabilities: ServerCapabilities {
                 tools: Some(Default::default()),
                 ..Default::default()
@@ -378,7 +399,7 @@ impl ServerHandler for MyServer {
                 icons: None,
                 website_url: None,
             },
-            instructions: None,
+            instructions: Some(tools::instructions(&self.config)),
         })
     }
 }
@@ -386,28 +407,362 @@ impl ServerHandler for MyServer {
 // 4. CREATE THE MAIN FUNCTION TO RUN THE SERVER
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> anyhow::Result<()> {
+    // Logs go to stderr, stdout carries the MCP messages of the stdio transport
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+
     // Create an instance of our server
-    let server = MyServer;
+    let server = MyServer::new();
+
+    // `call <tool> [--name value]...` runs one tool and prints its result
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("call") {
+        let code = cli::run(&server, &args[1..]);
+        server.shutdown();
+        std::process::exit(code);
+    }
+
+    // Only worth it for a server that goes on answering requests
+    if server.config.prefetch_tip {
+        server.prefetch_tip();
+    }
+
+    // Whichever way serving ends, persist the cache before exiting
+    tokio::select! {
+        served = serve(server.clone()) => {
+            server.shutdown();
+            served
+        }
+        () = shutdown_signal() => {
+            tracing::info!("Shutdown signal received");
+            server.shutdown();
+            // Winding down the runtime would wait for the pending read of stdin
+            std::process::exit(0);
+        }
+    }
+}
 
-    // This is the crucial part:
-    // 1. 'stdio()' creates the stdio transport.
-    // 2. '.serve()' attaches our server logic to the transport.
-    // 3. '.waiting()' keeps the server running until it's shut down.
-    let running_service = server.serve(stdio()).await?;
-    let _quit_reason = running_service.waiting().await?;
+// Serve over the configured transport: stdin/stdout until the client closes
+// stdin, or WebSocket connections until the listener fails
+async fn serve(server: MyServer) -> anyhow::Result<()> {
+    if server.config.transport == Transport::WebSocket {
+        let bind_addr = server.config.bind_addr.clone();
+        ws::serve(server, &bind_addr).await
+    } else {
+        stdio::serve(server).await
+    }
+}
 
-    Ok(())
+// Resolve on SIGINT, or SIGTERM on Unix
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for SIGINT: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = interrupt => {}
+        () = terminate => {}
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use rmcp::serde_json;
     use std::io::{BufRead, BufReader, Write};
     use std::process::{Command, Stdio};
 
+    #[test]
+    fn test_negotiate_protocol_version() {
+        assert_eq!(
+            negotiate_protocol_version(&ProtocolVersion::V_2024_11_05),
+            ProtocolVersion::V_2024_11_05
+        );
+        assert_eq!(
+            negotiate_protocol_version(&ProtocolVersion::V_2025_03_26),
+            ProtocolVersion::V_2025_03_26
+        );
+        let unknown: ProtocolVersion = serde_json::from_str(r#""2099-01-01""#).unwrap();
+        assert_eq!(
+            negotiate_protocol_version(&unknown),
+            ProtocolVersion::default()
+        );
+    }
+
+    struct SlowClient;
+
+    impl EsploraClient for SlowClient {
+        fn get(&self, _url: &str) -> Result<String, String> {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            Ok("840000".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_timeout() {
+        let server = |tool_timeout| MyServer {
+            client: Arc::new(SlowClient),
+            config: Arc::new(Config {
+                tool_timeout,
+                ..Config::default()
+            }),
+            stats: Arc::new(stats::ServerStats::new()),
+            cache: Arc::new(cache::MemoryCache::default()),
+        };
+        let params = || CallToolRequestParam {
+            name: "get_bitcoin_tip_height".into(),
+            arguments: None,
+        };
+        let err = server(std::time::Duration::from_millis(50))
+            .run_tool_with_deadline(params(), tracing::Span::none())
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("timed out"), "{}", err.message);
+        assert_eq!(err.data.unwrap()["kind"], "timeout");
+        let result = server(std::time::Duration::from_secs(5))
+            .run_tool_with_deadline(params(), tracing::Span::none())
+            .await
+            .unwrap();
+        assert_eq!(result.content[0].as_text().unwrap().text, "840000");
+    }
+
+    // Log output shared with the test reading it
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_request_id_in_call_logs() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let server = MyServer {
+            client: Arc::new(
+                esplora::testing::MockClient::default()
+                    .with("https://blockstream.info/api/blocks/tip/height", "840000"),
+            ),
+            config: Arc::new(Config::default()),
+            stats: Arc::new(stats::ServerStats::new()),
+            cache: Arc::new(cache::MemoryCache::default()),
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = call_span(&RequestId::Number(42)).entered();
+            server
+                .run_tool(CallToolRequestParam {
+                    name: "get_bitcoin_tip_height".into(),
+                    arguments: None,
+                })
+                .unwrap();
+        });
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        for event in ["tool call started", "tool call succeeded"] {
+            let line = logs
+                .lines()
+                .find(|line| line.contains(event))
+                .unwrap_or_else(|| panic!("{event} not logged: {logs}"));
+            assert!(line.contains("call_tool{request_id=42}"), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_prefetch_tip() {
+        let bitcoin = BitcoinNetwork::Mainnet.api_base();
+        let liquid = LiquidNetwork::Mainnet.api_base();
+        // Only Liquid answers: the failed Bitcoin prefetch is merely logged
+        let inner = Arc::new(
+            esplora::testing::MockClient::default()
+                .with(&format!("{liquid}/blocks/tip/height"), "3000000")
+                .with(&format!("{liquid}/blocks/tip/hash"), "00ff"),
+        );
+        let backend: Arc<dyn cache::CacheBackend> = Arc::new(cache::MemoryCache::default());
+        let server = MyServer {
+            client: Arc::new(cache::CachingClient::new(
+                inner.clone(),
+                std::time::Duration::from_secs(10),
+                backend.clone(),
+            )),
+            config: Arc::new(Config::default()),
+            stats: Arc::new(stats::ServerStats::new()),
+            cache: backend.clone(),
+        };
+        server.prefetch_tip().join().unwrap();
+        assert_eq!(
+            backend
+                .lookup(&format!("{liquid}/blocks/tip/height"))
+                .as_deref(),
+            Some("3000000")
+        );
+        assert_eq!(
+            backend
+                .lookup(&format!("{liquid}/blocks/tip/hash"))
+                .as_deref(),
+            Some("00ff")
+        );
+        assert_eq!(
+            backend.lookup(&format!("{bitcoin}/blocks/tip/height")),
+            None
+        );
+
+        // A call needing the tip shortly after is answered from the cache
+        let requests = inner.requests().len();
+        let result = server
+            .dispatch_tool(CallToolRequestParam {
+                name: "get_liquid_tip_height".into(),
+                arguments: None,
+            })
+            .unwrap();
+        assert_eq!(result.content[0].as_text().unwrap().text, "3000000");
+        assert_eq!(inner.requests().len(), requests);
+    }
+
+    #[test]
+    fn test_disabled_tool() {
+        let config = Config {
+            tools_enabled: Some(vec![
+                "broadcast_bitcoin_tx".to_string(),
+                "decode_bitcoin_script".to_string(),
+            ]),
+            tools_disabled: vec!["broadcast_bitcoin_tx".to_string()],
+            ..Config::default()
+        };
+        assert!(config.is_tool_enabled("decode_bitcoin_script"));
+        assert!(!config.is_tool_enabled("broadcast_bitcoin_tx"));
+        assert!(!config.is_tool_enabled("get_bitcoin_tip_height"));
+        assert!(Config::default().is_tool_enabled("broadcast_bitcoin_tx"));
+        let server = MyServer {
+            client: Arc::new(esplora::testing::MockClient::default()),
+            config: Arc::new(config),
+            stats: Arc::new(stats::ServerStats::new()),
+            cache: Arc::new(cache::MemoryCache::default()),
+        };
+        let err = server
+            .dispatch_tool(CallToolRequestParam {
+                name: "broadcast_bitcoin_tx".into(),
+                arguments: None,
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "Tool broadcast_bitcoin_tx is disabled on this server"
+        );
+    }
+
+    #[test]
+    fn test_shutdown_persists_pending_cache_entries() {
+        let dir =
+            std::env::temp_dir().join(format!("bitcoin-data-mcp-shutdown-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let server = || {
+            let backend: Arc<dyn cache::CacheBackend> =
+                Arc::new(cache::DiskCache::new(dir.clone()).unwrap());
+            MyServer {
+                client: Arc::new(cache::CachingClient::new(
+                    Arc::new(
+                        esplora::testing::MockClient::default()
+                            .with("http://e/block/00", "block 0")
+                            .with("http://e/block/01", "block 1"),
+                    ),
+                    std::time::Duration::ZERO,
+                    backend.clone(),
+                )),
+                config: Arc::new(Config::default()),
+                stats: Arc::new(stats::ServerStats::new()),
+                cache: backend,
+            }
+        };
+        let persisted = || std::fs::read_dir(&dir).unwrap().count();
+
+        // Shutting down flushes the batch even while a session still holds the server
+        let first = server();
+        first.client.get("http://e/block/00").unwrap();
+        let session = first.clone();
+        assert_eq!(persisted(), 0);
+        first.shutdown();
+        assert_eq!(persisted(), 1);
+        drop(session);
+
+        // So does dropping the last handle on the server mid-batch
+        let second = server();
+        second.client.get("http://e/block/01").unwrap();
+        assert_eq!(persisted(), 1);
+        drop(second);
+        assert_eq!(persisted(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_disabled_chain_tools() {
+        let server = MyServer {
+            client: Arc::new(esplora::testing::MockClient::default()),
+            config: Arc::new(Config {
+                enabled_chains: vec![Chain::Bitcoin],
+                ..Config::default()
+            }),
+            stats: Arc::new(stats::ServerStats::new()),
+            cache: Arc::new(cache::MemoryCache::default()),
+        };
+        let listed: Vec<&str> = server.listed_tools().map(|tool| tool.name).collect();
+        assert!(listed.contains(&"get_bitcoin_tx"));
+        assert!(listed.contains(&"find_tx"));
+        for name in ["get_liquid_tx", "get_liquid_mempool", "list_liquid_assets"] {
+            assert!(!listed.contains(&name), "{name} should be hidden");
+            let err = server
+                .dispatch_tool(CallToolRequestParam {
+                    name: name.into(),
+                    arguments: None,
+                })
+                .unwrap_err();
+            assert_eq!(
+                err.message,
+                "The liquid network is not enabled on this server"
+            );
+        }
+    }
+
     // Run with: cargo test test_mcp_protocol -- --ignored --nocapture
     #[test]
     #[ignore]
+    #[allow(
+        clippy::needless_borrows_for_generic_args,
+        clippy::to_string_in_format_args,
+        clippy::zombie_processes
+    )]
     fn test_mcp_protocol_initialize_and_list_tools() {
         // Build the binary first
         let build_result = Command::new("cargo")
@@ -516,7 +871,7 @@ mod tests {
         );
 
         let tools = tools_response["result"]["tools"].as_array().unwrap();
-        assert_eq!(tools.len(), 8, "Should have exactly 8 tools");
+        assert_eq!(tools.len(), 95, "Should have exactly 95 tools");
 
         // Check all tools exist with proper schema
         for tool_name in [
@@ -528,6 +883,11 @@ mod tests {
             "get_liquid_tip_height",
             "get_bitcoin_mempool",
             "get_liquid_mempool",
+            "find_tx",
+            "get_bitcoin_tx_opreturn",
+            "get_bitcoin_block_reward",
+            "get_bitcoin_adjacent_block",
+            "get_bitcoin_block_volume",
         ] {
             let tool = tools
                 .iter()
@@ -0,0 +1,138 @@
+// Optional provenance envelope around tool results.
+//
+// When INCLUDE_METADATA is enabled, every upstream request made while serving
+// a tool call is recorded, and the tool's payload is moved under a `data` key
+// next to a `metadata` object listing those requests.
+use crate::esplora::{EsploraClient, status_from_error};
+use rmcp::model::{CallToolResult, RawContent};
+use rmcp::serde_json::{self, Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub struct UpstreamRequest {
+    url: String,
+    status: Option<u16>,
+    latency: Duration,
+}
+
+// Client recording the URL, status, and latency of every request it forwards
+pub struct RecordingClient {
+    inner: Arc<dyn EsploraClient>,
+    requests: Mutex<Vec<UpstreamRequest>>,
+}
+
+impl RecordingClient {
+    pub fn new(inner: Arc<dyn EsploraClient>) -> Self {
+        RecordingClient {
+            inner,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn take_requests(&self) -> Vec<UpstreamRequest> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+impl RecordingClient {
+    fn recorded<T>(
+        &self,
+        url: &str,
+        request: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let started = Instant::now();
+        let result = request();
+        let status = match &result {
+            Ok(_) => Some(200),
+            Err(e) => status_from_error(e),
+        };
+        self.requests.lock().unwrap().push(UpstreamRequest {
+            url: url.to_string(),
+            status,
+            latency: started.elapsed(),
+        });
+        result
+    }
+}
+
+impl EsploraClient for RecordingClient {
+    fn get(&self, url: &str) -> Result<String, String> {
+        self.recorded(url, || self.inner.get(url))
+    }
+
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        self.recorded(url, || self.inner.get_bytes(url))
+    }
+
+    fn post(&self, url: &str, body: &str) -> Result<String, String> {
+        self.recorded(url, || self.inner.post(url, body))
+    }
+}
+
+// Move each text payload under `data` and attach the request metadata
+pub fn wrap_with_metadata(
+    mut result: CallToolResult,
+    requests: Vec<UpstreamRequest>,
+    elapsed: Duration,
+) -> CallToolResult {
+    let upstream: Vec<Value> = requests
+        .iter()
+        .map(|request| {
+            json!({
+                "url": request.url,
+                "status": request.status,
+                "latency_ms": request.latency.as_millis() as u64,
+            })
+        })
+        .collect();
+    let metadata = json!({
+        "upstream": upstream,
+        "elapsed_ms": elapsed.as_millis() as u64,
+    });
+    for content in result.content.iter_mut() {
+        if let RawContent::Text(text) = &mut content.raw {
+            // Keep JSON payloads structured, fall back to a plain string otherwise
+            let data = serde_json::from_str(&text.text)
+                .unwrap_or_else(|_| Value::String(text.text.clone()));
+            text.text = json!({ "data": data, "metadata": metadata }).to_string();
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+
+    struct StaticClient;
+
+    impl EsploraClient for StaticClient {
+        fn get(&self, url: &str) -> Result<String, String> {
+            if url.ends_with("/missing") {
+                Err(format!("{url}: status code 404"))
+            } else {
+                Ok("840000".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrap_with_metadata() {
+        let recorder = RecordingClient::new(Arc::new(StaticClient));
+        let body = recorder.get("http://e/blocks/tip/height").unwrap();
+        assert!(recorder.get("http://e/missing").is_err());
+
+        let result = CallToolResult::success(vec![Content::text(body)]);
+        let result = wrap_with_metadata(result, recorder.take_requests(), Duration::ZERO);
+        let text = &result.content[0].as_text().unwrap().text;
+        let envelope: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(envelope["data"], 840000);
+        assert_eq!(
+            envelope["metadata"]["upstream"][0]["url"],
+            "http://e/blocks/tip/height"
+        );
+        assert_eq!(envelope["metadata"]["upstream"][0]["status"], 200);
+        assert_eq!(envelope["metadata"]["upstream"][1]["status"], 404);
+    }
+}
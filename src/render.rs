@@ -0,0 +1,341 @@
+// Per-call rendering of tool results.
+//
+// Every tool accepts an `output_format` argument, taken out of the arguments
+// before the tool sees them: JSON results are then re-rendered as indented
+// JSON, as `path: value` lines, or as CSV for list results. Results that
+// aren't JSON, e.g. text summaries, are left alone.
+use rmcp::model::{CallToolResult, ErrorData, JsonObject, RawContent};
+use rmcp::serde_json::{self, Value, json};
+use serde::Deserialize;
+use std::sync::Arc;
+
+const OUTPUT_FORMAT_ARG: &str = "output_format";
+
+const OUTPUT_FORMAT_DESCRIPTION: &str = "How to render the result: 'json' (default), 'pretty_json' for indented JSON, 'text_summary' for one 'path: value' line per field, or 'csv' for list results, one row per item.";
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultFormat {
+    #[default]
+    Json,
+    PrettyJson,
+    TextSummary,
+    Csv,
+}
+
+// Number formatting of text results, selected via the LOCALE env var. JSON
+// results are never affected.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum Locale {
+    // 1234567.5, as in JSON
+    #[default]
+    Plain,
+    // 1,234,567.5
+    EnUs,
+    // 1.234.567,5
+    DeDe,
+    // 1 234 567,5, grouped with narrow no-break spaces
+    FrFr,
+}
+
+impl Locale {
+    // Languages are mapped to the number style they share, e.g. es or it to de-DE
+    pub fn parse(name: &str) -> Option<Locale> {
+        let name = name.trim().to_lowercase().replace('_', "-");
+        let language = name.split('-').next().unwrap_or_default();
+        match (name.as_str(), language) {
+            ("plain" | "c" | "posix", _) => Some(Locale::Plain),
+            (_, "en") => Some(Locale::EnUs),
+            (_, "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr") => Some(Locale::DeDe),
+            (_, "fr" | "ru" | "pl" | "cs" | "sv" | "nb" | "fi" | "uk") => Some(Locale::FrFr),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Plain => "plain",
+            Locale::EnUs => "en-US",
+            Locale::DeDe => "de-DE",
+            Locale::FrFr => "fr-FR",
+        }
+    }
+
+    // Group separator and decimal mark
+    fn separators(&self) -> (Option<char>, char) {
+        match self {
+            Locale::Plain => (None, '.'),
+            Locale::EnUs => (Some(','), '.'),
+            Locale::DeDe => (Some('.'), ','),
+            Locale::FrFr => (Some('\u{202f}'), ','),
+        }
+    }
+
+    // Format a plain decimal number like "-1234.5", leaving anything else,
+    // e.g. exponent notation, as it is
+    pub fn number(&self, plain: &str) -> String {
+        let (sign, unsigned) = match plain.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", plain),
+        };
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        };
+        let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+        if !is_digits(integer) || fraction.is_some_and(|fraction| !is_digits(fraction)) {
+            return plain.to_string();
+        }
+        let (group, decimal) = self.separators();
+        let mut formatted = sign.to_string();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.extend(group);
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(decimal);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+}
+
+// Advertise `output_format` in a tool's input schema
+pub fn with_output_format(mut schema: Arc<JsonObject>) -> Arc<JsonObject> {
+    let object = Arc::make_mut(&mut schema);
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        properties.insert(
+            OUTPUT_FORMAT_ARG.to_string(),
+            json!({
+                "type": "string",
+                "enum": ["json", "pretty_json", "text_summary", "csv"],
+                "default": "json",
+                "description": OUTPUT_FORMAT_DESCRIPTION,
+            }),
+        );
+    }
+    schema
+}
+
+// Remove `output_format` from the arguments of a call
+pub fn take_result_format(arguments: &mut Option<JsonObject>) -> Result<ResultFormat, ErrorData> {
+    match arguments
+        .as_mut()
+        .and_then(|args| args.remove(OUTPUT_FORMAT_ARG))
+    {
+        None | Some(Value::Null) => Ok(ResultFormat::default()),
+        Some(format) => serde_json::from_value(format).map_err(|_| {
+            ErrorData::invalid_request(
+                "output_format must be one of 'json', 'pretty_json', 'text_summary', or 'csv'",
+                None,
+            )
+        }),
+    }
+}
+
+pub fn render_result(
+    mut result: CallToolResult,
+    format: ResultFormat,
+    locale: Locale,
+) -> Result<CallToolResult, ErrorData> {
+    if format == ResultFormat::Json || result.is_error == Some(true) {
+        return Ok(result);
+    }
+    for content in result.content.iter_mut() {
+        if let RawContent::Text(text) = &mut content.raw {
+            // Scalars are left alone, there is nothing to lay out
+            if let Ok(value @ (Value::Object(_) | Value::Array(_))) =
+                serde_json::from_str::<Value>(&text.text)
+            {
+                text.text = match format {
+                    ResultFormat::Json => unreachable!(),
+                    ResultFormat::PrettyJson => serde_json::to_string_pretty(&value)
+                        .map_err(|e| ErrorData::internal_error(e.to_string(), None))?,
+                    ResultFormat::TextSummary => text_summary(&value, locale),
+                    ResultFormat::Csv => {
+                        to_csv(&value).map_err(|e| ErrorData::invalid_request(e, None))?
+                    }
+                };
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// One `path: value` line per leaf, e.g. `vout[0].value: 1000`, numbers
+// formatted for `locale`
+fn text_summary(value: &Value, locale: Locale) -> String {
+    fn walk(value: &Value, path: &str, locale: Locale, lines: &mut Vec<String>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, child) in map {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    walk(child, &path, locale, lines);
+                }
+            }
+            Value::Array(items) if !items.is_empty() => {
+                for (i, child) in items.iter().enumerate() {
+                    walk(child, &format!("{path}[{i}]"), locale, lines);
+                }
+            }
+            Value::Number(number) => {
+                lines.push(format!("{path}: {}", locale.number(&number.to_string())))
+            }
+            leaf => lines.push(format!("{path}: {}", scalar_text(leaf))),
+        }
+    }
+    let mut lines = Vec::new();
+    walk(value, "", locale, &mut lines);
+    lines.join("\n")
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// The list of a result: the result itself, or its only array field
+fn csv_rows(value: &Value) -> Option<&Vec<Value>> {
+    match value {
+        Value::Array(items) => Some(items),
+        Value::Object(map) => {
+            let mut arrays = map.values().filter_map(Value::as_array);
+            let rows = arrays.next()?;
+            arrays.next().is_none().then_some(rows)
+        }
+        _ => None,
+    }
+}
+
+// One column per key seen in the items, in the order they are first seen;
+// nested values are kept as compact JSON
+fn to_csv(value: &Value) -> Result<String, String> {
+    let rows = csv_rows(value).ok_or(
+        "csv output needs a list result, i.e. an array or an object with a single array field; use json instead",
+    )?;
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        if let Value::Object(map) = row {
+            for key in map.keys() {
+                if !columns.contains(&key.as_str()) {
+                    columns.push(key);
+                }
+            }
+        }
+    }
+    let mut lines = Vec::new();
+    if columns.is_empty() {
+        lines.push("value".to_string());
+        lines.extend(rows.iter().map(|row| csv_field(&scalar_text(row))));
+    } else {
+        lines.push(
+            columns
+                .iter()
+                .map(|c| csv_field(c))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        for row in rows {
+            let fields: Vec<String> = columns
+                .iter()
+                .map(|column| match row.get(column) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(value) => csv_field(&scalar_text(value)),
+                })
+                .collect();
+            lines.push(fields.join(","));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+
+    fn render(text: &str, format: ResultFormat) -> Result<String, ErrorData> {
+        let result = CallToolResult::success(vec![Content::text(text)]);
+        let result = render_result(result, format, Locale::Plain)?;
+        Ok(result.content[0].as_text().unwrap().text.clone())
+    }
+
+    #[test]
+    fn test_take_result_format() {
+        let mut args =
+            Some(serde_json::from_str(r#"{"txid":"ab","output_format":"csv"}"#).unwrap());
+        assert_eq!(take_result_format(&mut args).unwrap(), ResultFormat::Csv);
+        assert!(!args.as_ref().unwrap().contains_key(OUTPUT_FORMAT_ARG));
+        assert_eq!(take_result_format(&mut args).unwrap(), ResultFormat::Json);
+        assert_eq!(take_result_format(&mut None).unwrap(), ResultFormat::Json);
+        let mut args = Some(serde_json::from_str(r#"{"output_format":"xml"}"#).unwrap());
+        assert!(take_result_format(&mut args).is_err());
+    }
+
+    #[test]
+    fn test_render_result() {
+        let text = r#"{"txid":"ab","vout":[{"value":1000},{"value":2}]}"#;
+        assert_eq!(render(text, ResultFormat::Json).unwrap(), text);
+        assert!(
+            render(text, ResultFormat::PrettyJson)
+                .unwrap()
+                .contains("\n  \"txid\": \"ab\"")
+        );
+        assert_eq!(
+            render(text, ResultFormat::TextSummary).unwrap(),
+            "txid: ab\nvout[0].value: 1000\nvout[1].value: 2"
+        );
+        // Text results are left alone
+        assert_eq!(render("840000", ResultFormat::Csv).unwrap(), "840000");
+    }
+
+    #[test]
+    fn test_locale() {
+        assert_eq!(Locale::parse("en_GB"), Some(Locale::EnUs));
+        assert_eq!(Locale::parse("es-ES"), Some(Locale::DeDe));
+        assert_eq!(Locale::parse("xx"), None);
+        assert_eq!(Locale::Plain.number("1234567.5"), "1234567.5");
+        assert_eq!(Locale::EnUs.number("1234567.5"), "1,234,567.5");
+        assert_eq!(Locale::DeDe.number("-1234567"), "-1.234.567");
+        assert_eq!(Locale::FrFr.number("123.25"), "123,25");
+        assert_eq!(Locale::EnUs.number("1e21"), "1e21");
+        let result = CallToolResult::success(vec![Content::text(r#"{"value":1234,"txid":"ab"}"#)]);
+        let result = render_result(result, ResultFormat::TextSummary, Locale::DeDe).unwrap();
+        assert_eq!(
+            result.content[0].as_text().unwrap().text,
+            "txid: ab\nvalue: 1.234"
+        );
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let utxos =
+            r#"[{"txid":"aa","vout":0,"status":{"confirmed":true}},{"txid":"b,b","value":5}]"#;
+        assert_eq!(
+            render(utxos, ResultFormat::Csv).unwrap(),
+            "status,txid,vout,value\n\"{\"\"confirmed\"\":true}\",aa,0,\n,\"b,b\",,5"
+        );
+        assert_eq!(
+            render(r#"{"count":2,"txids":["aa","bb"]}"#, ResultFormat::Csv).unwrap(),
+            "value\naa\nbb"
+        );
+        let err = render(r#"{"a":[1],"b":[2]}"#, ResultFormat::Csv).unwrap_err();
+        assert!(err.message.contains("single array field"));
+    }
+}
@@ -0,0 +1,95 @@
+// In-memory usage metrics, reset when the server restarts
+use rmcp::serde_json::{self, Value, json};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Upper bounds of the latency histogram buckets, in milliseconds
+const LATENCY_BUCKETS_MS: [u64; 9] = [10, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+#[derive(Default)]
+struct ToolStats {
+    calls: u64,
+    errors: u64,
+    total_latency: Duration,
+    // One count per bucket, plus a last one for slower calls
+    latency_buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+pub struct ServerStats {
+    started: Instant,
+    tools: Mutex<BTreeMap<&'static str, ToolStats>>,
+}
+
+impl ServerStats {
+    pub fn new() -> Self {
+        ServerStats {
+            started: Instant::now(),
+            tools: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn record(&self, tool: &'static str, latency: Duration, failed: bool) {
+        let mut tools = self.tools.lock().unwrap();
+        let stats = tools.entry(tool).or_default();
+        stats.calls += 1;
+        if failed {
+            stats.errors += 1;
+        }
+        stats.total_latency += latency;
+        let ms = latency.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        stats.latency_buckets[bucket] += 1;
+    }
+
+    pub fn snapshot(&self) -> Value {
+        let tools = self.tools.lock().unwrap();
+        let per_tool: serde_json::Map<String, Value> = tools
+            .iter()
+            .map(|(name, stats)| {
+                let buckets: serde_json::Map<String, Value> = LATENCY_BUCKETS_MS
+                    .iter()
+                    .map(|bound| format!("le_{bound}ms"))
+                    .chain(std::iter::once("le_inf".to_string()))
+                    .zip(stats.latency_buckets.iter().map(|count| json!(count)))
+                    .collect();
+                let mean_ms = stats.total_latency.as_millis() as u64 / stats.calls.max(1);
+                let entry = json!({
+                    "calls": stats.calls,
+                    "errors": stats.errors,
+                    "mean_latency_ms": mean_ms,
+                    "latency_histogram": buckets,
+                });
+                (name.to_string(), entry)
+            })
+            .collect();
+        json!({
+            "uptime_secs": self.started.elapsed().as_secs(),
+            "total_calls": tools.values().map(|s| s.calls).sum::<u64>(),
+            "total_errors": tools.values().map(|s| s.errors).sum::<u64>(),
+            "tools": per_tool,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_snapshot() {
+        let stats = ServerStats::new();
+        stats.record("get_bitcoin_tx", Duration::from_millis(5), false);
+        stats.record("get_bitcoin_tx", Duration::from_millis(300), true);
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot["total_calls"], 2);
+        assert_eq!(snapshot["total_errors"], 1);
+        let tool = &snapshot["tools"]["get_bitcoin_tx"];
+        assert_eq!(tool["latency_histogram"]["le_10ms"], 1);
+        assert_eq!(tool["latency_histogram"]["le_500ms"], 1);
+        assert_eq!(tool["latency_histogram"]["le_inf"], 0);
+    }
+}
@@ -0,0 +1,130 @@
+// Stdio transport: one JSON-RPC message per line on stdin and stdout.
+//
+// rmcp's own stdio transport ends the session on the first line it can't
+// decode. Here such a line is answered with a JSON-RPC error instead, and the
+// following lines are served as usual.
+use crate::MyServer;
+use futures::channel::mpsc;
+use futures::future::ready;
+use futures::{SinkExt, StreamExt};
+use rmcp::ServiceExt;
+use rmcp::model::{ClientJsonRpcMessage, ServerJsonRpcMessage};
+use rmcp::serde_json::{self, Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+
+pub async fn serve(server: MyServer) -> anyhow::Result<()> {
+    // A single writer, so that responses and decode errors never interleave
+    let (lines, mut outgoing) = mpsc::unbounded::<String>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(line) = outgoing.next().await {
+            stdout.write_all(line.as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+        Ok::<_, std::io::Error>(())
+    });
+    let sink =
+        lines
+            .clone()
+            .sink_map_err(std::io::Error::other)
+            .with(|message: ServerJsonRpcMessage| {
+                ready(serde_json::to_string(&message).map_err(std::io::Error::other))
+            });
+    // End of input, or a failure reading it, ends the session
+    let incoming = futures::stream::unfold(
+        BufReader::new(tokio::io::stdin()),
+        |mut reader| async move {
+            let mut line = Vec::new();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) => None,
+                Ok(_) => Some((line, reader)),
+                Err(e) => {
+                    tracing::error!("Error reading from stdin: {e}");
+                    None
+                }
+            }
+        },
+    );
+    let stream = incoming.filter_map(move |line| {
+        ready(match decode_line(&line) {
+            Ok(message) => message,
+            Err(reply) => {
+                let _ = lines.unbounded_send(reply.to_string());
+                None
+            }
+        })
+    });
+    let running = server.serve((sink, Box::pin(stream))).await?;
+    running.waiting().await?;
+    // Every sender is gone with the session: the writer ends once it has
+    // written out what is left
+    writer.await??;
+    Ok(())
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+// The message carried by a line (or a WebSocket frame), None for a line to
+// skip, or the error response to send back
+pub(crate) fn decode_line(line: &[u8]) -> Result<Option<ClientJsonRpcMessage>, Value> {
+    if line.trim_ascii().is_empty() {
+        return Ok(None);
+    }
+    let value: Value = serde_json::from_slice(line).map_err(|e| {
+        tracing::warn!("Received a line that is not valid JSON: {e}");
+        error_response(Value::Null, PARSE_ERROR, format!("Parse error: {e}"))
+    })?;
+    match serde_json::from_value(value.clone()) {
+        Ok(message) => Ok(Some(message)),
+        Err(e) => match value
+            .get("id")
+            .filter(|id| id.is_string() || id.is_number())
+        {
+            Some(id) => Err(error_response(
+                id.clone(),
+                INVALID_REQUEST,
+                format!("Invalid Request: {e}"),
+            )),
+            // Notifications get no response, even an error one
+            None => {
+                tracing::warn!("Ignoring a message that is not a valid JSON-RPC message: {e}");
+                Ok(None)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_line() {
+        let err = decode_line(br#"{"jsonrpc":"2.0","id":1,"meth"#).unwrap_err();
+        assert_eq!(err["error"]["code"], PARSE_ERROR);
+        assert_eq!(err["id"], Value::Null);
+
+        let err = decode_line(br#"{"jsonrpc":"2.0","id":7,"method":42}"#).unwrap_err();
+        assert_eq!(err["error"]["code"], INVALID_REQUEST);
+        assert_eq!(err["id"], 7);
+
+        assert!(decode_line(b"  \n").unwrap().is_none());
+        assert!(
+            decode_line(br#"{"jsonrpc":"2.0","method":42}"#)
+                .unwrap()
+                .is_none()
+        );
+        let message = decode_line(br#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#);
+        assert!(message.unwrap().is_some());
+    }
+}
@@ -0,0 +1,1507 @@
+// Address tools
+use super::{
+    AMOUNTS_IN_BTC_DESCRIPTION, BatchResults, FORMAT_TIMESTAMP_DESCRIPTION, ToolDef, check_cap,
+    hex_encode, make_schema, optional_hex_id, page_info, parse_params, text_result,
+    with_btc_amounts, with_utc_timestamps,
+};
+use crate::config::Chain;
+use crate::esplora::{ADDRESS_CHAIN_TXS_PAGE_SIZE, BitcoinNetwork, EsploraClient, fetch_json};
+use rmcp::model::{CallToolResult, Content, ErrorData};
+use rmcp::schemars;
+use rmcp::serde_json::{self, Value, json};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+pub(super) const TOOLS: &[ToolDef] = &[
+    ToolDef {
+        name: "validate_bitcoin_address",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a string is a valid Bitcoin address, locally without any network call. Returns valid (bool), the address type (p2pkh, p2sh, p2wpkh, p2wsh, p2tr), and the network(s) it belongs to, or valid: false with a reason.",
+        schema: make_schema::<ValidateBitcoinAddressParams>,
+        handler: |_server, args| {
+            let params: ValidateBitcoinAddressParams = parse_params(args)?;
+            let result = validate_address(&params.address, params.network);
+            Ok(CallToolResult::success(vec![Content::text(
+                result.to_string(),
+            )]))
+        },
+    },
+    ToolDef {
+        name: "address_to_scripthash",
+        chain: Some(Chain::Bitcoin),
+        description: "Convert a Bitcoin address to its Electrum-style scripthash (SHA256 of the output script, byte-reversed), as used by the Esplora /scripthash endpoints. Computed locally without any network call.",
+        schema: make_schema::<AddressToScripthashParams>,
+        handler: |_server, args| {
+            let params: AddressToScripthashParams = parse_params(args)?;
+            let result = address_to_scripthash(&params.address)
+                .map_err(|e| ErrorData::invalid_request(e, None))?;
+            Ok(CallToolResult::success(vec![Content::text(result)]))
+        },
+    },
+    ToolDef {
+        name: "address_to_script",
+        chain: Some(Chain::Bitcoin),
+        description: "Convert a Bitcoin address to its output script (scriptPubKey), as hex and as asm, e.g. to build an output or compare with the scriptpubkey of a transaction output. Computed locally without any network call. Fails if the address is not valid for the given network.",
+        schema: make_schema::<AddressToScriptParams>,
+        handler: |_server, args| {
+            let params: AddressToScriptParams = parse_params(args)?;
+            let result = address_to_script(&params.address, params.network)
+                .map_err(|e| ErrorData::invalid_request(e, None))?;
+            Ok(CallToolResult::success(vec![Content::text(result)]))
+        },
+    },
+    ToolDef {
+        name: "parse_bitcoin_uri",
+        chain: Some(Chain::Bitcoin),
+        description: "Parse a BIP21 payment URI such as 'bitcoin:bc1q...?amount=0.001&label=Shop', locally without any network call. Returns the address, the requested amount in satoshis (or null when the URI has none), the label and message, and any other parameters. Fails on a scheme other than bitcoin:, an invalid address or amount, or an unknown required (req-) parameter.",
+        schema: make_schema::<ParseBitcoinUriParams>,
+        handler: |_server, args| {
+            let params: ParseBitcoinUriParams = parse_params(args)?;
+            let result =
+                parse_bitcoin_uri(&params.uri).map_err(|e| ErrorData::invalid_request(e, None))?;
+            Ok(CallToolResult::success(vec![Content::text(result)]))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_txs",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the transaction history of a Bitcoin address, newest first, one page at a time: the first page holds its unconfirmed transactions followed by the latest 25 confirmed ones, as returned by Esplora; pass page_info.next_cursor back as after_txid for the next 25 older confirmed ones, until page_info.has_more is false. Returns the address, the txs, and page_info. Set confirmed_only to drop the unconfirmed (mempool) transactions and keep only settled history. An address without any (matching) transaction is not an error: it returns an object with found: true, tx_count: 0, an empty txs array, and a note.",
+        schema: make_schema::<GetBitcoinAddressTxsParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressTxsParams = parse_params(args)?;
+            parse_address(&params.address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(with_utc_timestamps(
+                with_btc_amounts(
+                    fetch_address_txs(
+                        server.client.as_ref(),
+                        &params.network.api_base(),
+                        params.address.trim(),
+                        params.confirmed_only,
+                        params.after_txid.as_deref(),
+                    ),
+                    params.amounts_in_btc,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_history_summary",
+        chain: Some(Chain::Bitcoin),
+        description: "Get a balance-over-time timeline of a Bitcoin address, e.g. for charts. Returns one entry per transaction, oldest first, with its timestamp (block time, null when unconfirmed), the net effect on the address (delta_sat: outputs to it minus inputs spending from it), and the balance after it. Balances are anchored on the current balance, so they are exact, but only the first page of history is covered (unconfirmed transactions plus the latest 25 confirmed ones): complete is false when older transactions were left out.",
+        schema: make_schema::<GetBitcoinAddressHistorySummaryParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressHistorySummaryParams = parse_params(args)?;
+            parse_address(&params.address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(with_utc_timestamps(
+                with_btc_amounts(
+                    fetch_address_history_summary(
+                        server.client.as_ref(),
+                        &params.network.api_base(),
+                        params.address.trim(),
+                    ),
+                    params.amounts_in_btc,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_overview",
+        chain: Some(Chain::Bitcoin),
+        description: "Get an overview of a Bitcoin address in one call: its balance and the summary of its recent transactions, fetched concurrently. Returns confirmed_sat (balance in the chain), pending_sat (net effect of its mempool transactions, possibly negative), tx_count, and recent_txs: the first page of history (unconfirmed transactions plus the latest 25 confirmed ones), newest first, each with its txid, confirmed status, block_height, block_time, and delta_sat (net effect on the address). more_txs is true when older transactions were left out.",
+        schema: make_schema::<GetBitcoinAddressOverviewParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressOverviewParams = parse_params(args)?;
+            parse_address(&params.address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(with_utc_timestamps(
+                with_btc_amounts(
+                    fetch_address_overview(
+                        server.client.as_ref(),
+                        &params.network.api_base(),
+                        params.address.trim(),
+                    ),
+                    params.amounts_in_btc,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_reuse",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a Bitcoin address is reused, a privacy leak linking all its payments together. Reuse is defined simply: the address appears in more than one confirmed transaction (chain_stats.tx_count > 1), whether it received several payments or sent change back to itself. Returns reused (bool), the confirmed tx_count, funded_txo_count (outputs received), spent_txo_count (outputs spent), and mempool_tx_count, which doesn't count towards reuse until confirmed.",
+        schema: make_schema::<GetBitcoinAddressReuseParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressReuseParams = parse_params(args)?;
+            parse_address(&params.address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(fetch_address_reuse(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.address.trim(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_spends",
+        chain: Some(Chain::Bitcoin),
+        description: "Trace what happened to the outputs received by a Bitcoin address: which are still unspent and which transaction spent the others. Looks at the outputs of its recent transactions (unconfirmed ones plus the latest 25 confirmed) and at its UTXOs. Warning: unspent outputs are known from the UTXO set, but every spent one costs a request of its own, made concurrently, up to the configured maximum (MAX_SPEND_LOOKUPS): truncated is true when outputs were left unexamined. Returns one item per output, keyed by txid:vout, with its value and spent status, and for a spent output the spending txid, input index, and whether the spend is confirmed.",
+        schema: make_schema::<GetBitcoinAddressSpendsParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressSpendsParams = parse_params(args)?;
+            parse_address(&params.address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(with_btc_amounts(
+                fetch_address_spends(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    params.address.trim(),
+                    server.config.max_spend_lookups,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_utxo_summary",
+        chain: Some(Chain::Bitcoin),
+        description: "Summarize the UTXOs of a Bitcoin address without listing them, e.g. to plan coin selection or consolidation: utxo_count, total_sat (split into confirmed_sat and unconfirmed_sat), largest_sat and smallest_sat, and a histogram of UTXO sizes. Buckets: dust (below the dust threshold of the address's script type, e.g. 294 sats for p2wpkh, costing more to spend than they are worth), small (below 100,000 sats), medium (below 10,000,000 sats), and large, each with its count and total_sat.",
+        schema: make_schema::<GetBitcoinAddressUtxoSummaryParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressUtxoSummaryParams = parse_params(args)?;
+            parse_address(&params.address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(with_btc_amounts(
+                fetch_address_utxo_summary(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    params.address.trim(),
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_balances",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the balances of several Bitcoin addresses at once, e.g. for a portfolio view. Addresses are looked up concurrently. Returns one item per address, in order, with key set to the address and data holding confirmed_sat (balance in the chain) pending_sat (net effect of its mempool transactions, possibly negative), and tx_count (0 for an address never used). An invalid address or failed lookup is reported inline with ok: false instead of failing the whole call.",
+        schema: make_schema::<GetBitcoinAddressBalancesParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressBalancesParams = parse_params(args)?;
+            check_cap(
+                "Address count",
+                params.addresses.len() as u64,
+                server.config.max_batch_addresses as u64,
+            )?;
+            text_result(with_btc_amounts(
+                fetch_address_balances(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.addresses,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_address_first_tx",
+        chain: Some(Chain::Bitcoin),
+        description: "Find when a Bitcoin address was first used: pages back through its confirmed history, 25 transactions per request, to the oldest confirmed transaction. Returns its txid, block_height, and block_time, or found: false if the address has no confirmed transactions. Long histories are cut at the configured maximum number of pages (MAX_HISTORY_PAGES): reached_first is false when the transaction returned is only the oldest one seen before hitting it.",
+        schema: make_schema::<GetBitcoinAddressFirstTxParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAddressFirstTxParams = parse_params(args)?;
+            parse_address(&params.address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(with_utc_timestamps(
+                fetch_address_first_tx(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    params.address.trim(),
+                    server.config.max_history_pages,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "find_common_txs",
+        chain: Some(Chain::Bitcoin),
+        description: "Find the transactions two Bitcoin addresses have in common, e.g. as a building block for address clustering. Returns the txids appearing in both address histories. Only the first page of each history is compared (the latest 25 confirmed transactions plus unconfirmed ones), so older common transactions are not found.",
+        schema: make_schema::<FindCommonTxsParams>,
+        handler: |server, args| {
+            let params: FindCommonTxsParams = parse_params(args)?;
+            for address in [&params.address_a, &params.address_b] {
+                parse_address(address).map_err(|e| ErrorData::invalid_request(e, None))?;
+            }
+            text_result(find_common_txs(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.address_a.trim(),
+                params.address_b.trim(),
+            ))
+        },
+    },
+];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ValidateBitcoinAddressParams {
+    #[schemars(description = "The address string to validate.")]
+    address: String,
+    #[schemars(
+        description = "Optional network the address must belong to: 'mainnet', 'testnet', or 'signet'. If omitted, any network is accepted."
+    )]
+    #[serde(default)]
+    network: Option<BitcoinNetwork>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct AddressToScripthashParams {
+    #[schemars(description = "The Bitcoin address to convert.")]
+    address: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct AddressToScriptParams {
+    #[schemars(description = "The Bitcoin address to convert.")]
+    address: String,
+    #[schemars(
+        description = "The Bitcoin network the address must belong to: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ParseBitcoinUriParams {
+    #[schemars(description = "The BIP21 URI, starting with 'bitcoin:'.")]
+    uri: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressTxsParams {
+    #[schemars(description = "The Bitcoin address to look up.")]
+    address: String,
+    #[schemars(
+        description = "Only return confirmed transactions, leaving out those still in the mempool (default false)."
+    )]
+    #[serde(default)]
+    confirmed_only: bool,
+    #[schemars(
+        description = "Cursor for the next page: the page_info.next_cursor of the previous page, i.e. its last confirmed txid. Omit it for the first page."
+    )]
+    #[serde(default, deserialize_with = "optional_hex_id")]
+    after_txid: Option<String>,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressHistorySummaryParams {
+    #[schemars(description = "The Bitcoin address to summarize.")]
+    address: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressOverviewParams {
+    #[schemars(description = "The Bitcoin address to look up.")]
+    address: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressReuseParams {
+    #[schemars(description = "The Bitcoin address to check.")]
+    address: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressSpendsParams {
+    #[schemars(description = "The Bitcoin address to trace.")]
+    address: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressUtxoSummaryParams {
+    #[schemars(description = "The Bitcoin address to summarize.")]
+    address: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressBalancesParams {
+    #[schemars(description = "The Bitcoin addresses to look up.")]
+    addresses: Vec<String>,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAddressFirstTxParams {
+    #[schemars(description = "The Bitcoin address to look up.")]
+    address: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FindCommonTxsParams {
+    #[schemars(description = "The first Bitcoin address.")]
+    address_a: String,
+    #[schemars(description = "The second Bitcoin address.")]
+    address_b: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+// Validate an address locally, reporting invalid input as `valid: false` rather than an error
+fn validate_address(address: &str, network: Option<BitcoinNetwork>) -> Value {
+    use std::str::FromStr;
+    let unchecked = match bitcoin::Address::from_str(address.trim()) {
+        Ok(unchecked) => unchecked,
+        Err(e) => return json!({ "valid": false, "reason": e.to_string() }),
+    };
+    let networks: Vec<&str> = [
+        BitcoinNetwork::Mainnet,
+        BitcoinNetwork::Testnet,
+        BitcoinNetwork::Signet,
+    ]
+    .into_iter()
+    .filter(|n| unchecked.is_valid_for_network(n.to_bitcoin_network()))
+    .map(|n| n.as_str())
+    .collect();
+    if let Some(required) = network {
+        if !networks.contains(&required.as_str()) {
+            return json!({
+                "valid": false,
+                "reason": format!("Address is not valid for {}", required.as_str()),
+                "networks": networks,
+            });
+        }
+    }
+    let address_type = unchecked
+        .assume_checked_ref()
+        .address_type()
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    json!({
+        "valid": !networks.is_empty(),
+        "type": address_type,
+        // Testnet and signet share the same address encoding
+        "network": networks.first(),
+        "networks": networks,
+    })
+}
+
+// Parse an address of any Bitcoin network
+pub(super) fn parse_address(address: &str) -> Result<bitcoin::Address, String> {
+    use std::str::FromStr;
+    bitcoin::Address::from_str(address.trim())
+        .map(|unchecked| unchecked.assume_checked())
+        .map_err(|e| format!("Invalid address '{address}': {e}"))
+}
+
+// Electrum-style scripthash: the SHA256 of the output script, byte-reversed
+fn address_to_scripthash(address: &str) -> Result<String, String> {
+    use bitcoin::hashes::{Hash, sha256};
+    let script = parse_address(address)?.script_pubkey();
+    let mut hash = sha256::Hash::hash(script.as_bytes()).to_byte_array();
+    hash.reverse();
+    let result = json!({
+        "address": address.trim(),
+        "scriptpubkey": hex_encode(script.as_bytes()),
+        "scripthash": hex_encode(&hash),
+    });
+    Ok(result.to_string())
+}
+
+fn address_to_script(address: &str, network: BitcoinNetwork) -> Result<String, String> {
+    use std::str::FromStr;
+    let address = address.trim();
+    let checked = bitcoin::Address::from_str(address)
+        .map_err(|e| format!("Invalid address '{address}': {e}"))?
+        .require_network(network.to_bitcoin_network())
+        .map_err(|_| format!("Address '{address}' is not valid for {}", network.as_str()))?;
+    let script = checked.script_pubkey();
+    let result = json!({
+        "address": address,
+        "network": network.as_str(),
+        "type": checked.address_type().map(|t| t.to_string()),
+        "scriptpubkey": hex_encode(script.as_bytes()),
+        "scriptpubkey_asm": script.to_asm_string(),
+    });
+    Ok(result.to_string())
+}
+
+// Split a BIP21 URI into its address and its percent-decoded parameters
+fn parse_bitcoin_uri(uri: &str) -> Result<String, String> {
+    let uri = uri.trim();
+    let rest = uri
+        .get(.."bitcoin:".len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case("bitcoin:"))
+        .map(|scheme| &uri[scheme.len()..])
+        .ok_or_else(|| format!("Not a BIP21 URI, expected the bitcoin: scheme: '{uri}'"))?;
+    let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+    parse_address(address)?;
+
+    let mut amount_sat = None;
+    let mut label = None;
+    let mut message = None;
+    let mut other = serde_json::Map::new();
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "amount" => {
+                let amount = bitcoin::Amount::from_str_in(&value, bitcoin::Denomination::Bitcoin)
+                    .map_err(|e| format!("Invalid amount '{value}': {e}"))?;
+                amount_sat = Some(amount.to_sat());
+            }
+            "label" => label = Some(value.into_owned()),
+            "message" => message = Some(value.into_owned()),
+            // BIP21 requires rejecting URIs with required parameters we don't understand
+            key if key.starts_with("req-") => {
+                return Err(format!("Unsupported required parameter '{key}'"));
+            }
+            key => {
+                other.insert(key.to_string(), json!(value));
+            }
+        }
+    }
+    let result = json!({
+        "address": address,
+        "amount_sat": amount_sat,
+        "label": label,
+        "message": message,
+        "other_params": other,
+    });
+    Ok(result.to_string())
+}
+
+// The first page of an address history, newest first
+fn fetch_address_txs(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+    confirmed_only: bool,
+    after_txid: Option<&str>,
+) -> Result<String, String> {
+    let url = match after_txid {
+        Some(txid) => format!("{base_url}/address/{address}/txs/chain/{txid}"),
+        None => format!("{base_url}/address/{address}/txs"),
+    };
+    let mut txs = match fetch_json(client, &url)? {
+        Value::Array(txs) => txs,
+        _ => return Err("Address transactions response is not an array".to_string()),
+    };
+    if confirmed_only {
+        txs.retain(|tx| tx["status"]["confirmed"] == true);
+    }
+    // A full page of confirmed transactions may have older ones after it
+    let confirmed: Vec<&Value> = txs
+        .iter()
+        .filter(|tx| tx["status"]["confirmed"] == true)
+        .collect();
+    let next_cursor = match confirmed.last() {
+        Some(last) if confirmed.len() == ADDRESS_CHAIN_TXS_PAGE_SIZE => Some(last["txid"].clone()),
+        _ => None,
+    };
+    if txs.is_empty() && after_txid.is_none() {
+        let note = if confirmed_only {
+            "This address has no confirmed transactions yet"
+        } else {
+            NO_HISTORY_NOTE
+        };
+        let mut result = empty_history(address, note);
+        result["page_info"] = page_info(0, None);
+        return Ok(result.to_string());
+    }
+    let result = json!({
+        "address": address,
+        "page_info": page_info(txs.len(), next_cursor),
+        "txs": txs,
+    });
+    Ok(result.to_string())
+}
+
+const NO_HISTORY_NOTE: &str =
+    "This address has no transactions yet, which is normal for a fresh address";
+
+// Explicit result for an address without history, so that an empty list
+// isn't mistaken for a failed lookup: the address exists, it is just unused
+fn empty_history(address: &str, note: &str) -> Value {
+    json!({
+        "address": address,
+        "found": true,
+        "tx_count": 0,
+        "txs": [],
+        "note": note,
+    })
+}
+
+// Net amount a transaction moves to (positive) or from (negative) an address
+fn address_delta_sat(tx: &Value, address: &str) -> i64 {
+    let sum = |entries: &Value, pointer: &str| -> i64 {
+        entries
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.pointer(pointer))
+            .filter(|output| output["scriptpubkey_address"] == address)
+            .filter_map(|output| output["value"].as_i64())
+            .sum()
+    };
+    sum(&tx["vout"], "") - sum(&tx["vin"], "/prevout")
+}
+
+// Sum of the confirmed and mempool figures of an /address stats field
+fn address_stat(info: &Value, field: &str) -> i64 {
+    ["chain_stats", "mempool_stats"]
+        .iter()
+        .filter_map(|stats| info[stats][field].as_i64())
+        .sum()
+}
+
+// Walk the first page of history back from the current balance, so that
+// balances are right even though older transactions are missing
+fn fetch_address_history_summary(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+) -> Result<String, String> {
+    let info = fetch_json(client, &format!("{base_url}/address/{address}"))?;
+    let txs = fetch_json(client, &format!("{base_url}/address/{address}/txs"))?;
+    let txs = txs
+        .as_array()
+        .ok_or("Address transactions response is not an array")?;
+    let mut balance = address_stat(&info, "funded_txo_sum") - address_stat(&info, "spent_txo_sum");
+    let mut timeline = Vec::new();
+    for tx in txs {
+        let delta = address_delta_sat(tx, address);
+        timeline.push(json!({
+            "txid": tx["txid"],
+            "confirmed": tx["status"]["confirmed"],
+            "timestamp": tx["status"]["block_time"],
+            "delta_sat": delta,
+            "balance_sat": balance,
+        }));
+        balance -= delta;
+    }
+    timeline.reverse();
+    let tx_count = address_stat(&info, "tx_count");
+    let mut result = json!({
+        "address": address,
+        "tx_count": tx_count,
+        "complete": tx_count == txs.len() as i64,
+        // Balance before the oldest transaction listed
+        "starting_balance_sat": balance,
+        "timeline": timeline,
+    });
+    if tx_count == 0 {
+        result["note"] = json!(NO_HISTORY_NOTE);
+    }
+    Ok(result.to_string())
+}
+
+fn fetch_address_balance(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+) -> Result<Value, String> {
+    parse_address(address)?;
+    let info = fetch_json(client, &format!("{base_url}/address/{}", address.trim()))?;
+    Ok(address_balance(&info))
+}
+
+// Confirmed and pending balances of an /address response
+fn address_balance(info: &Value) -> Value {
+    let balance = |stats: &str| {
+        let stat = |field: &str| info[stats][field].as_i64().unwrap_or(0);
+        stat("funded_txo_sum") - stat("spent_txo_sum")
+    };
+    json!({
+        "confirmed_sat": balance("chain_stats"),
+        "pending_sat": balance("mempool_stats"),
+        "tx_count": address_stat(info, "tx_count"),
+    })
+}
+
+fn fetch_address_reuse(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+) -> Result<String, String> {
+    let info = fetch_json(client, &format!("{base_url}/address/{address}"))?;
+    let chain = |field: &str| info["chain_stats"][field].as_u64().unwrap_or(0);
+    let result = json!({
+        "address": address,
+        "reused": chain("tx_count") > 1,
+        "tx_count": chain("tx_count"),
+        "funded_txo_count": chain("funded_txo_count"),
+        "spent_txo_count": chain("spent_txo_count"),
+        "mempool_tx_count": info["mempool_stats"]["tx_count"].as_u64().unwrap_or(0),
+    });
+    Ok(result.to_string())
+}
+
+// Upper bounds, exclusive, of the small and medium UTXO buckets; dust
+// depends on the script type
+const SMALL_UTXO_MAX_SAT: u64 = 100_000;
+const MEDIUM_UTXO_MAX_SAT: u64 = 10_000_000;
+
+fn fetch_address_utxo_summary(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+) -> Result<String, String> {
+    let dust_threshold = parse_address(address)?
+        .script_pubkey()
+        .minimal_non_dust()
+        .to_sat();
+    let utxos = fetch_json(client, &format!("{base_url}/address/{address}/utxo"))?;
+    let utxos = utxos.as_array().ok_or("UTXO response is not an array")?;
+    // (name, lower bound, upper bound), value ranges as [min, max)
+    let bounds = [
+        ("dust", 0, Some(dust_threshold)),
+        ("small", dust_threshold, Some(SMALL_UTXO_MAX_SAT)),
+        ("medium", SMALL_UTXO_MAX_SAT, Some(MEDIUM_UTXO_MAX_SAT)),
+        ("large", MEDIUM_UTXO_MAX_SAT, None),
+    ];
+    let mut buckets = [(0usize, 0u64); 4];
+    let (mut confirmed, mut unconfirmed) = (0u64, 0u64);
+    let mut values = Vec::new();
+    for utxo in utxos {
+        let value = utxo["value"].as_u64().ok_or("UTXO has no value")?;
+        values.push(value);
+        if utxo["status"]["confirmed"] == true {
+            confirmed += value;
+        } else {
+            unconfirmed += value;
+        }
+        let bucket = bounds
+            .iter()
+            .position(|(_, _, max)| max.is_none_or(|max| value < max))
+            .unwrap_or(bounds.len() - 1);
+        buckets[bucket].0 += 1;
+        buckets[bucket].1 += value;
+    }
+    let histogram: Vec<Value> = bounds
+        .iter()
+        .zip(buckets)
+        .map(|((name, min, max), (count, total))| {
+            json!({
+                "bucket": name,
+                "min_sat": min,
+                "max_sat": max,
+                "count": count,
+                "total_sat": total,
+            })
+        })
+        .collect();
+    let result = json!({
+        "address": address,
+        "utxo_count": values.len(),
+        "total_sat": confirmed + unconfirmed,
+        "confirmed_sat": confirmed,
+        "unconfirmed_sat": unconfirmed,
+        "largest_sat": values.iter().max(),
+        "smallest_sat": values.iter().min(),
+        "dust_threshold_sat": dust_threshold,
+        "histogram": histogram,
+    });
+    Ok(result.to_string())
+}
+
+// Fetch two JSON documents concurrently
+fn fetch_json_pair(
+    client: &dyn EsploraClient,
+    url_a: &str,
+    url_b: &str,
+) -> (Result<Value, String>, Result<Value, String>) {
+    let fetch = |url: &str| {
+        let span = tracing::Span::current();
+        let url = url.to_string();
+        move || {
+            let _span = span.entered();
+            fetch_json(client, &url)
+        }
+    };
+    std::thread::scope(|scope| {
+        let a = scope.spawn(fetch(url_a));
+        let b = scope.spawn(fetch(url_b));
+        let join = |handle: std::thread::ScopedJoinHandle<'_, Result<Value, String>>| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()))
+        };
+        (join(a), join(b))
+    })
+}
+
+// Spender of an output, from /tx/{txid}/outspend/{vout}
+fn fetch_spend(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    outpoint: &str,
+) -> Result<Value, String> {
+    let (txid, vout) = outpoint.split_once(':').ok_or("Invalid outpoint")?;
+    let outspend = fetch_json(client, &format!("{base_url}/tx/{txid}/outspend/{vout}"))?;
+    if outspend["spent"] != true {
+        return Ok(json!({ "spent": false }));
+    }
+    Ok(json!({
+        "spent": true,
+        "spending_txid": outspend["txid"],
+        "spending_vin": outspend["vin"],
+        "spending_confirmed": outspend["status"]["confirmed"],
+    }))
+}
+
+// Outputs of the recent history that are still in the UTXO set need no
+// lookup, the others are followed to their spender
+fn fetch_address_spends(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+    max_lookups: usize,
+) -> Result<String, String> {
+    let (utxos, txs) = fetch_json_pair(
+        client,
+        &format!("{base_url}/address/{address}/utxo"),
+        &format!("{base_url}/address/{address}/txs"),
+    );
+    let (utxos, txs) = (utxos?, txs?);
+    let utxos = utxos.as_array().ok_or("UTXO response is not an array")?;
+    let txs = txs
+        .as_array()
+        .ok_or("Address transactions response is not an array")?;
+    let outpoint = |txid: &Value, vout: &Value| format!("{}:{}", txid.as_str().unwrap_or(""), vout);
+    let unspent: HashSet<String> = utxos
+        .iter()
+        .map(|utxo| outpoint(&utxo["txid"], &utxo["vout"]))
+        .collect();
+
+    // Every output received in the recent history, newest first
+    let mut outputs: Vec<(String, Value)> = Vec::new();
+    for tx in txs {
+        for (vout, output) in tx["vout"].as_array().into_iter().flatten().enumerate() {
+            if output["scriptpubkey_address"] == address {
+                outputs.push((outpoint(&tx["txid"], &json!(vout)), output["value"].clone()));
+            }
+        }
+    }
+    let seen: HashSet<String> = outputs.iter().map(|(key, _)| key.clone()).collect();
+    // Older UTXOs, received before the recent history
+    outputs.extend(
+        utxos
+            .iter()
+            .map(|utxo| {
+                (
+                    outpoint(&utxo["txid"], &utxo["vout"]),
+                    utxo["value"].clone(),
+                )
+            })
+            .filter(|(key, _)| !seen.contains(key)),
+    );
+
+    let to_lookup: Vec<&str> = outputs
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .filter(|key| !unspent.contains(*key))
+        .take(max_lookups)
+        .collect();
+    let spends: Vec<Result<Value, String>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = to_lookup
+            .iter()
+            .map(|&outpoint| {
+                let span = tracing::Span::current();
+                scope.spawn(move || {
+                    let _span = span.entered();
+                    fetch_spend(client, base_url, outpoint)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()))
+            })
+            .collect()
+    });
+    let mut spends: HashMap<&str, Result<Value, String>> =
+        to_lookup.iter().copied().zip(spends).collect();
+
+    let mut results = BatchResults::default();
+    let mut not_examined = 0;
+    for (key, value) in &outputs {
+        let spend = if unspent.contains(key) {
+            Ok(json!({ "spent": false }))
+        } else {
+            match spends.remove(key.as_str()) {
+                Some(spend) => spend,
+                None => {
+                    not_examined += 1;
+                    continue;
+                }
+            }
+        };
+        results.push(
+            key.as_str(),
+            spend.map(|mut spend| {
+                spend["value"] = value.clone();
+                spend
+            }),
+        );
+    }
+    results.check_any_succeeded()?;
+    let spent = results
+        .items
+        .iter()
+        .filter(|item| item["data"]["spent"] == true)
+        .count();
+    let result = json!({
+        "address": address,
+        "summary": results.summary(),
+        "spent_count": spent,
+        "unspent_count": unspent.len(),
+        "truncated": not_examined > 0,
+        "not_examined": not_examined,
+        "outputs": results.items,
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_address_overview(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+) -> Result<String, String> {
+    let (info, txs) = fetch_json_pair(
+        client,
+        &format!("{base_url}/address/{address}"),
+        &format!("{base_url}/address/{address}/txs"),
+    );
+    let info = info?;
+    let txs = txs?;
+    let txs = txs
+        .as_array()
+        .ok_or("Address transactions response is not an array")?;
+    let recent_txs: Vec<Value> = txs
+        .iter()
+        .map(|tx| {
+            json!({
+                "txid": tx["txid"],
+                "confirmed": tx["status"]["confirmed"],
+                "block_height": tx["status"]["block_height"],
+                "block_time": tx["status"]["block_time"],
+                "delta_sat": address_delta_sat(tx, address),
+            })
+        })
+        .collect();
+    let mut result = address_balance(&info);
+    let tx_count = address_stat(&info, "tx_count");
+    result["address"] = json!(address);
+    result["more_txs"] = json!(tx_count > recent_txs.len() as i64);
+    result["recent_txs"] = json!(recent_txs);
+    if tx_count == 0 {
+        result["note"] = json!(NO_HISTORY_NOTE);
+    }
+    Ok(result.to_string())
+}
+
+fn fetch_address_balances(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    addresses: &[String],
+) -> Result<String, String> {
+    let results: Vec<Result<Value, String>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = addresses
+            .iter()
+            .map(|address| {
+                let span = tracing::Span::current();
+                scope.spawn(move || {
+                    let _span = span.entered();
+                    fetch_address_balance(client, base_url, address)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()))
+            })
+            .collect()
+    });
+    let mut balances = BatchResults::default();
+    for (address, result) in addresses.iter().zip(results) {
+        balances.push(address.as_str(), result);
+    }
+    balances.check_any_succeeded()?;
+    let result = json!({
+        "summary": balances.summary(),
+        "balances": balances.items,
+    });
+    Ok(result.to_string())
+}
+
+// Follow the confirmed history, newest first, from each page's last txid
+// until a short page shows there is nothing older
+fn fetch_address_first_tx(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+    max_pages: usize,
+) -> Result<String, String> {
+    let mut oldest: Option<Value> = None;
+    let mut pages_fetched = 0;
+    let mut reached_first = false;
+    while pages_fetched < max_pages {
+        let url = match oldest.as_ref().and_then(|tx| tx["txid"].as_str()) {
+            Some(cursor) => format!("{base_url}/address/{address}/txs/chain/{cursor}"),
+            None => format!("{base_url}/address/{address}/txs/chain"),
+        };
+        let page = match fetch_json(client, &url)? {
+            Value::Array(page) => page,
+            _ => return Err("Address transactions response is not an array".to_string()),
+        };
+        pages_fetched += 1;
+        let full = page.len() == ADDRESS_CHAIN_TXS_PAGE_SIZE;
+        if let Some(tx) = page.into_iter().last() {
+            oldest = Some(tx);
+        }
+        if !full {
+            reached_first = true;
+            break;
+        }
+    }
+    let mut result = json!({
+        "address": address,
+        "found": oldest.is_some(),
+        "reached_first": reached_first,
+        "pages_fetched": pages_fetched,
+    });
+    if let Some(tx) = oldest {
+        result["txid"] = tx["txid"].clone();
+        result["block_height"] = tx["status"]["block_height"].clone();
+        result["block_time"] = tx["status"]["block_time"].clone();
+    }
+    Ok(result.to_string())
+}
+
+// Txids of the first page of an address history, newest first
+fn fetch_address_txids(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address: &str,
+) -> Result<Vec<String>, String> {
+    let txs = fetch_json(client, &format!("{base_url}/address/{address}/txs"))?;
+    let txs = txs
+        .as_array()
+        .ok_or("Address transactions response is not an array")?;
+    Ok(txs
+        .iter()
+        .filter_map(|tx| tx["txid"].as_str().map(str::to_string))
+        .collect())
+}
+
+fn find_common_txs(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    address_a: &str,
+    address_b: &str,
+) -> Result<String, String> {
+    let span = tracing::Span::current();
+    let (txids_a, txids_b) = std::thread::scope(|scope| {
+        let a = scope.spawn(|| span.in_scope(|| fetch_address_txids(client, base_url, address_a)));
+        let b = scope.spawn(|| span.in_scope(|| fetch_address_txids(client, base_url, address_b)));
+        let join = |handle: std::thread::ScopedJoinHandle<'_, _>| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()))
+        };
+        (join(a), join(b))
+    });
+    let (txids_a, txids_b) = (txids_a?, txids_b?);
+    let in_b: HashSet<&String> = txids_b.iter().collect();
+    let common: Vec<&String> = txids_a.iter().filter(|txid| in_b.contains(txid)).collect();
+    let result = json!({
+        "count": common.len(),
+        "common_txids": common,
+        "txs_checked": { address_a: txids_a.len(), address_b: txids_b.len() },
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esplora::testing::MockClient;
+
+    #[test]
+    fn test_validate_address() {
+        let result = validate_address("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq", None);
+        assert_eq!(result["valid"], true);
+        assert_eq!(result["type"], "p2wpkh");
+        assert_eq!(result["network"], "mainnet");
+
+        let result = validate_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", None);
+        assert_eq!(result["type"], "p2pkh");
+
+        let result = validate_address(
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+            Some(BitcoinNetwork::Mainnet),
+        );
+        assert_eq!(result["valid"], false);
+        assert_eq!(result["reason"], "Address is not valid for mainnet");
+
+        let result = validate_address("not an address", None);
+        assert_eq!(result["valid"], false);
+        assert!(result["reason"].is_string());
+    }
+
+    #[test]
+    fn test_parse_bitcoin_uri() {
+        let result = parse_bitcoin_uri(
+            "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=0.0015&label=Luke-Jr&message=Donation%20for%20project%20xyz&lightning=lnbc1",
+        )
+        .unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["address"],
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"
+        );
+        assert_eq!(result["amount_sat"], 150_000);
+        assert_eq!(result["label"], "Luke-Jr");
+        assert_eq!(result["message"], "Donation for project xyz");
+        assert_eq!(result["other_params"], json!({ "lightning": "lnbc1" }));
+
+        let result = parse_bitcoin_uri("BITCOIN:1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["amount_sat"], Value::Null);
+
+        assert!(
+            parse_bitcoin_uri("litecoin:1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2")
+                .unwrap_err()
+                .starts_with("Not a BIP21 URI")
+        );
+        assert!(
+            parse_bitcoin_uri("bitcoin:1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2?amount=1,5").is_err()
+        );
+        assert!(
+            parse_bitcoin_uri(
+                "bitcoin:1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2?req-somethingyoudontunderstand=50"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fetch_address_txs() {
+        let client = MockClient::default().with(
+            "http://esplora/address/1abc/txs",
+            r#"[{"txid":"02","status":{"confirmed":false}},{"txid":"01","status":{"confirmed":true}}]"#,
+        );
+        let all = fetch_address_txs(&client, "http://esplora", "1abc", false, None).unwrap();
+        let all: Value = serde_json::from_str(&all).unwrap();
+        assert_eq!(all["txs"][0]["txid"], "02");
+        assert_eq!(
+            all["page_info"],
+            json!({ "count": 2, "has_more": false, "next_cursor": null })
+        );
+        let confirmed = fetch_address_txs(&client, "http://esplora", "1abc", true, None).unwrap();
+        let confirmed: Value = serde_json::from_str(&confirmed).unwrap();
+        assert_eq!(
+            confirmed["txs"],
+            json!([{ "status": { "confirmed": true }, "txid": "01" }])
+        );
+
+        let client = MockClient::default().with("http://esplora/address/1new/txs", "[]");
+        let result = fetch_address_txs(&client, "http://esplora", "1new", false, None).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["found"], true);
+        assert_eq!(result["tx_count"], 0);
+        assert_eq!(result["txs"], json!([]));
+        assert_eq!(result["page_info"]["has_more"], false);
+    }
+
+    #[test]
+    fn test_fetch_address_txs_pages() {
+        let page = |from: usize| -> String {
+            let txs: Vec<Value> = (from..from + ADDRESS_CHAIN_TXS_PAGE_SIZE)
+                .map(|i| json!({ "txid": format!("t{i}"), "status": { "confirmed": true } }))
+                .collect();
+            Value::Array(txs).to_string()
+        };
+        let cursor = "ab".repeat(32);
+        let client = MockClient::default()
+            .with("http://esplora/address/1abc/txs", &page(0))
+            .with(
+                &format!("http://esplora/address/1abc/txs/chain/{cursor}"),
+                r#"[{"txid":"t25","status":{"confirmed":true}}]"#,
+            );
+        let result = fetch_address_txs(&client, "http://esplora", "1abc", false, None).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["page_info"]["has_more"], true);
+        assert_eq!(result["page_info"]["next_cursor"], "t24");
+        assert_eq!(result["page_info"]["count"], 25);
+
+        let result =
+            fetch_address_txs(&client, "http://esplora", "1abc", false, Some(&cursor)).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["txs"][0]["txid"], "t25");
+        assert_eq!(result["page_info"]["has_more"], false);
+        assert_eq!(result["page_info"]["next_cursor"], Value::Null);
+    }
+
+    #[test]
+    fn test_fetch_address_history_summary() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/address/1abc",
+                r#"{"chain_stats":{"funded_txo_sum":1500,"spent_txo_sum":1000,"tx_count":2},
+                    "mempool_stats":{"funded_txo_sum":0,"spent_txo_sum":0,"tx_count":0}}"#,
+            )
+            .with(
+                "http://esplora/address/1abc/txs",
+                r#"[
+                    {"txid":"02","status":{"confirmed":true,"block_time":200},
+                     "vin":[{"prevout":{"scriptpubkey_address":"1abc","value":1000}}],
+                     "vout":[{"scriptpubkey_address":"1xyz","value":400},{"scriptpubkey_address":"1abc","value":500}]},
+                    {"txid":"01","status":{"confirmed":true,"block_time":100},
+                     "vin":[{"prevout":{"scriptpubkey_address":"1xyz","value":2000}}],
+                     "vout":[{"scriptpubkey_address":"1abc","value":1000}]}
+                ]"#,
+            );
+        let result = fetch_address_history_summary(&client, "http://esplora", "1abc").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["complete"], true);
+        assert_eq!(result["starting_balance_sat"], 0);
+        let timeline = &result["timeline"];
+        assert_eq!(timeline[0]["txid"], "01");
+        assert_eq!(timeline[0]["delta_sat"], 1000);
+        assert_eq!(timeline[0]["balance_sat"], 1000);
+        assert_eq!(timeline[1]["delta_sat"], -500);
+        assert_eq!(timeline[1]["balance_sat"], 500);
+        assert_eq!(timeline[1]["timestamp"], 200);
+    }
+
+    #[test]
+    fn test_fetch_address_spends() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/address/1abc/utxo",
+                r#"[{"txid":"02","vout":0,"value":500},{"txid":"00","vout":3,"value":70}]"#,
+            )
+            .with(
+                "http://esplora/address/1abc/txs",
+                r#"[
+                    {"txid":"02","vout":[{"scriptpubkey_address":"1abc","value":500}]},
+                    {"txid":"01","vout":[{"scriptpubkey_address":"1xyz","value":1},
+                                         {"scriptpubkey_address":"1abc","value":1000},
+                                         {"scriptpubkey_address":"1abc","value":2000}]}
+                ]"#,
+            )
+            .with(
+                "http://esplora/tx/01/outspend/1",
+                r#"{"spent":true,"txid":"02","vin":0,"status":{"confirmed":true}}"#,
+            )
+            .with(
+                "http://esplora/tx/01/outspend/2",
+                r#"{"spent":true,"txid":"03","vin":1,"status":{"confirmed":false}}"#,
+            );
+        let result = fetch_address_spends(&client, "http://esplora", "1abc", 25).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["spent_count"], 2);
+        assert_eq!(result["unspent_count"], 2);
+        assert_eq!(result["truncated"], false);
+        let outputs = result["outputs"].as_array().unwrap();
+        let keys: Vec<&str> = outputs.iter().map(|o| o["key"].as_str().unwrap()).collect();
+        assert_eq!(keys, ["02:0", "01:1", "01:2", "00:3"]);
+        assert_eq!(outputs[0]["data"]["spent"], false);
+        assert_eq!(outputs[1]["data"]["spending_txid"], "02");
+        assert_eq!(outputs[1]["data"]["value"], 1000);
+        assert_eq!(outputs[2]["data"]["spending_confirmed"], false);
+        assert_eq!(outputs[3]["data"]["value"], 70);
+        // Unspent outputs cost no lookup
+        assert_eq!(client.requests().len(), 4);
+
+        let result = fetch_address_spends(&client, "http://esplora", "1abc", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["not_examined"], 1);
+    }
+
+    #[test]
+    fn test_fetch_address_overview() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/address/1abc",
+                r#"{"chain_stats":{"funded_txo_sum":1000,"spent_txo_sum":0,"tx_count":3},
+                    "mempool_stats":{"funded_txo_sum":0,"spent_txo_sum":1000,"tx_count":1}}"#,
+            )
+            .with(
+                "http://esplora/address/1abc/txs",
+                r#"[
+                    {"txid":"02","status":{"confirmed":false},
+                     "vin":[{"prevout":{"scriptpubkey_address":"1abc","value":1000}}],
+                     "vout":[{"scriptpubkey_address":"1xyz","value":900}]},
+                    {"txid":"01","status":{"confirmed":true,"block_height":7,"block_time":100},
+                     "vin":[{"prevout":{"scriptpubkey_address":"1xyz","value":2000}}],
+                     "vout":[{"scriptpubkey_address":"1abc","value":1000}]}
+                ]"#,
+            );
+        let result = fetch_address_overview(&client, "http://esplora", "1abc").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["confirmed_sat"], 1000);
+        assert_eq!(result["pending_sat"], -1000);
+        assert_eq!(result["tx_count"], 4);
+        assert_eq!(result["more_txs"], true);
+        let txs = &result["recent_txs"];
+        assert_eq!(txs[0]["delta_sat"], -1000);
+        assert_eq!(txs[0]["confirmed"], false);
+        assert_eq!(txs[1]["delta_sat"], 1000);
+        assert_eq!(txs[1]["block_height"], 7);
+    }
+
+    #[test]
+    fn test_fetch_address_reuse() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/address/1abc",
+                r#"{"chain_stats":{"tx_count":2,"funded_txo_count":2,"spent_txo_count":1},
+                    "mempool_stats":{"tx_count":0}}"#,
+            )
+            .with(
+                "http://esplora/address/1new",
+                r#"{"chain_stats":{"tx_count":1,"funded_txo_count":1,"spent_txo_count":0},
+                    "mempool_stats":{"tx_count":1}}"#,
+            );
+        let reuse = |address| {
+            let result = fetch_address_reuse(&client, "http://esplora", address).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = reuse("1abc");
+        assert_eq!(result["reused"], true);
+        assert_eq!(result["funded_txo_count"], 2);
+        let result = reuse("1new");
+        assert_eq!(result["reused"], false);
+        assert_eq!(result["mempool_tx_count"], 1);
+    }
+
+    #[test]
+    fn test_fetch_address_utxo_summary() {
+        let address = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+        let client = MockClient::default().with(
+            &format!("http://esplora/address/{address}/utxo"),
+            r#"[{"value":200,"status":{"confirmed":true}},
+                {"value":50000,"status":{"confirmed":true}},
+                {"value":60000,"status":{"confirmed":false}},
+                {"value":250000000,"status":{"confirmed":true}}]"#,
+        );
+        let result = fetch_address_utxo_summary(&client, "http://esplora", address).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["utxo_count"], 4);
+        assert_eq!(result["total_sat"], 250_110_200u64);
+        assert_eq!(result["unconfirmed_sat"], 60_000);
+        assert_eq!(result["largest_sat"], 250_000_000);
+        assert_eq!(result["smallest_sat"], 200);
+        // p2wpkh: dust below 294 sats
+        let histogram = result["histogram"].as_array().unwrap();
+        let counts: Vec<&Value> = histogram.iter().map(|bucket| &bucket["count"]).collect();
+        assert_eq!(counts, [1, 2, 0, 1]);
+        assert_eq!(histogram[0]["max_sat"], 294);
+        assert_eq!(histogram[3]["max_sat"], Value::Null);
+
+        let client =
+            MockClient::default().with(&format!("http://esplora/address/{address}/utxo"), "[]");
+        let result = fetch_address_utxo_summary(&client, "http://esplora", address).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["utxo_count"], 0);
+        assert_eq!(result["largest_sat"], Value::Null);
+    }
+
+    #[test]
+    fn test_fetch_address_balances() {
+        let client = MockClient::default().with(
+            "http://esplora/address/bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            r#"{"chain_stats":{"funded_txo_sum":3000,"spent_txo_sum":1000},
+                "mempool_stats":{"funded_txo_sum":0,"spent_txo_sum":500}}"#,
+        );
+        let addresses = [
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string(),
+            "notanaddress".to_string(),
+        ];
+        let result = fetch_address_balances(&client, "http://esplora", &addresses).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["summary"], json!({ "succeeded": 1, "failed": 1 }));
+        let balances = &result["balances"];
+        assert_eq!(balances[0]["key"], addresses[0].as_str());
+        assert_eq!(balances[0]["data"]["confirmed_sat"], 2000);
+        assert_eq!(balances[0]["data"]["pending_sat"], -500);
+        assert_eq!(balances[1]["ok"], false);
+        // Invalid addresses are not looked up
+        assert_eq!(client.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_fetch_address_first_tx() {
+        let tx = |txid: &str, height: u32| json!({ "txid": txid, "status": { "block_height": height, "block_time": height * 10 } });
+        let full_page: Vec<Value> = (0..ADDRESS_CHAIN_TXS_PAGE_SIZE as u32)
+            .map(|i| tx(&format!("t{i}"), 1000 - i))
+            .collect();
+        let last = format!("t{}", ADDRESS_CHAIN_TXS_PAGE_SIZE - 1);
+        let client = MockClient::default()
+            .with(
+                "http://esplora/address/1abc/txs/chain",
+                &json!(full_page).to_string(),
+            )
+            .with(
+                &format!("http://esplora/address/1abc/txs/chain/{last}"),
+                &json!([tx("first", 500)]).to_string(),
+            );
+        let result = fetch_address_first_tx(&client, "http://esplora", "1abc", 10).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["txid"], "first");
+        assert_eq!(result["block_time"], 5000);
+        assert_eq!(result["reached_first"], true);
+        assert_eq!(result["pages_fetched"], 2);
+
+        // Cut at the page limit
+        let result = fetch_address_first_tx(&client, "http://esplora", "1abc", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["txid"], last.as_str());
+        assert_eq!(result["reached_first"], false);
+
+        let client = MockClient::default().with("http://esplora/address/1new/txs/chain", "[]");
+        let result = fetch_address_first_tx(&client, "http://esplora", "1new", 10).unwrap();
+        assert_eq!(
+            result,
+            r#"{"address":"1new","found":false,"pages_fetched":1,"reached_first":true}"#
+        );
+    }
+
+    #[test]
+    fn test_address_to_script() {
+        let result = address_to_script(
+            " bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq ",
+            BitcoinNetwork::Mainnet,
+        )
+        .unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["type"], "p2wpkh");
+        assert_eq!(
+            result["scriptpubkey"],
+            "0014e8df018c7e326cc253faac7e46cdc51e68542c42"
+        );
+        assert_eq!(
+            result["scriptpubkey_asm"],
+            "OP_0 OP_PUSHBYTES_20 e8df018c7e326cc253faac7e46cdc51e68542c42"
+        );
+
+        let err = address_to_script(
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            BitcoinNetwork::Testnet,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "Address 'bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq' is not valid for testnet"
+        );
+    }
+
+    #[test]
+    fn test_address_to_scripthash() {
+        // Genesis coinbase address, scripthash as reported by Electrum
+        let result = address_to_scripthash("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["scriptpubkey"],
+            "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac"
+        );
+        assert_eq!(
+            result["scripthash"],
+            "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+        );
+        assert!(address_to_scripthash("garbage").is_err());
+    }
+
+    #[test]
+    fn test_find_common_txs() {
+        let txs = |ids: &[&str]| {
+            let txs: Vec<Value> = ids.iter().map(|id| json!({ "txid": id })).collect();
+            serde_json::to_string(&txs).unwrap()
+        };
+        let client = MockClient::default()
+            .with("http://esplora/address/a/txs", &txs(&["t3", "t2", "t1"]))
+            .with("http://esplora/address/b/txs", &txs(&["t4", "t1", "t3"]));
+        let result = find_common_txs(&client, "http://esplora", "a", "b").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["common_txids"], json!(["t3", "t1"]));
+        assert_eq!(result["txs_checked"]["b"], 3);
+
+        assert!(find_common_txs(&client, "http://esplora", "a", "missing").is_err());
+    }
+}
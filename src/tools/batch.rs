@@ -0,0 +1,164 @@
+// Meta-tool running several tool calls in one round trip
+use super::{ToolDef, check_cap, make_schema, parse_params};
+use crate::MyServer;
+use rmcp::model::{CallToolRequestParam, CallToolResult, Content, JsonObject, RawContent};
+use rmcp::schemars;
+use rmcp::serde_json::{Value, json};
+use serde::Deserialize;
+
+pub(super) const TOOLS: &[ToolDef] = &[ToolDef {
+    name: "batch_call",
+    chain: None,
+    description: "Run several tool calls of this server in one round trip, e.g. a transaction lookup and a fee estimate. The calls are independent and run concurrently, up to the configured maximum per batch (MAX_BATCH_CALLS); batch_call itself can't be nested. Returns one item per call, in order, with key set to the tool name and data holding its result (parsed as JSON when it is JSON). A failed call is reported inline with ok: false and its error instead of failing the whole batch.",
+    schema: make_schema::<BatchCallParams>,
+    handler: |server, args| {
+        let params: BatchCallParams = parse_params(args)?;
+        check_cap(
+            "Call count",
+            params.calls.len() as u64,
+            server.config.max_batch_calls as u64,
+        )?;
+        let result = batch_call(server, params.calls);
+        Ok(CallToolResult::success(vec![Content::text(
+            result.to_string(),
+        )]))
+    },
+}];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BatchCall {
+    #[schemars(description = "The name of the tool to call.")]
+    tool: String,
+    #[schemars(description = "The arguments of the call, as for a direct call (optional).")]
+    #[serde(default)]
+    arguments: Option<JsonObject>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BatchCallParams {
+    #[schemars(description = "The calls to run, as { tool, arguments } objects.")]
+    calls: Vec<BatchCall>,
+}
+
+// The payload of a tool result: its text, as JSON when it parses
+fn result_data(result: CallToolResult) -> Result<Value, String> {
+    let text: Vec<String> = result
+        .content
+        .into_iter()
+        .filter_map(|content| match content.raw {
+            RawContent::Text(text) => Some(text.text),
+            _ => None,
+        })
+        .collect();
+    let text = text.join("\n");
+    if result.is_error == Some(true) {
+        return Err(text);
+    }
+    Ok(rmcp::serde_json::from_str(&text).unwrap_or(Value::String(text)))
+}
+
+fn run_call(server: &MyServer, call: &BatchCall) -> Result<Value, String> {
+    if call.tool == "batch_call" {
+        return Err("batch_call can't be nested".to_string());
+    }
+    let params = CallToolRequestParam {
+        name: call.tool.clone().into(),
+        arguments: call.arguments.clone(),
+    };
+    server
+        .dispatch_tool(params)
+        .map_err(|e| e.message.into_owned())
+        .and_then(result_data)
+}
+
+fn batch_call(server: &MyServer, calls: Vec<BatchCall>) -> Value {
+    let results: Vec<Result<Value, String>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = calls
+            .iter()
+            .map(|call| {
+                let span = tracing::Span::current();
+                scope.spawn(move || {
+                    let _span = span.entered();
+                    run_call(server, call)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Call thread panicked".to_string()))
+            })
+            .collect()
+    });
+    let mut batch = super::BatchResults::default();
+    for (call, result) in calls.iter().zip(results) {
+        batch.push(call.tool.as_str(), result);
+    }
+    json!({
+        "summary": batch.summary(),
+        "results": batch.items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::MemoryCache;
+    use crate::config::Config;
+    use crate::esplora::testing::MockClient;
+    use crate::stats::ServerStats;
+    use std::sync::Arc;
+
+    fn call(tool: &str, arguments: Value) -> BatchCall {
+        BatchCall {
+            tool: tool.to_string(),
+            arguments: arguments.as_object().cloned(),
+        }
+    }
+
+    #[test]
+    fn test_batch_call() {
+        let client = MockClient::default()
+            .with("https://blockstream.info/api/blocks/tip/height", "840000")
+            .with(
+                "https://blockstream.info/api/fee-estimates",
+                r#"{"1":20.0,"6":10.0}"#,
+            );
+        let server = MyServer {
+            client: Arc::new(client),
+            config: Arc::new(Config::default()),
+            stats: Arc::new(ServerStats::new()),
+            cache: Arc::new(MemoryCache::default()),
+        };
+        let result = batch_call(
+            &server,
+            vec![
+                call("get_bitcoin_tip_height", json!({})),
+                call("get_bitcoin_feerate_for_target", json!({ "blocks": 6 })),
+                call("get_bitcoin_feerate_for_target", json!({})),
+                call("no_such_tool", json!({})),
+                call("batch_call", json!({ "calls": [] })),
+            ],
+        );
+        assert_eq!(result["summary"], json!({ "succeeded": 2, "failed": 3 }));
+        let results = &result["results"];
+        assert_eq!(results[0]["key"], "get_bitcoin_tip_height");
+        assert_eq!(results[0]["data"], 840000);
+        assert_eq!(results[1]["data"]["feerate_sat_vb"], 10.0);
+        assert!(
+            results[2]["error"]
+                .as_str()
+                .unwrap()
+                .contains("Invalid parameters")
+        );
+        assert!(
+            results[3]["error"]
+                .as_str()
+                .unwrap()
+                .contains("Unknown tool")
+        );
+        assert!(results[4]["error"].as_str().unwrap().contains("nested"));
+    }
+}
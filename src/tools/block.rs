@@ -0,0 +1,2892 @@
+// Block and chain tip tools
+use super::tx::{op_return_data, sample_block_txs, tx_feerate};
+use super::{
+    AMOUNTS_IN_BTC_DESCRIPTION, BLOCK_INTERVAL_SECS, BatchResults, CHUNK_SIZE_DESCRIPTION,
+    FORMAT_TIMESTAMP_DESCRIPTION, ToolDef, check_cap, chunked_result, default_list_limit,
+    format_utc, hex_decode, hex_encode, hex_id, make_schema, normalize_hex_id, page_info,
+    parse_params, text_result, with_btc_amounts, with_utc_timestamps,
+};
+use crate::config::Chain;
+use crate::esplora::{
+    BLOCK_TXS_PAGE_SIZE, BitcoinNetwork, EsploraClient, LiquidNetwork, fetch_block, fetch_esplora,
+    fetch_json, fetch_tip_height, is_not_found,
+};
+use bitcoin::pow::{CompactTarget, Target, Work};
+use rmcp::model::ErrorData;
+use rmcp::schemars;
+use rmcp::serde_json::{self, Value, json};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub(super) const TOOLS: &[ToolDef] = &[
+    ToolDef {
+        name: "get_bitcoin_block",
+        chain: Some(Chain::Bitcoin),
+        description: "Get a Bitcoin block by its hash from the Esplora API. Returns block data including height, timestamp, tx_count, size, and weight.",
+        schema: make_schema::<GetBitcoinBlockParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockParams = parse_params(args)?;
+            text_result(with_utc_timestamps(
+                fetch_block(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.hash,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_liquid_block",
+        chain: Some(Chain::Liquid),
+        description: "Get a Liquid block by its hash from the Esplora API. Returns block data including height, timestamp, tx_count, size, and weight.",
+        schema: make_schema::<GetLiquidBlockParams>,
+        handler: |server, args| {
+            let params: GetLiquidBlockParams = parse_params(args)?;
+            text_result(fetch_block(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.hash,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tip_height",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the current Bitcoin blockchain tip height from the Esplora API.",
+        schema: make_schema::<GetBitcoinTipHeightParams>,
+        handler: |server, args| {
+            let params: GetBitcoinTipHeightParams = parse_params(args)?;
+            text_result(fetch_tip_height(
+                server.client.as_ref(),
+                &params.network.api_base(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_liquid_tip_height",
+        chain: Some(Chain::Liquid),
+        description: "Get the current Liquid blockchain tip height from the Esplora API.",
+        schema: make_schema::<GetLiquidTipHeightParams>,
+        handler: |server, args| {
+            let params: GetLiquidTipHeightParams = parse_params(args)?;
+            text_result(fetch_tip_height(
+                server.client.as_ref(),
+                &params.network.api_base(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_detailed",
+        chain: Some(Chain::Bitcoin),
+        description: "Get a rich view of a Bitcoin block by its hash: the same data as get_bitcoin_block, plus the coinbase transaction's txid, scriptSig, and printable message (usually the mining pool's tag), and the block reward, subsidy, and total fees in satoshis. Makes one extra request for the first page of the block's transactions; use get_bitcoin_block for the lightweight summary.",
+        schema: make_schema::<GetBitcoinBlockParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockParams = parse_params(args)?;
+            text_result(fetch_block_detailed(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.hash,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_txs",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the transactions of a Bitcoin block by its hash, 25 per page in block order, the coinbase first. Returns the block's hash and tx_count, the page's txs as returned by Esplora, and page_info: pass page_info.next_cursor back as start_index for the following page, until page_info.has_more is false.",
+        schema: make_schema::<GetBitcoinBlockTxsParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockTxsParams = parse_params(args)?;
+            if params.start_index % BLOCK_TXS_PAGE_SIZE != 0 {
+                return Err(ErrorData::invalid_request(
+                    format!("start_index must be a multiple of {BLOCK_TXS_PAGE_SIZE}"),
+                    None,
+                ));
+            }
+            text_result(with_btc_amounts(
+                fetch_block_txs(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.hash,
+                    params.start_index,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "find_bitcoin_txid_by_prefix",
+        chain: Some(Chain::Bitcoin),
+        description: "Find the full txids starting with a prefix, e.g. the first characters of a txid shown truncated by a UI. A block context (hash or height) is required: Esplora has no global prefix search, so only the transactions of that block are searched. Returns every match up to `limit` (default 100), as short prefixes may match several txids; truncated is true and count gives the number of matches when some were left out.",
+        schema: make_schema::<FindBitcoinTxidByPrefixParams>,
+        handler: |server, args| {
+            let params: FindBitcoinTxidByPrefixParams = parse_params(args)?;
+            let invalid = |e| ErrorData::invalid_request(e, None);
+            let prefix = normalize_txid_prefix(&params.prefix).map_err(invalid)?;
+            let block = parse_block_ref(&params.block).map_err(invalid)?;
+            check_cap(
+                "limit",
+                params.limit as u64,
+                server.config.max_list_limit as u64,
+            )?;
+            text_result(find_txids_by_prefix(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                block,
+                &prefix,
+                params.limit,
+            ))
+        },
+    },
+    ToolDef {
+        name: "verify_bitcoin_block_merkle",
+        chain: Some(Chain::Bitcoin),
+        description: "Verify the Merkle root of a Bitcoin block by its hash: fetches the header and the list of all its txids, recomputes the root locally (double-SHA256 of pairs, duplicating the last hash of odd levels), and compares it with the header's merkle_root. Returns valid (bool), both roots, and the number of transactions. Warning: the txids of a large block make a response of several hundred kilobytes.",
+        schema: make_schema::<VerifyBitcoinBlockMerkleParams>,
+        handler: |server, args| {
+            let params: VerifyBitcoinBlockMerkleParams = parse_params(args)?;
+            text_result(verify_block_merkle(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.hash,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_subsidy",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the Bitcoin block subsidy at a height (default: the current tip), computed from the halving schedule: 50 BTC halved every 210000 blocks, rounded down to the satoshi, so it reaches zero at the 33rd halving. Returns the subsidy in satoshis, the halving epoch, and the height of the next halving and the blocks until it (null once the subsidy is zero). Only requests the tip height when no height is given.",
+        schema: make_schema::<GetBitcoinSubsidyParams>,
+        handler: |server, args| {
+            let params: GetBitcoinSubsidyParams = parse_params(args)?;
+            text_result(with_btc_amounts(
+                fetch_subsidy(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    params.height,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_reward",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the reward of a Bitcoin block by its hash. Returns the total coinbase reward, the subsidy computed from the halving schedule, and the total fees (reward - subsidy), all in satoshis.",
+        schema: make_schema::<GetBitcoinBlockRewardParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockRewardParams = parse_params(args)?;
+            text_result(with_btc_amounts(
+                fetch_block_reward(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.hash,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_adjacent_block",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the next or previous Bitcoin block relative to a block hash. Returns the adjacent block's data, or an error when asking for the block after the tip or before genesis.",
+        schema: make_schema::<GetBitcoinAdjacentBlockParams>,
+        handler: |server, args| {
+            let params: GetBitcoinAdjacentBlockParams = parse_params(args)?;
+            text_result(with_utc_timestamps(
+                fetch_adjacent_block(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.hash,
+                    params.direction,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_volume",
+        chain: Some(Chain::Bitcoin),
+        description: "Compute the total BTC moved in a Bitcoin block. Returns the sum of all output values, the coinbase output, and the net volume excluding the coinbase (all in satoshis). Warning: blocks are fetched 25 transactions at a time, so large blocks trigger many sub-requests and can be slow; blocks needing more than the configured maximum of pages (MAX_BLOCK_TX_PAGES) only have their first transactions counted, with truncated: true. Failed pages are reported per page instead of failing the whole call (complete is false when totals are partial).",
+        schema: make_schema::<BitcoinBlockAmountsParams>,
+        handler: |server, args| {
+            let params: BitcoinBlockAmountsParams = parse_params(args)?;
+            text_result(with_btc_amounts(
+                fetch_block_volume(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.hash,
+                    server.config.max_block_tx_pages,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_opreturn_stats",
+        chain: Some(Chain::Bitcoin),
+        description: "Measure the OP_RETURN data embedded in a Bitcoin block, e.g. for research on block space usage. Returns how many transactions carry OP_RETURN outputs and their share of the block, the number of OP_RETURN outputs, the total data bytes they embed (the pushed payload, without opcodes), the sats burned in them, and the total fees paid by those transactions. Warning: blocks are fetched 25 transactions at a time, so large blocks trigger many sub-requests and can be slow; blocks needing more than the configured maximum of pages (MAX_BLOCK_TX_PAGES) only have their first transactions measured, with truncated: true. Failed pages are reported per page instead of failing the whole call (complete is false when totals are partial).",
+        schema: make_schema::<BitcoinBlockAmountsParams>,
+        handler: |server, args| {
+            let params: BitcoinBlockAmountsParams = parse_params(args)?;
+            text_result(with_btc_amounts(
+                fetch_block_opreturn_stats(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.hash,
+                    server.config.max_block_tx_pages,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_top_txs",
+        chain: Some(Chain::Bitcoin),
+        description: "List the largest transactions of a Bitcoin block by a metric, to spot notable ones (large transfers, high fees) without scanning the whole block. by is 'value' (total output value, sats), 'fee' (sats), or 'size' (bytes); the top `limit` transactions (default 10, at most 20) are returned in descending order, each with its txid, position in the block, value, fee, size, vsize, fee rate, and input and output counts. Ties keep the block order. Warning: blocks are fetched 25 transactions at a time, so large blocks trigger many sub-requests and can be slow; blocks needing more than the configured maximum of pages (MAX_BLOCK_TX_PAGES) are only ranked over their first transactions, with truncated: true, so the top transactions are those of the pages walked. Failed pages are reported per page instead of failing the whole call (complete is false when the ranking is partial).",
+        schema: make_schema::<GetBitcoinBlockTopTxsParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockTopTxsParams = parse_params(args)?;
+            if !(1..=MAX_TOP_TXS).contains(&params.limit) {
+                return Err(ErrorData::invalid_request(
+                    format!("limit must be between 1 and {MAX_TOP_TXS}"),
+                    None,
+                ));
+            }
+            text_result(with_btc_amounts(
+                fetch_block_top_txs(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.hash,
+                    params.by,
+                    params.limit,
+                    server.config.max_block_tx_pages,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_address_types",
+        chain: Some(Chain::Bitcoin),
+        description: "Tally the output script types of every transaction in a Bitcoin block (p2pkh, p2sh, v0_p2wpkh, v0_p2wsh, v1_p2tr, op_return, ...). Returns the number of outputs per scriptpubkey_type. Warning: blocks are fetched 25 transactions at a time, so large blocks trigger many sub-requests and can be slow; blocks needing more than the configured maximum of pages (MAX_BLOCK_TX_PAGES) only have their first transactions tallied, with truncated: true. Failed pages are reported per page instead of failing the whole call (complete is false when counts are partial).",
+        schema: make_schema::<GetBitcoinBlockAddressTypesParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockAddressTypesParams = parse_params(args)?;
+            text_result(fetch_block_address_types(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.hash,
+                server.config.max_block_tx_pages,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_difficulty",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the current Bitcoin mining difficulty from the tip block, with the nbits target and the estimated network hashrate (difficulty * 2^32 / 600) in H/s and as a human-readable EH/s string.",
+        schema: make_schema::<GetBitcoinDifficultyParams>,
+        handler: |server, args| {
+            let params: GetBitcoinDifficultyParams = parse_params(args)?;
+            text_result(fetch_difficulty(
+                server.client.as_ref(),
+                &params.network.api_base(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_chainwork",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the cumulative proof of work of the Bitcoin chain at the tip block, as a 256-bit hex number with its log2 and a human-readable approximation. Uses the block's chainwork when the backend exposes it (source 'backend'); Esplora doesn't, so otherwise it is estimated as (height + 1) times the work of the tip block at the current difficulty (source 'estimate'). The estimate is an upper bound, far above the real value since difficulty used to be lower.",
+        schema: make_schema::<GetBitcoinChainworkParams>,
+        handler: |server, args| {
+            let params: GetBitcoinChainworkParams = parse_params(args)?;
+            text_result(fetch_chainwork(
+                server.client.as_ref(),
+                &params.network.api_base(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_total_tx_estimate",
+        chain: Some(Chain::Bitcoin),
+        description: "Estimate the total number of transactions ever confirmed on the Bitcoin chain. This is a rough estimate, not an exact count, which Esplora doesn't expose: the average tx_count of 10-block windows is sampled at 10 heights evenly spread from genesis to the tip (10 requests) and integrated over the chain height. Returns the exact tip height, the estimate, the recent average transactions per block, and the samples used.",
+        schema: make_schema::<GetBitcoinTotalTxEstimateParams>,
+        handler: |server, args| {
+            let params: GetBitcoinTotalTxEstimateParams = parse_params(args)?;
+            text_result(fetch_total_tx_estimate(
+                server.client.as_ref(),
+                &params.network.api_base(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_difficulty_adjustment",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the progress of the current Bitcoin difficulty epoch: the height of the next retarget (every 2016 blocks), the blocks remaining until it, the progress through the epoch as a percentage, and the estimated time to the retarget, assuming one block every 10 minutes, in seconds and as a UTC timestamp.",
+        schema: make_schema::<GetBitcoinDifficultyAdjustmentParams>,
+        handler: |server, args| {
+            let params: GetBitcoinDifficultyAdjustmentParams = parse_params(args)?;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            text_result(fetch_difficulty_adjustment(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                now,
+            ))
+        },
+    },
+    ToolDef {
+        name: "estimate_block_eta",
+        chain: Some(Chain::Bitcoin),
+        description: "Estimate when a future Bitcoin block height will be reached, assuming one block every 10 minutes from the current tip. Returns the blocks remaining, the estimated duration in seconds, and the estimated arrival time as a UTC timestamp. Fails if the target height has already been mined.",
+        schema: make_schema::<EstimateBlockEtaParams>,
+        handler: |server, args| {
+            let params: EstimateBlockEtaParams = parse_params(args)?;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            text_result(estimate_block_eta(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.target_height,
+                now,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_by_alias",
+        chain: Some(Chain::Bitcoin),
+        description: "Get a well-known Bitcoin block by a memorable alias: 'genesis' (height 0), 'tip' (the current chain tip), or 'halving:N' (the block of the Nth halving, at height N * 210000). Returns the same block data as get_bitcoin_block.",
+        schema: make_schema::<GetBitcoinBlockByAliasParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockByAliasParams = parse_params(args)?;
+            let alias = parse_block_alias(&params.alias)
+                .map_err(|e| ErrorData::invalid_request(e, None))?;
+            text_result(with_utc_timestamps(
+                fetch_block_by_alias(server.client.as_ref(), &params.network.api_base(), alias),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_blocks_range",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the Bitcoin blocks of an inclusive height range, at most 50 blocks per call unless configured otherwise (see server_capabilities). Blocks are fetched concurrently and returned in height order, each as { key: height, ok, data } or { key: height, ok: false, error } so that one failing block doesn't fail the whole range.",
+        schema: make_schema::<GetBitcoinBlocksRangeParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlocksRangeParams = parse_params(args)?;
+            check_blocks_range(
+                params.start_height,
+                params.end_height,
+                server.config.max_blocks_range,
+            )
+            .map_err(|e| ErrorData::invalid_request(e, None))?;
+            chunked_result(
+                with_utc_timestamps(
+                    fetch_blocks_range(
+                        server.client.as_ref(),
+                        &params.network.api_base(),
+                        params.start_height,
+                        params.end_height,
+                    ),
+                    params.format_timestamp,
+                ),
+                "blocks",
+                params.chunk_size,
+            )
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_blocks_by_time",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the Bitcoin blocks mined within a time window, e.g. to answer which blocks were mined yesterday. The first and last heights are found by binary search over block timestamps, then the blocks are returned in height order as summaries: height, hash, timestamp, tx_count, size, and weight. At most 50 blocks are returned unless configured otherwise (see server_capabilities, max_blocks_range): truncated is true when the window holds more, the rest starting at next_height. Warning: miners set timestamps loosely, they are not strictly increasing, so blocks within about two hours of either bound may be included or left out wrongly.",
+        schema: make_schema::<GetBitcoinBlocksByTimeParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlocksByTimeParams = parse_params(args)?;
+            if params.start_unix > params.end_unix {
+                return Err(ErrorData::invalid_request(
+                    format!(
+                        "start_unix {} is after end_unix {}",
+                        params.start_unix, params.end_unix
+                    ),
+                    None,
+                ));
+            }
+            text_result(with_utc_timestamps(
+                fetch_blocks_by_time(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    params.start_unix,
+                    params.end_unix,
+                    server.config.max_blocks_range,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_ancestry",
+        chain: Some(Chain::Bitcoin),
+        description: "Walk the Bitcoin chain backwards from a block, following previousblockhash for up to `depth` ancestors (at most 20 unless configured otherwise, see server_capabilities). Returns the starting block and its ancestors in that order, each as { hash, height }, stopping early at the genesis block. Useful to inspect reorgs or draw the chain.",
+        schema: make_schema::<GetBitcoinBlockAncestryParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockAncestryParams = parse_params(args)?;
+            check_cap(
+                "depth",
+                params.depth.into(),
+                server.config.max_ancestry_depth.into(),
+            )?;
+            text_result(fetch_block_ancestry(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.hash,
+                params.depth,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_block_intervals",
+        chain: Some(Chain::Bitcoin),
+        description: "Get statistics on the time between the last `count` Bitcoin blocks (at most 100 unless configured otherwise, see server_capabilities), to spot unusually slow or fast periods. Returns the height range covered and the minimum, maximum, and average interval in seconds between consecutive blocks, computed from their timestamps. Miners set timestamps loosely, so an interval can be negative.",
+        schema: make_schema::<GetBitcoinBlockIntervalsParams>,
+        handler: |server, args| {
+            let params: GetBitcoinBlockIntervalsParams = parse_params(args)?;
+            if params.count < 2 {
+                return Err(ErrorData::invalid_request(
+                    "count must be at least 2 to measure an interval",
+                    None,
+                ));
+            }
+            check_cap(
+                "count",
+                params.count.into(),
+                server.config.max_interval_blocks.into(),
+            )?;
+            text_result(fetch_block_intervals(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.count as usize,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_recent_fee_stats",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the fee rates paid in the last `blocks` Bitcoin blocks (at most 10 unless configured otherwise, see server_capabilities), to see how the fee market behaved recently. Returns, per block newest first, its height, hash, tx_count, and the minimum, median, and maximum fee rate (sat/vB) of its sampled transactions, plus an overall summary over all samples. A block whose sample fails is reported inline with ok: false, and the summary covers the others (complete is false then, fetch_summary counts the blocks sampled and failed). Warning: for each block, up to 4 pages of 25 transactions spread across the block are sampled, so the figures are estimates from at most 100 transactions per block; the coinbase is left out.",
+        schema: make_schema::<BitcoinRecentFeeParams>,
+        handler: |server, args| {
+            let params: BitcoinRecentFeeParams = parse_params(args)?;
+            if params.blocks == 0 {
+                return Err(ErrorData::invalid_request(
+                    "blocks must be at least 1",
+                    None,
+                ));
+            }
+            check_cap(
+                "blocks",
+                params.blocks.into(),
+                server.config.max_fee_stats_blocks as u64,
+            )?;
+            text_result(fetch_recent_fee_stats(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.blocks as usize,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_avg_feerate",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the average fee rate actually paid in the last `blocks` Bitcoin blocks (at most 10 unless configured otherwise, see server_capabilities), unlike fee estimates which look ahead. The average is weighted by size: the total fees of the sampled transactions divided by their total vsize, in sat/vB. Returns it overall and per block, newest first, with the number of transactions and the vsize sampled. A block whose sample fails is reported inline with ok: false, and the overall average covers the others (complete is false then). Warning: for each block, up to 4 pages of 25 transactions spread across the block are sampled, so this is an estimate from at most 100 transactions per block; the coinbase is left out.",
+        schema: make_schema::<BitcoinRecentFeeParams>,
+        handler: |server, args| {
+            let params: BitcoinRecentFeeParams = parse_params(args)?;
+            if params.blocks == 0 {
+                return Err(ErrorData::invalid_request(
+                    "blocks must be at least 1",
+                    None,
+                ));
+            }
+            check_cap(
+                "blocks",
+                params.blocks.into(),
+                server.config.max_fee_stats_blocks as u64,
+            )?;
+            text_result(fetch_avg_feerate(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.blocks as usize,
+            ))
+        },
+    },
+];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockTxsParams {
+    #[schemars(description = "The block hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "Index of the first transaction of the page, a multiple of 25: the page_info.next_cursor of the previous page. Defaults to 0."
+    )]
+    #[serde(default)]
+    start_index: usize,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockParams {
+    #[schemars(description = "The block hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetLiquidBlockParams {
+    #[schemars(description = "The block hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinTipHeightParams {
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetLiquidTipHeightParams {
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BitcoinBlockAmountsParams {
+    #[schemars(description = "The block hash to analyze.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+// Transactions get_bitcoin_block_top_txs returns at most
+const MAX_TOP_TXS: usize = 20;
+
+fn default_top_txs() -> usize {
+    10
+}
+
+#[derive(Deserialize, schemars::JsonSchema, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TopTxsMetric {
+    #[default]
+    Value,
+    Fee,
+    Size,
+}
+
+impl TopTxsMetric {
+    fn as_str(self) -> &'static str {
+        match self {
+            TopTxsMetric::Value => "value",
+            TopTxsMetric::Fee => "fee",
+            TopTxsMetric::Size => "size",
+        }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockTopTxsParams {
+    #[schemars(description = "The block hash to analyze.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "The metric to rank transactions by: 'value' (default, total output value), 'fee', or 'size'."
+    )]
+    #[serde(default)]
+    by: TopTxsMetric,
+    #[schemars(description = "Number of transactions to return, from 1 to 20 (default 10).")]
+    #[serde(default = "default_top_txs")]
+    limit: usize,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockAddressTypesParams {
+    #[schemars(description = "The block hash to analyze.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct VerifyBitcoinBlockMerkleParams {
+    #[schemars(description = "The block hash to verify.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockRewardParams {
+    #[schemars(description = "The block hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinSubsidyParams {
+    #[schemars(description = "The block height (optional, defaults to the current tip).")]
+    #[serde(default)]
+    height: Option<u64>,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinTotalTxEstimateParams {
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinChainworkParams {
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinDifficultyParams {
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinDifficultyAdjustmentParams {
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct EstimateBlockEtaParams {
+    #[schemars(description = "The future block height to estimate the arrival time of.")]
+    target_height: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockByAliasParams {
+    #[schemars(description = "The block alias: 'genesis', 'tip', or 'halving:N'.")]
+    alias: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlocksRangeParams {
+    #[schemars(description = "The first block height of the range.")]
+    start_height: u32,
+    #[schemars(description = "The last block height of the range, included.")]
+    end_height: u32,
+    #[schemars(description = CHUNK_SIZE_DESCRIPTION)]
+    #[serde(default)]
+    chunk_size: Option<usize>,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlocksByTimeParams {
+    #[schemars(description = "Start of the time window, as a Unix timestamp in seconds.")]
+    start_unix: u64,
+    #[schemars(description = "End of the time window, included, as a Unix timestamp in seconds.")]
+    end_unix: u64,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockAncestryParams {
+    #[schemars(description = "The block hash to start from.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(description = "Number of ancestors to walk, at most 20 by default.")]
+    depth: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinBlockIntervalsParams {
+    #[schemars(description = "Number of recent blocks to measure, from 2 to 100 by default.")]
+    count: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BitcoinRecentFeeParams {
+    #[schemars(description = "Number of recent blocks to cover, from 1 to 10 by default.")]
+    blocks: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FindBitcoinTxidByPrefixParams {
+    #[schemars(description = "The beginning of the txid, in hex.")]
+    prefix: String,
+    #[schemars(description = "The block containing the transaction, as a block hash or a height.")]
+    block: String,
+    #[schemars(description = "Maximum number of matching txids to return (default 100).")]
+    #[serde(default = "default_list_limit")]
+    limit: usize,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum BlockDirection {
+    Next,
+    Prev,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinAdjacentBlockParams {
+    #[schemars(description = "The block hash to start from.")]
+    #[serde(deserialize_with = "hex_id")]
+    hash: String,
+    #[schemars(
+        description = "Which neighbour to return: 'next' (child block) or 'prev' (parent block)."
+    )]
+    direction: BlockDirection,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+const HALVING_INTERVAL: u64 = 210_000;
+
+// Block subsidy in satoshis: 50 BTC halved every 210000 blocks, zero after 64 halvings
+fn block_subsidy_sat(height: u64) -> u64 {
+    let halvings = height / HALVING_INTERVAL;
+    if halvings >= 64 {
+        0
+    } else {
+        (50 * 100_000_000) >> halvings
+    }
+}
+
+// Subsidy, halving epoch, and next halving at a height
+fn subsidy_schedule(height: u64) -> Value {
+    let subsidy_sat = block_subsidy_sat(height);
+    let epoch = height / HALVING_INTERVAL;
+    // Once the subsidy is zero, halvings no longer change anything
+    let next_halving_height = (subsidy_sat > 0).then(|| (epoch + 1) * HALVING_INTERVAL);
+    json!({
+        "height": height,
+        "subsidy_sat": subsidy_sat,
+        "halving_epoch": epoch,
+        "next_halving_height": next_halving_height,
+        "blocks_until_next_halving": next_halving_height.map(|next| next - height),
+    })
+}
+
+fn fetch_subsidy(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    height: Option<u64>,
+) -> Result<String, String> {
+    let height = match height {
+        Some(height) => height,
+        None => fetch_tip_height(client, base_url)?
+            .trim()
+            .parse()
+            .map_err(|e| format!("Failed to parse tip height: {e}"))?,
+    };
+    Ok(subsidy_schedule(height).to_string())
+}
+
+// The coinbase is always the first transaction of the first page
+fn fetch_coinbase(client: &dyn EsploraClient, base_url: &str, hash: &str) -> Result<Value, String> {
+    let mut txs = fetch_json(client, &format!("{base_url}/block/{hash}/txs/0"))?;
+    txs.get_mut(0)
+        .map(Value::take)
+        .ok_or_else(|| "Block has no transactions".to_string())
+}
+
+// Height, reward, subsidy, and fees paid to the miner by a coinbase transaction
+fn coinbase_reward(coinbase: &Value) -> Result<Value, String> {
+    let height = coinbase["status"]["block_height"]
+        .as_u64()
+        .ok_or("Coinbase transaction is missing block_height")?;
+    let reward_sat: u64 = coinbase["vout"]
+        .as_array()
+        .map(|vout| vout.iter().filter_map(|o| o["value"].as_u64()).sum())
+        .unwrap_or(0);
+    let subsidy_sat = block_subsidy_sat(height);
+    Ok(json!({
+        "height": height,
+        "reward_sat": reward_sat,
+        "subsidy_sat": subsidy_sat,
+        // A miner may claim less than allowed, so never report negative fees
+        "fees_sat": reward_sat.saturating_sub(subsidy_sat),
+    }))
+}
+
+fn fetch_block_reward(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+) -> Result<String, String> {
+    let coinbase = fetch_coinbase(client, base_url, hash)?;
+    let mut result = coinbase_reward(&coinbase)?;
+    result["block_hash"] = json!(hash);
+    result["coinbase_txid"] = coinbase["txid"].clone();
+    Ok(result.to_string())
+}
+
+// Printable ASCII runs of a coinbase scriptSig, where miners put their pool tag
+fn coinbase_message(scriptsig: &[u8]) -> String {
+    let text: String = scriptsig
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn fetch_block_txs(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    start_index: usize,
+) -> Result<String, String> {
+    let block = fetch_json(client, &format!("{base_url}/block/{hash}"))?;
+    let tx_count = block["tx_count"]
+        .as_u64()
+        .ok_or("Block response is missing tx_count")? as usize;
+    if start_index >= tx_count {
+        return Err(format!(
+            "start_index {start_index} is past the last transaction, the block has {tx_count}"
+        ));
+    }
+    let txs = fetch_json(
+        client,
+        &format!("{base_url}/block/{hash}/txs/{start_index}"),
+    )?;
+    let count = txs
+        .as_array()
+        .ok_or("Block txs response is not an array")?
+        .len();
+    let next_index = start_index + count;
+    let result = json!({
+        "hash": hash,
+        "tx_count": tx_count,
+        "txs": txs,
+        "page_info": page_info(count, (next_index < tx_count).then(|| json!(next_index))),
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_block_detailed(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+) -> Result<String, String> {
+    let mut block = fetch_json(client, &format!("{base_url}/block/{hash}"))?;
+    let coinbase = fetch_coinbase(client, base_url, hash)?;
+    let reward = coinbase_reward(&coinbase)?;
+    let scriptsig = coinbase["vin"][0]["scriptsig"].as_str().unwrap_or_default();
+    let message = coinbase_message(&hex_decode(scriptsig)?);
+    block["coinbase"] = json!({
+        "txid": coinbase["txid"],
+        "scriptsig": scriptsig,
+        "message": message,
+    });
+    for field in ["reward_sat", "subsidy_sat", "fees_sat"] {
+        block[field] = reward[field].clone();
+    }
+    Ok(block.to_string())
+}
+
+fn fetch_adjacent_block(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    direction: BlockDirection,
+) -> Result<String, String> {
+    let block = fetch_json(client, &format!("{base_url}/block/{hash}"))?;
+    let adjacent_hash = match direction {
+        BlockDirection::Prev => match block["previousblockhash"].as_str() {
+            Some(prev) => prev.to_string(),
+            None => {
+                return Err(format!(
+                    "Block {hash} is the genesis block, it has no previous block"
+                ));
+            }
+        },
+        BlockDirection::Next => {
+            let height = block["height"]
+                .as_u64()
+                .ok_or("Block response is missing height")?;
+            match fetch_esplora(client, &format!("{base_url}/block-height/{}", height + 1)) {
+                Ok(next) => next.trim().to_string(),
+                Err(e) if is_not_found(&e) => {
+                    return Err(format!(
+                        "Block {hash} at height {height} is the chain tip, there is no next block yet"
+                    ));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    };
+    fetch_block(client, base_url, &adjacent_hash)
+}
+
+#[derive(Debug, PartialEq)]
+enum BlockAlias {
+    Height(u64),
+    Tip,
+}
+
+fn parse_block_alias(alias: &str) -> Result<BlockAlias, String> {
+    let alias = alias.trim().to_lowercase();
+    let unsupported =
+        || format!("Unknown block alias '{alias}', supported aliases are: genesis, tip, halving:N");
+    match alias.as_str() {
+        "genesis" => Ok(BlockAlias::Height(0)),
+        "tip" => Ok(BlockAlias::Tip),
+        _ => {
+            let n: u64 = alias
+                .strip_prefix("halving:")
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(unsupported)?;
+            n.checked_mul(HALVING_INTERVAL)
+                .map(BlockAlias::Height)
+                .ok_or_else(unsupported)
+        }
+    }
+}
+
+fn fetch_block_by_alias(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    alias: BlockAlias,
+) -> Result<String, String> {
+    match alias {
+        BlockAlias::Tip => {
+            let hash = fetch_esplora(client, &format!("{base_url}/blocks/tip/hash"))?;
+            fetch_block(client, base_url, hash.trim())
+        }
+        BlockAlias::Height(height) => fetch_block_at_height(client, base_url, height),
+    }
+}
+
+fn fetch_block_hash_at_height(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    height: u64,
+) -> Result<String, String> {
+    match fetch_esplora(client, &format!("{base_url}/block-height/{height}")) {
+        Ok(hash) => Ok(hash.trim().to_string()),
+        Err(e) if is_not_found(&e) => Err(format!("There is no block at height {height} yet")),
+        Err(e) => Err(e),
+    }
+}
+
+fn fetch_block_at_height(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    height: u64,
+) -> Result<String, String> {
+    let hash = fetch_block_hash_at_height(client, base_url, height)?;
+    fetch_block(client, base_url, &hash)
+}
+
+// A block given either by hash or by height
+#[derive(Debug, PartialEq)]
+enum BlockRef {
+    Hash(String),
+    Height(u64),
+}
+
+fn parse_block_ref(input: &str) -> Result<BlockRef, String> {
+    // Checked first, since a hash may consist of digits only
+    if let Ok(hash) = normalize_hex_id(input) {
+        return Ok(BlockRef::Hash(hash));
+    }
+    input
+        .trim()
+        .parse()
+        .map(BlockRef::Height)
+        .map_err(|_| format!("'{input}' is neither a block hash nor a block height"))
+}
+
+// Lowercase hex prefix of a txid, accepting the same pasting quirks as full ids
+fn normalize_txid_prefix(input: &str) -> Result<String, String> {
+    let trimmed = input.trim();
+    let prefix = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed)
+        .to_lowercase();
+    if prefix.is_empty() || prefix.len() > 64 || !prefix.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("'{input}' is not a hex txid prefix"));
+    }
+    Ok(prefix)
+}
+
+fn find_txids_by_prefix(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    block: BlockRef,
+    prefix: &str,
+    limit: usize,
+) -> Result<String, String> {
+    let hash = match block {
+        BlockRef::Hash(hash) => hash,
+        BlockRef::Height(height) => fetch_block_hash_at_height(client, base_url, height)?,
+    };
+    let txids = fetch_json(client, &format!("{base_url}/block/{hash}/txids"))?;
+    let txids = txids
+        .as_array()
+        .ok_or("Block txids response is not an array")?;
+    let matches: Vec<&Value> = txids
+        .iter()
+        .filter(|txid| txid.as_str().is_some_and(|txid| txid.starts_with(prefix)))
+        .collect();
+    let result = json!({
+        "block_hash": hash,
+        "prefix": prefix,
+        "txs_searched": txids.len(),
+        "count": matches.len(),
+        "truncated": matches.len() > limit,
+        "matches": &matches[..matches.len().min(limit)],
+    });
+    Ok(result.to_string())
+}
+
+// Merkle root of hashes in internal byte order: each level hashes pairs of
+// the one below, an odd last hash being paired with itself
+fn merkle_root(mut level: Vec<[u8; 32]>) -> Option<[u8; 32]> {
+    use bitcoin::hashes::{Hash, HashEngine, sha256d};
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(level[level.len() - 1]);
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let mut engine = sha256d::Hash::engine();
+                engine.input(&pair[0]);
+                engine.input(&pair[1]);
+                sha256d::Hash::from_engine(engine).to_byte_array()
+            })
+            .collect();
+    }
+    level.first().copied()
+}
+
+fn verify_block_merkle(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+) -> Result<String, String> {
+    use bitcoin::hashes::Hash;
+    let block = fetch_json(client, &format!("{base_url}/block/{hash}"))?;
+    let header_root = block["merkle_root"]
+        .as_str()
+        .ok_or("Block has no merkle_root")?;
+    let txids = fetch_json(client, &format!("{base_url}/block/{hash}/txids"))?;
+    let txids = txids
+        .as_array()
+        .ok_or("Block txids response is not an array")?
+        .iter()
+        .map(|txid| {
+            txid.as_str()
+                .and_then(|txid| txid.parse::<bitcoin::Txid>().ok())
+                .map(|txid| txid.to_byte_array())
+                .ok_or_else(|| format!("Invalid txid in block {hash}: {txid}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let tx_count = txids.len();
+    let computed_root =
+        merkle_root(txids).ok_or_else(|| format!("Block {hash} has no transactions"))?;
+    let computed_root = bitcoin::TxMerkleNode::from_byte_array(computed_root).to_string();
+    let result = json!({
+        "block_hash": hash,
+        "valid": computed_root == header_root,
+        "header_merkle_root": header_root,
+        "computed_merkle_root": computed_root,
+        "tx_count": tx_count,
+    });
+    Ok(result.to_string())
+}
+
+fn check_blocks_range(start_height: u32, end_height: u32, max_blocks: u32) -> Result<(), String> {
+    if start_height > end_height {
+        return Err(format!(
+            "start_height {start_height} is greater than end_height {end_height}"
+        ));
+    }
+    let blocks = u64::from(end_height - start_height) + 1;
+    if blocks > u64::from(max_blocks) {
+        return Err(format!(
+            "Range of {blocks} blocks exceeds the configured maximum of {max_blocks}, retry with at most {max_blocks} blocks per call"
+        ));
+    }
+    Ok(())
+}
+
+// Fetch every block of an inclusive height range concurrently, in height order
+fn fetch_blocks_range(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    start_height: u32,
+    end_height: u32,
+) -> Result<String, String> {
+    let results: Vec<(u32, Result<Value, String>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (start_height..=end_height)
+            .map(|height| {
+                let span = tracing::Span::current();
+                let handle = scope.spawn(move || {
+                    let _span = span.entered();
+                    let block = fetch_block_at_height(client, base_url, u64::from(height))?;
+                    serde_json::from_str(&block)
+                        .map_err(|e| format!("Failed to parse response: {e}"))
+                });
+                (height, handle)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(height, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()));
+                (height, result)
+            })
+            .collect()
+    });
+    let mut blocks = BatchResults::default();
+    for (height, result) in results {
+        blocks.push(height, result);
+    }
+    blocks.check_any_succeeded()?;
+    let result = json!({
+        "start_height": start_height,
+        "end_height": end_height,
+        "summary": blocks.summary(),
+        "blocks": blocks.items,
+    });
+    Ok(result.to_string())
+}
+
+// Up to 10 blocks from `height` down, newest first, as listed by /blocks
+fn fetch_blocks_page(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    height: u64,
+) -> Result<Vec<Value>, String> {
+    let page = fetch_json(client, &format!("{base_url}/blocks/{height}"))?;
+    match page {
+        Value::Array(blocks) if !blocks.is_empty() => Ok(blocks),
+        _ => Err(format!("No block found at height {height}")),
+    }
+}
+
+// The lowest height in [0, tip_height + 1] whose block timestamp is at least
+// `time`, tip_height + 1 if there is none. Timestamps are not strictly
+// increasing, so this is only exact where they are.
+fn first_height_at_or_after(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    tip_height: u64,
+    time: u64,
+) -> Result<u64, String> {
+    let (mut low, mut high) = (0, tip_height + 1);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let timestamp = fetch_blocks_page(client, base_url, mid)?[0]["timestamp"]
+            .as_u64()
+            .ok_or("Block has no timestamp")?;
+        if timestamp < time {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+fn fetch_blocks_by_time(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    start_unix: u64,
+    end_unix: u64,
+    max_blocks: u32,
+) -> Result<String, String> {
+    let tip_height: u64 = fetch_tip_height(client, base_url)?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to parse tip height: {e}"))?;
+    let start_height = first_height_at_or_after(client, base_url, tip_height, start_unix)?;
+    // One past the last block of the window, the tip's successor for an
+    // unbounded window
+    let end_bound =
+        first_height_at_or_after(client, base_url, tip_height, end_unix.saturating_add(1))?;
+    let block_count = end_bound.saturating_sub(start_height);
+    let returned = block_count.min(u64::from(max_blocks));
+
+    // Pages list blocks downwards: walk from the last returned one
+    let mut blocks: Vec<Value> = Vec::new();
+    let mut height = start_height + returned;
+    while height > start_height {
+        let above = height;
+        let page = fetch_blocks_page(client, base_url, height - 1)?;
+        for block in page {
+            match block["height"].as_u64() {
+                Some(h) if h >= start_height && h < height => {
+                    blocks.push(json!({
+                        "height": h,
+                        "hash": block["id"],
+                        "timestamp": block["timestamp"],
+                        "tx_count": block["tx_count"],
+                        "size": block["size"],
+                        "weight": block["weight"],
+                    }));
+                    height = h;
+                }
+                _ => {}
+            }
+        }
+        if height == above {
+            return Err(format!(
+                "Blocks response has no block at height {}",
+                above - 1
+            ));
+        }
+    }
+    blocks.reverse();
+    let truncated = returned < block_count;
+    let result = json!({
+        "start_unix": start_unix,
+        "end_unix": end_unix,
+        "block_count": block_count,
+        "start_height": (block_count > 0).then_some(start_height),
+        "end_height": (block_count > 0).then(|| end_bound - 1),
+        "truncated": truncated,
+        "next_height": truncated.then(|| start_height + returned),
+        "blocks": blocks,
+    });
+    Ok(result.to_string())
+}
+
+// Follow previousblockhash from `hash`, one request per block since each
+// parent is only known once its child has been fetched
+fn fetch_block_ancestry(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    depth: u32,
+) -> Result<String, String> {
+    let mut blocks = Vec::new();
+    let mut hash = hash.to_string();
+    let reached_genesis = loop {
+        let block = fetch_json(client, &format!("{base_url}/block/{hash}"))?;
+        blocks.push(json!({ "hash": hash, "height": block["height"] }));
+        let Some(prev) = block["previousblockhash"].as_str() else {
+            break true;
+        };
+        if blocks.len() > depth as usize {
+            break false;
+        }
+        hash = prev.to_string();
+    };
+    let result = json!({
+        "depth": blocks.len() - 1,
+        "reached_genesis": reached_genesis,
+        "blocks": blocks,
+    });
+    Ok(result.to_string())
+}
+
+// The last `count` blocks, newest first, 10 per /blocks request
+fn fetch_recent_blocks(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    count: usize,
+) -> Result<Vec<Value>, String> {
+    let mut blocks: Vec<Value> = Vec::new();
+    let mut url = format!("{base_url}/blocks");
+    while blocks.len() < count {
+        let page = fetch_json(client, &url)?;
+        let page = page.as_array().ok_or("Blocks response is not an array")?;
+        blocks.extend(page.iter().cloned());
+        match blocks.last().and_then(|block| block["height"].as_u64()) {
+            Some(height) if height > 0 && !page.is_empty() => {
+                url = format!("{base_url}/blocks/{}", height - 1);
+            }
+            _ => break,
+        }
+    }
+    blocks.truncate(count);
+    Ok(blocks)
+}
+
+// Time between consecutive blocks among the latest `count`, walking the
+// pages of 10 block summaries served by /blocks and /blocks/:start_height
+fn fetch_block_intervals(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    count: usize,
+) -> Result<String, String> {
+    let blocks = fetch_recent_blocks(client, base_url, count)?;
+    let timestamps: Vec<i64> = blocks
+        .iter()
+        .map(|block| {
+            block["timestamp"]
+                .as_i64()
+                .ok_or("Block is missing timestamp")
+        })
+        .collect::<Result<_, _>>()?;
+    // Blocks come newest first
+    let intervals: Vec<i64> = timestamps.windows(2).map(|w| w[0] - w[1]).collect();
+    if intervals.is_empty() {
+        return Err("Not enough blocks to measure an interval".to_string());
+    }
+    let result = json!({
+        "block_count": blocks.len(),
+        "from_height": blocks.last().map(|block| &block["height"]),
+        "to_height": blocks[0]["height"],
+        "min_interval_secs": intervals.iter().min(),
+        "max_interval_secs": intervals.iter().max(),
+        "average_interval_secs": intervals.iter().sum::<i64>() as f64 / intervals.len() as f64,
+    });
+    Ok(result.to_string())
+}
+
+// Minimum, median, and maximum of sorted fee rates, in sat/vB
+fn feerate_stats(sorted: &[f64]) -> Value {
+    let round = |feerate: f64| (feerate * 100.0).round() / 100.0;
+    let (Some(min), Some(max)) = (sorted.first(), sorted.last()) else {
+        return json!({ "min_sat_vb": null, "median_sat_vb": null, "max_sat_vb": null });
+    };
+    let mid = sorted.len() / 2;
+    let median = if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    };
+    json!({
+        "min_sat_vb": round(*min),
+        "median_sat_vb": round(median),
+        "max_sat_vb": round(*max),
+    })
+}
+
+// The sampled transactions of each block of a /blocks listing, fetched
+// concurrently
+fn fetch_fee_samples(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    blocks: &[Value],
+) -> Vec<Result<Vec<Value>, String>> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = blocks
+            .iter()
+            .map(|block| {
+                let span = tracing::Span::current();
+                scope.spawn(move || {
+                    let _span = span.entered();
+                    let hash = block["id"].as_str().ok_or("Block is missing its id")?;
+                    let tx_count = block["tx_count"]
+                        .as_u64()
+                        .ok_or("Block is missing its tx_count")?;
+                    sample_block_txs(client, base_url, hash, tx_count as usize)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()))
+            })
+            .collect()
+    })
+}
+
+fn fetch_recent_fee_stats(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    count: usize,
+) -> Result<String, String> {
+    let blocks = fetch_recent_blocks(client, base_url, count)?;
+    let samples = fetch_fee_samples(client, base_url, &blocks);
+    let mut series = BatchResults::default();
+    let mut all = Vec::new();
+    for (block, sample) in blocks.iter().zip(samples) {
+        let entry = sample.map(|sample| {
+            let mut sample: Vec<f64> = sample.iter().filter_map(tx_feerate).collect();
+            sample.sort_by(f64::total_cmp);
+            let mut entry = feerate_stats(&sample);
+            entry["height"] = block["height"].clone();
+            entry["hash"] = block["id"].clone();
+            entry["tx_count"] = block["tx_count"].clone();
+            entry["sample_size"] = json!(sample.len());
+            all.extend(sample);
+            entry
+        });
+        series.push(block["height"].clone(), entry);
+    }
+    series.check_any_succeeded()?;
+    // Over the blocks sampled successfully
+    all.sort_by(f64::total_cmp);
+    let mut summary = feerate_stats(&all);
+    summary["sample_size"] = json!(all.len());
+    let result = json!({
+        "block_count": blocks.len(),
+        "from_height": blocks.last().map(|block| &block["height"]),
+        "to_height": blocks.first().map(|block| &block["height"]),
+        "complete": series.failed == 0,
+        "fetch_summary": series.summary(),
+        "blocks": series.items,
+        "summary": summary,
+    });
+    Ok(result.to_string())
+}
+
+// Fees divided by vsize of the transactions paying a fee, in sat/vB, along
+// with their count and total vsize
+fn weighted_feerate(txs: &[Value]) -> (Option<f64>, usize, u64) {
+    let (mut fees, mut vsize, mut count) = (0, 0, 0);
+    for tx in txs.iter().filter(|tx| tx_feerate(tx).is_some()) {
+        fees += tx["fee"].as_u64().unwrap_or(0);
+        vsize += tx["weight"].as_u64().unwrap_or(0).div_ceil(4);
+        count += 1;
+    }
+    let feerate = (vsize > 0).then(|| (fees as f64 / vsize as f64 * 100.0).round() / 100.0);
+    (feerate, count, vsize)
+}
+
+fn fetch_avg_feerate(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    count: usize,
+) -> Result<String, String> {
+    let blocks = fetch_recent_blocks(client, base_url, count)?;
+    let samples = fetch_fee_samples(client, base_url, &blocks);
+    let mut series = BatchResults::default();
+    let mut all = Vec::new();
+    for (block, sample) in blocks.iter().zip(samples) {
+        let entry = sample.map(|sample| {
+            let (feerate, sample_size, sample_vsize) = weighted_feerate(&sample);
+            all.extend(sample);
+            json!({
+                "height": block["height"],
+                "hash": block["id"],
+                "tx_count": block["tx_count"],
+                "avg_feerate_sat_vb": feerate,
+                "sample_size": sample_size,
+                "sample_vsize": sample_vsize,
+            })
+        });
+        series.push(block["height"].clone(), entry);
+    }
+    series.check_any_succeeded()?;
+    // Over the blocks sampled successfully
+    let (feerate, sample_size, sample_vsize) = weighted_feerate(&all);
+    let result = json!({
+        "block_count": blocks.len(),
+        "from_height": blocks.last().map(|block| &block["height"]),
+        "to_height": blocks.first().map(|block| &block["height"]),
+        "avg_feerate_sat_vb": feerate,
+        "sample_size": sample_size,
+        "sample_vsize": sample_vsize,
+        "complete": series.failed == 0,
+        "summary": series.summary(),
+        "blocks": series.items,
+    });
+    Ok(result.to_string())
+}
+
+// The pages of /block/:hash/txs walked for a block, up to a maximum
+struct BlockTxPages {
+    block: Value,
+    tx_count: usize,
+    truncated: bool,
+    pages: BatchResults,
+}
+
+// Walk the pages of transactions of a block, up to `max_pages`, folding each
+// with `per_page` given the index of its first transaction. A failed page is
+// reported with the others, the walk only fails when every page did.
+fn walk_block_txs(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    max_pages: usize,
+    mut per_page: impl FnMut(usize, &[Value]) -> Result<Value, String>,
+) -> Result<BlockTxPages, String> {
+    let block = fetch_json(client, &format!("{base_url}/block/{hash}"))?;
+    let tx_count = block["tx_count"]
+        .as_u64()
+        .ok_or("Block response is missing tx_count")? as usize;
+    let mut pages = BatchResults::default();
+    for start_index in (0..tx_count).step_by(BLOCK_TXS_PAGE_SIZE).take(max_pages) {
+        let page = fetch_json(
+            client,
+            &format!("{base_url}/block/{hash}/txs/{start_index}"),
+        )
+        .and_then(|txs| {
+            let txs = txs.as_array().ok_or("Block txs response is not an array")?;
+            per_page(start_index, txs)
+        });
+        pages.push(start_index, page);
+    }
+    pages.check_any_succeeded()?;
+    Ok(BlockTxPages {
+        block,
+        tx_count,
+        truncated: tx_count.div_ceil(BLOCK_TXS_PAGE_SIZE) > max_pages,
+        pages,
+    })
+}
+
+impl BlockTxPages {
+    // The result of a walk: its own `fields`, and the block and pages walked
+    fn result(self, hash: &str, fields: Value) -> String {
+        let mut result = json!({
+            "block_hash": hash,
+            "height": self.block["height"],
+            "tx_count": self.tx_count,
+            // Figures only cover the pages that were fetched successfully
+            "complete": self.pages.failed == 0 && !self.truncated,
+            "truncated": self.truncated,
+            "summary": self.pages.summary(),
+            "pages": self.pages.items,
+        });
+        if let (Value::Object(result), Value::Object(fields)) = (&mut result, fields) {
+            result.extend(fields);
+        }
+        result.to_string()
+    }
+}
+
+// Sum the output values of every transaction in a block
+fn fetch_block_volume(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    max_pages: usize,
+) -> Result<String, String> {
+    let mut total_output_sat: u64 = 0;
+    let mut coinbase_output_sat: u64 = 0;
+    let walk = walk_block_txs(client, base_url, hash, max_pages, |_, txs| {
+        let mut page_output_sat = 0;
+        for tx in txs {
+            let tx_output_sat: u64 = tx["vout"]
+                .as_array()
+                .map(|vout| vout.iter().filter_map(|o| o["value"].as_u64()).sum())
+                .unwrap_or(0);
+            let is_coinbase = tx["vin"][0]["is_coinbase"].as_bool().unwrap_or(false);
+            if is_coinbase {
+                coinbase_output_sat += tx_output_sat;
+            }
+            page_output_sat += tx_output_sat;
+        }
+        total_output_sat += page_output_sat;
+        Ok(json!({ "tx_count": txs.len(), "output_sat": page_output_sat }))
+    })?;
+    Ok(walk.result(
+        hash,
+        json!({
+            "total_output_sat": total_output_sat,
+            "coinbase_output_sat": coinbase_output_sat,
+            "net_volume_sat": total_output_sat - coinbase_output_sat,
+        }),
+    ))
+}
+
+#[derive(Default)]
+struct OpReturnStats {
+    txs: u64,
+    outputs: u64,
+    data_bytes: u64,
+    burned_sat: u64,
+    fees_sat: u64,
+}
+
+impl OpReturnStats {
+    fn add_tx(&mut self, tx: &Value) -> Result<(), String> {
+        let mut found = false;
+        for output in tx["vout"].as_array().into_iter().flatten() {
+            if output["scriptpubkey_type"] != "op_return" {
+                continue;
+            }
+            found = true;
+            let script = hex_decode(output["scriptpubkey"].as_str().unwrap_or_default())?;
+            self.outputs += 1;
+            self.data_bytes += op_return_data(&script).len() as u64;
+            self.burned_sat += output["value"].as_u64().unwrap_or(0);
+        }
+        if found {
+            self.txs += 1;
+            self.fees_sat += tx["fee"].as_u64().unwrap_or(0);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &OpReturnStats) {
+        self.txs += other.txs;
+        self.outputs += other.outputs;
+        self.data_bytes += other.data_bytes;
+        self.burned_sat += other.burned_sat;
+        self.fees_sat += other.fees_sat;
+    }
+}
+
+fn fetch_block_opreturn_stats(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    max_pages: usize,
+) -> Result<String, String> {
+    let mut stats = OpReturnStats::default();
+    let mut fetched_txs = 0;
+    let walk = walk_block_txs(client, base_url, hash, max_pages, |_, txs| {
+        // Counted apart so that a page failing halfway adds nothing
+        let mut page_stats = OpReturnStats::default();
+        for tx in txs {
+            page_stats.add_tx(tx)?;
+        }
+        fetched_txs += txs.len();
+        stats.merge(&page_stats);
+        Ok(json!({ "tx_count": txs.len(), "opreturn_tx_count": page_stats.txs }))
+    })?;
+
+    let share = if fetched_txs > 0 {
+        (stats.txs as f64 / fetched_txs as f64 * 10_000.0).round() / 100.0
+    } else {
+        0.0
+    };
+    Ok(walk.result(
+        hash,
+        json!({
+            "opreturn_tx_count": stats.txs,
+            "opreturn_tx_percent": share,
+            "opreturn_output_count": stats.outputs,
+            "data_bytes": stats.data_bytes,
+            "burned_sat": stats.burned_sat,
+            "opreturn_fees_sat": stats.fees_sat,
+        }),
+    ))
+}
+
+// The figures of a block transaction get_bitcoin_block_top_txs ranks by
+fn top_tx_entry(tx: &Value, position: usize) -> Value {
+    let value: u64 = tx["vout"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|output| output["value"].as_u64())
+        .sum();
+    let vsize = tx["weight"].as_u64().map(|weight| weight.div_ceil(4));
+    json!({
+        "txid": tx["txid"],
+        "position": position,
+        "value": value,
+        "fee": tx["fee"].as_u64().unwrap_or(0),
+        "size": tx["size"],
+        "vsize": vsize,
+        "feerate_sat_vb": tx_feerate(tx).map(|feerate| (feerate * 100.0).round() / 100.0),
+        "input_count": tx["vin"].as_array().map(Vec::len),
+        "output_count": tx["vout"].as_array().map(Vec::len),
+    })
+}
+
+// Rank the transactions of a block, keeping the top `limit` of the pages
+// walked so far
+fn fetch_block_top_txs(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    by: TopTxsMetric,
+    limit: usize,
+    max_pages: usize,
+) -> Result<String, String> {
+    let mut entries = Vec::new();
+    let walk = walk_block_txs(client, base_url, hash, max_pages, |start_index, txs| {
+        entries.extend(
+            txs.iter()
+                .enumerate()
+                .map(|(i, tx)| top_tx_entry(tx, start_index + i)),
+        );
+        // Stable, so that ties keep the block order
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry[by.as_str()].as_u64().unwrap_or(0)));
+        entries.truncate(limit);
+        Ok(json!({ "tx_count": txs.len() }))
+    })?;
+    Ok(walk.result(hash, json!({ "by": by.as_str(), "txs": entries })))
+}
+
+// Count the outputs of every transaction in a block by scriptpubkey_type
+fn fetch_block_address_types(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hash: &str,
+    max_pages: usize,
+) -> Result<String, String> {
+    let mut types: BTreeMap<String, u64> = BTreeMap::new();
+    let walk = walk_block_txs(client, base_url, hash, max_pages, |_, txs| {
+        let mut page_outputs = 0;
+        for output in txs.iter().filter_map(|tx| tx["vout"].as_array()).flatten() {
+            let script_type = output["scriptpubkey_type"].as_str().unwrap_or("unknown");
+            *types.entry(script_type.to_string()).or_default() += 1;
+            page_outputs += 1;
+        }
+        Ok(json!({ "tx_count": txs.len(), "output_count": page_outputs }))
+    })?;
+    Ok(walk.result(
+        hash,
+        json!({
+            "output_count": types.values().sum::<u64>(),
+            "script_types": types,
+        }),
+    ))
+}
+
+// Difficulty encoded by a compact nbits target, relative to the difficulty 1
+// target 0x1d00ffff
+fn difficulty_from_bits(bits: u32) -> f64 {
+    let exponent = (bits >> 24) as i32;
+    let mantissa = (bits & 0x00ff_ffff) as f64;
+    if mantissa == 0.0 {
+        return 0.0;
+    }
+    (0xffff as f64 / mantissa) * 256f64.powi(0x1d - exponent)
+}
+
+// Blocks are expected every BLOCK_INTERVAL_SECS, each taking difficulty * 2^32
+// hashes on average
+fn estimate_hashrate(difficulty: f64) -> f64 {
+    difficulty * 2f64.powi(32) / BLOCK_INTERVAL_SECS as f64
+}
+
+fn fetch_difficulty(client: &dyn EsploraClient, base_url: &str) -> Result<String, String> {
+    let tip_hash = fetch_esplora(client, &format!("{base_url}/blocks/tip/hash"))?;
+    let tip_hash = tip_hash.trim();
+    let block = fetch_json(client, &format!("{base_url}/block/{tip_hash}"))?;
+    let bits = block["bits"]
+        .as_u64()
+        .ok_or("Block response is missing bits")? as u32;
+    let difficulty = block["difficulty"]
+        .as_f64()
+        .unwrap_or_else(|| difficulty_from_bits(bits));
+    let hashrate = estimate_hashrate(difficulty);
+    let result = json!({
+        "block_hash": tip_hash,
+        "height": block["height"],
+        "difficulty": difficulty,
+        "bits": format!("{bits:08x}"),
+        "hashrate_hs": hashrate,
+        "hashrate": format!("{:.2} EH/s", hashrate / 1e18),
+    });
+    Ok(result.to_string())
+}
+
+// `work` added `n` times, by doubling
+fn multiply_work(mut work: Work, mut n: u64) -> Work {
+    let mut total = Work::from_be_bytes([0; 32]);
+    while n > 0 {
+        if n & 1 == 1 {
+            total = total + work;
+        }
+        work = work + work;
+        n >>= 1;
+    }
+    total
+}
+
+fn fetch_chainwork(client: &dyn EsploraClient, base_url: &str) -> Result<String, String> {
+    let tip_hash = fetch_esplora(client, &format!("{base_url}/blocks/tip/hash"))?;
+    let tip_hash = tip_hash.trim();
+    let block = fetch_json(client, &format!("{base_url}/block/{tip_hash}"))?;
+    let height = block["height"]
+        .as_u64()
+        .ok_or("Block response is missing height")?;
+    let (chainwork, source) = match block["chainwork"].as_str() {
+        Some(hex) => (
+            Work::from_unprefixed_hex(hex.trim_start_matches("0x"))
+                .map_err(|e| format!("Failed to parse chainwork {hex}: {e}"))?,
+            "backend",
+        ),
+        None => {
+            let bits = block["bits"]
+                .as_u64()
+                .ok_or("Block response is missing bits")? as u32;
+            let block_work = Target::from_compact(CompactTarget::from_consensus(bits)).to_work();
+            (multiply_work(block_work, height + 1), "estimate")
+        }
+    };
+    let log2 = chainwork.log2();
+    let mut result = json!({
+        "block_hash": tip_hash,
+        "height": height,
+        "source": source,
+        "chainwork_hex": hex_encode(&chainwork.to_be_bytes()),
+        "log2": (log2 * 100.0).round() / 100.0,
+        "approximate": format!("{:.3e} hashes (2^{log2:.2})", 2f64.powf(log2)),
+    });
+    if source == "estimate" {
+        result["note"] = json!(
+            "The backend doesn't expose chainwork: this assumes every block since genesis had the current difficulty, an upper bound well above the real chainwork"
+        );
+    }
+    Ok(result.to_string())
+}
+
+// Heights sampled to estimate the chain's transaction count, one request each
+const TX_ESTIMATE_SAMPLES: u64 = 10;
+
+// Average tx_count of the page of blocks ending at `height`
+fn fetch_average_tx_count(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    height: u64,
+) -> Result<f64, String> {
+    let page = fetch_json(client, &format!("{base_url}/blocks/{height}"))?;
+    let counts: Vec<u64> = page
+        .as_array()
+        .ok_or("Blocks response is not an array")?
+        .iter()
+        .filter_map(|block| block["tx_count"].as_u64())
+        .collect();
+    if counts.is_empty() {
+        return Err(format!("No block summaries at height {height}"));
+    }
+    Ok(counts.iter().sum::<u64>() as f64 / counts.len() as f64)
+}
+
+// Integrate the sampled transactions per block over the chain height with
+// the trapezoidal rule
+fn fetch_total_tx_estimate(client: &dyn EsploraClient, base_url: &str) -> Result<String, String> {
+    let tip_height: u64 = fetch_tip_height(client, base_url)?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to parse tip height: {e}"))?;
+    let mut heights: Vec<u64> = (0..TX_ESTIMATE_SAMPLES)
+        .map(|i| tip_height * i / (TX_ESTIMATE_SAMPLES - 1))
+        .collect();
+    heights.dedup();
+    let samples: Vec<(u64, Result<f64, String>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = heights
+            .iter()
+            .map(|&height| {
+                let span = tracing::Span::current();
+                let handle = scope.spawn(move || {
+                    let _span = span.entered();
+                    fetch_average_tx_count(client, base_url, height)
+                });
+                (height, handle)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(height, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()));
+                (height, result)
+            })
+            .collect()
+    });
+    let samples: Vec<(u64, f64)> = samples
+        .into_iter()
+        .map(|(height, average)| average.map(|average| (height, average)))
+        .collect::<Result<_, _>>()?;
+    // The genesis block, then the blocks of each span between samples
+    let estimate = samples[0].1
+        + samples
+            .windows(2)
+            .map(|w| (w[1].0 - w[0].0) as f64 * (w[0].1 + w[1].1) / 2.0)
+            .sum::<f64>();
+    let recent_average = samples[samples.len() - 1].1;
+    let result = json!({
+        "tip_height": tip_height,
+        "estimated_total_txs": estimate.round() as u64,
+        "recent_average_txs_per_block": (recent_average * 10.0).round() / 10.0,
+        "samples": samples
+            .iter()
+            .map(|(height, average)| json!({ "height": height, "average_tx_count": average }))
+            .collect::<Vec<_>>(),
+        "note": "Rough estimate integrated from sampled blocks, not an exact count",
+    });
+    Ok(result.to_string())
+}
+
+// Blocks between two difficulty adjustments
+const DIFFICULTY_EPOCH_BLOCKS: u64 = 2016;
+
+fn fetch_difficulty_adjustment(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    now: u64,
+) -> Result<String, String> {
+    let tip_height: u64 = fetch_tip_height(client, base_url)?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to parse tip height: {e}"))?;
+    let blocks_into_epoch = tip_height % DIFFICULTY_EPOCH_BLOCKS;
+    let blocks_remaining = DIFFICULTY_EPOCH_BLOCKS - blocks_into_epoch;
+    let eta_secs = blocks_remaining * BLOCK_INTERVAL_SECS;
+    let progress = blocks_into_epoch as f64 * 100.0 / DIFFICULTY_EPOCH_BLOCKS as f64;
+    let result = json!({
+        "tip_height": tip_height,
+        "epoch": tip_height / DIFFICULTY_EPOCH_BLOCKS,
+        "next_retarget_height": tip_height + blocks_remaining,
+        "blocks_remaining": blocks_remaining,
+        "progress_percent": (progress * 100.0).round() / 100.0,
+        "eta_secs": eta_secs,
+        "eta_utc": format_utc(now + eta_secs),
+    });
+    Ok(result.to_string())
+}
+
+fn estimate_block_eta(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    target_height: u32,
+    now: u64,
+) -> Result<String, String> {
+    let tip_height: u64 = fetch_tip_height(client, base_url)?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to parse tip height: {e}"))?;
+    let target_height = u64::from(target_height);
+    if target_height <= tip_height {
+        return Err(format!(
+            "Block {target_height} already exists, the current tip is at height {tip_height}"
+        ));
+    }
+    let blocks_remaining = target_height - tip_height;
+    let eta_secs = blocks_remaining * BLOCK_INTERVAL_SECS;
+    let result = json!({
+        "tip_height": tip_height,
+        "target_height": target_height,
+        "blocks_remaining": blocks_remaining,
+        "eta_secs": eta_secs,
+        "eta_utc": format_utc(now + eta_secs),
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esplora::testing::MockClient;
+    use rmcp::serde_json::{self, Value};
+
+    #[test]
+    fn test_fetch_block_volume() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":2}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[
+                    {"vin":[{"is_coinbase":true}],"vout":[{"value":5000000000}]},
+                    {"vin":[{"is_coinbase":false}],"vout":[{"value":100},{"value":200}]}
+                ]"#,
+            );
+        let result = fetch_block_volume(&client, "http://esplora", "00ff", 10).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["total_output_sat"], 5000000300u64);
+        assert_eq!(result["coinbase_output_sat"], 5000000000u64);
+        assert_eq!(result["net_volume_sat"], 300);
+        assert_eq!(result["complete"], true);
+        assert_eq!(result["summary"], json!({ "succeeded": 1, "failed": 0 }));
+    }
+
+    #[test]
+    fn test_fetch_block_volume_partial_failure() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":30}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[{"vin":[{"is_coinbase":false}],"vout":[{"value":100}]}]"#,
+            );
+        let result = fetch_block_volume(&client, "http://esplora", "00ff", 10).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["total_output_sat"], 100);
+        assert_eq!(result["complete"], false);
+        assert_eq!(result["summary"], json!({ "succeeded": 1, "failed": 1 }));
+        assert_eq!(result["pages"][1]["key"], 25);
+        assert_eq!(result["pages"][1]["ok"], false);
+    }
+
+    #[test]
+    fn test_fetch_block_volume_page_cap() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":60}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[{"vin":[{"is_coinbase":false}],"vout":[{"value":100}]}]"#,
+            )
+            .with(
+                "http://esplora/block/00ff/txs/25",
+                r#"[{"vin":[{"is_coinbase":false}],"vout":[{"value":200}]}]"#,
+            );
+        let result = fetch_block_volume(&client, "http://esplora", "00ff", 2).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["total_output_sat"], 300);
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["complete"], false);
+        // The third page, past the cap, is never requested
+        assert_eq!(client.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_fetch_block_opreturn_stats() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":3}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[
+                    {"fee":0,"vout":[{"value":5000000000,"scriptpubkey_type":"p2pkh"},
+                        {"value":0,"scriptpubkey_type":"op_return","scriptpubkey":"6a24aa21a9ed"}]},
+                    {"fee":300,"vout":[{"value":0,"scriptpubkey_type":"op_return","scriptpubkey":"6a0568656c6c6f"},
+                        {"value":10,"scriptpubkey_type":"op_return","scriptpubkey":"6a0101"}]},
+                    {"fee":200,"vout":[{"value":100,"scriptpubkey_type":"v0_p2wpkh"}]}
+                ]"#,
+            );
+        let result = fetch_block_opreturn_stats(&client, "http://esplora", "00ff", 10).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["opreturn_tx_count"], 2);
+        assert_eq!(result["opreturn_output_count"], 3);
+        // The coinbase's truncated push counts what is there, plus "hello" and 0x01
+        assert_eq!(result["data_bytes"], 4 + 5 + 1);
+        assert_eq!(result["burned_sat"], 10);
+        assert_eq!(result["opreturn_fees_sat"], 300);
+        assert_eq!(result["opreturn_tx_percent"], 66.67);
+        assert_eq!(result["complete"], true);
+        assert_eq!(result["truncated"], false);
+    }
+
+    #[test]
+    fn test_fetch_block_opreturn_stats_page_cap() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":30}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[{"fee":300,"vout":[{"value":0,"scriptpubkey_type":"op_return","scriptpubkey":"6a0101"}]}]"#,
+            );
+        let result = fetch_block_opreturn_stats(&client, "http://esplora", "00ff", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["opreturn_tx_count"], 1);
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["complete"], false);
+        // The second page, past the cap, is never requested
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_fetch_block_top_txs() {
+        let tx = |txid: &str, fee: u64, size: u64, values: &[u64]| {
+            let outputs: Vec<Value> = values
+                .iter()
+                .map(|value| json!({ "value": value }))
+                .collect();
+            json!({ "txid": txid, "fee": fee, "size": size, "weight": size * 4, "vin": [{}], "vout": outputs })
+        };
+        let page = json!([
+            tx("cb", 0, 200, &[312_500_000]),
+            tx("a1", 500, 250, &[1000, 2000]),
+            tx("a2", 9000, 400, &[50_000]),
+            tx("a3", 500, 1200, &[400_000_000, 1]),
+        ]);
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":4}"#)
+            .with("http://esplora/block/00ff/txs/0", &page.to_string());
+        let top = |by, limit| {
+            let result =
+                fetch_block_top_txs(&client, "http://esplora", "00ff", by, limit, 10).unwrap();
+            let result: Value = serde_json::from_str(&result).unwrap();
+            let txids: Vec<String> = result["txs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|tx| tx["txid"].as_str().unwrap().to_string())
+                .collect();
+            txids
+        };
+        assert_eq!(top(TopTxsMetric::Value, 2), ["a3", "cb"]);
+        // a1 and a3 pay the same fee, a1 comes first in the block
+        assert_eq!(top(TopTxsMetric::Fee, 3), ["a2", "a1", "a3"]);
+        assert_eq!(top(TopTxsMetric::Size, 10), ["a3", "a2", "a1", "cb"]);
+
+        let result =
+            fetch_block_top_txs(&client, "http://esplora", "00ff", TopTxsMetric::Fee, 1, 10)
+                .unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["txs"][0]["position"], 2);
+        assert_eq!(result["txs"][0]["vsize"], 400);
+        assert_eq!(result["txs"][0]["feerate_sat_vb"], 22.5);
+        assert_eq!(result["complete"], true);
+    }
+
+    #[test]
+    fn test_fetch_block_top_txs_page_cap() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":30}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[{"txid":"a1","fee":100},{"txid":"a2","fee":300}]"#,
+            )
+            .with(
+                "http://esplora/block/00ff/txs/25",
+                r#"[{"txid":"b1","fee":200}]"#,
+            );
+        let top = |max_pages| {
+            let result = fetch_block_top_txs(
+                &client,
+                "http://esplora",
+                "00ff",
+                TopTxsMetric::Fee,
+                2,
+                max_pages,
+            )
+            .unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = top(2);
+        assert_eq!(result["txs"][0]["txid"], "a2");
+        assert_eq!(result["txs"][1]["txid"], "b1");
+        assert_eq!(result["truncated"], false);
+        // Past the cap, the ranking only covers the first page
+        let result = top(1);
+        assert_eq!(result["txs"][1]["txid"], "a1");
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["complete"], false);
+    }
+
+    #[test]
+    fn test_fetch_block_address_types() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":2}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[
+                    {"vout":[{"scriptpubkey_type":"v1_p2tr"},{"scriptpubkey_type":"op_return"}]},
+                    {"vout":[{"scriptpubkey_type":"v1_p2tr"},{"scriptpubkey_type":"p2pkh"}]}
+                ]"#,
+            );
+        let result = fetch_block_address_types(&client, "http://esplora", "00ff", 10).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["script_types"],
+            json!({ "op_return": 1, "p2pkh": 1, "v1_p2tr": 2 })
+        );
+        assert_eq!(result["output_count"], 4);
+        assert_eq!(result["complete"], true);
+    }
+
+    #[test]
+    fn test_fetch_block_address_types_page_cap() {
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"height":1,"tx_count":30}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[{"vout":[{"scriptpubkey_type":"v1_p2tr"}]}]"#,
+            );
+        let result = fetch_block_address_types(&client, "http://esplora", "00ff", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["script_types"], json!({ "v1_p2tr": 1 }));
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["complete"], false);
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_subsidy_schedule() {
+        let schedule = subsidy_schedule(840_000);
+        assert_eq!(schedule["subsidy_sat"], 312_500_000);
+        assert_eq!(schedule["halving_epoch"], 4);
+        assert_eq!(schedule["next_halving_height"], 1_050_000);
+        assert_eq!(schedule["blocks_until_next_halving"], 210_000);
+        // The last satoshi of subsidy is paid in epoch 32
+        assert_eq!(subsidy_schedule(32 * 210_000)["subsidy_sat"], 1);
+        let schedule = subsidy_schedule(33 * 210_000);
+        assert_eq!(schedule["subsidy_sat"], 0);
+        assert_eq!(schedule["next_halving_height"], Value::Null);
+
+        let client = MockClient::default().with("http://esplora/blocks/tip/height", "839999\n");
+        let result = fetch_subsidy(&client, "http://esplora", None).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["subsidy_sat"], 625_000_000);
+        assert_eq!(result["blocks_until_next_halving"], 1);
+    }
+
+    #[test]
+    fn test_block_subsidy_sat() {
+        assert_eq!(block_subsidy_sat(0), 5_000_000_000);
+        assert_eq!(block_subsidy_sat(209_999), 5_000_000_000);
+        assert_eq!(block_subsidy_sat(210_000), 2_500_000_000);
+        assert_eq!(block_subsidy_sat(840_000), 312_500_000);
+        assert_eq!(block_subsidy_sat(64 * 210_000), 0);
+    }
+
+    #[test]
+    fn test_fetch_block_reward() {
+        let client = MockClient::default().with(
+            "http://esplora/block/00ff/txs/0",
+            r#"[{"txid":"cb","status":{"block_height":840000},
+                 "vout":[{"value":312500000},{"value":12345},{"value":0}]}]"#,
+        );
+        let result = fetch_block_reward(&client, "http://esplora", "00ff").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["reward_sat"], 312512345u64);
+        assert_eq!(result["subsidy_sat"], 312500000u64);
+        assert_eq!(result["fees_sat"], 12345);
+    }
+
+    #[test]
+    fn test_fetch_adjacent_block() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/block/b1",
+                r#"{"id":"b1","height":1,"previousblockhash":"b0"}"#,
+            )
+            .with("http://esplora/block/b0", r#"{"id":"b0","height":0}"#)
+            .with("http://esplora/block-height/2", "b2")
+            .with("http://esplora/block/b2", r#"{"id":"b2","height":2}"#);
+        let next = fetch_adjacent_block(&client, "http://esplora", "b1", BlockDirection::Next);
+        assert_eq!(next.unwrap(), r#"{"id":"b2","height":2}"#);
+        let prev = fetch_adjacent_block(&client, "http://esplora", "b1", BlockDirection::Prev);
+        assert_eq!(prev.unwrap(), r#"{"id":"b0","height":0}"#);
+
+        let err = fetch_adjacent_block(&client, "http://esplora", "b0", BlockDirection::Prev);
+        assert!(err.unwrap_err().contains("genesis"));
+        let err = fetch_adjacent_block(&client, "http://esplora", "b2", BlockDirection::Next);
+        assert!(err.unwrap_err().contains("chain tip"));
+    }
+
+    #[test]
+    fn test_parse_block_alias() {
+        assert_eq!(parse_block_alias("genesis"), Ok(BlockAlias::Height(0)));
+        assert_eq!(parse_block_alias(" Tip "), Ok(BlockAlias::Tip));
+        assert_eq!(
+            parse_block_alias("halving:4"),
+            Ok(BlockAlias::Height(840_000))
+        );
+        let err = parse_block_alias("halving:x").unwrap_err();
+        assert!(
+            err.contains("genesis, tip, halving:N"),
+            "unexpected error: {err}"
+        );
+        assert!(parse_block_alias("latest").is_err());
+    }
+
+    #[test]
+    fn test_fetch_block_by_alias() {
+        let client = MockClient::default()
+            .with("http://esplora/block-height/840000", "00ab\n")
+            .with("http://esplora/block/00ab", r#"{"height":840000}"#);
+        let result =
+            fetch_block_by_alias(&client, "http://esplora", BlockAlias::Height(840_000)).unwrap();
+        assert_eq!(result, r#"{"height":840000}"#);
+
+        let err = fetch_block_by_alias(&client, "http://esplora", BlockAlias::Height(1_050_000))
+            .unwrap_err();
+        assert_eq!(err, "There is no block at height 1050000 yet");
+    }
+
+    #[test]
+    fn test_check_blocks_range() {
+        assert!(check_blocks_range(10, 10, 50).is_ok());
+        assert!(check_blocks_range(0, 49, 50).is_ok());
+        assert_eq!(
+            check_blocks_range(0, 50, 50).unwrap_err(),
+            "Range of 51 blocks exceeds the configured maximum of 50, retry with at most 50 blocks per call"
+        );
+        assert!(check_blocks_range(5, 4, 50).is_err());
+    }
+
+    #[test]
+    fn test_fetch_blocks_range() {
+        let client = MockClient::default()
+            .with("http://esplora/block-height/1", "00aa")
+            .with("http://esplora/block/00aa", r#"{"height":1}"#)
+            .with("http://esplora/block-height/3", "00cc")
+            .with("http://esplora/block/00cc", r#"{"height":3}"#);
+        let result = fetch_blocks_range(&client, "http://esplora", 1, 3).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["summary"], json!({ "succeeded": 2, "failed": 1 }));
+        let blocks = result["blocks"].as_array().unwrap();
+        assert_eq!(blocks[0]["data"]["height"], 1);
+        assert_eq!(blocks[1]["key"], 2);
+        assert_eq!(blocks[1]["error"], "There is no block at height 2 yet");
+        assert_eq!(blocks[2]["data"]["height"], 3);
+    }
+
+    #[test]
+    fn test_coinbase_message() {
+        // Height push of block 840000, then a pool tag
+        let scriptsig =
+            hex_decode("0340d10c192f5669614254432f4d696e6564206279206275696c646572732f").unwrap();
+        assert_eq!(coinbase_message(&scriptsig), "@ /ViaBTC/Mined by builders/");
+    }
+
+    #[test]
+    fn test_fetch_recent_fee_stats() {
+        let page = |rates: &[u64]| {
+            let txs: Vec<Value> = rates
+                .iter()
+                .map(|rate| json!({ "fee": rate * 100, "weight": 400 }))
+                .collect();
+            Value::Array(txs).to_string()
+        };
+        let client = MockClient::default()
+            .with(
+                "http://esplora/blocks",
+                r#"[{"id":"00bb","height":11,"tx_count":4},{"id":"00aa","height":10,"tx_count":3}]"#,
+            )
+            .with("http://esplora/block/00bb/txs/0", &page(&[0, 30, 10, 20]))
+            .with("http://esplora/block/00aa/txs/0", &page(&[0, 2, 4]));
+        let result = fetch_recent_fee_stats(&client, "http://esplora", 2).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        let block = &result["blocks"][0]["data"];
+        assert_eq!(block["height"], 11);
+        assert_eq!(block["min_sat_vb"], 10.0);
+        assert_eq!(block["median_sat_vb"], 20.0);
+        assert_eq!(block["max_sat_vb"], 30.0);
+        assert_eq!(result["blocks"][1]["data"]["median_sat_vb"], 3.0);
+        assert_eq!(result["summary"]["sample_size"], 5);
+        assert_eq!(result["summary"]["median_sat_vb"], 10.0);
+        assert_eq!(result["summary"]["min_sat_vb"], 2.0);
+        assert_eq!(result["complete"], true);
+        // The tx_count of the /blocks listing is used, blocks aren't fetched again
+        assert_eq!(client.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_fetch_recent_fee_stats_partial_failure() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/blocks",
+                r#"[{"id":"00bb","height":11,"tx_count":2},{"id":"00aa","height":10,"tx_count":2}]"#,
+            )
+            .with(
+                "http://esplora/block/00aa/txs/0",
+                r#"[{"fee":0,"weight":400},{"fee":500,"weight":400}]"#,
+            );
+        let result = fetch_recent_fee_stats(&client, "http://esplora", 2).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["blocks"][0]["ok"], false);
+        assert_eq!(result["blocks"][1]["data"]["median_sat_vb"], 5.0);
+        assert_eq!(result["summary"]["sample_size"], 1);
+        assert_eq!(result["complete"], false);
+        assert_eq!(
+            result["fetch_summary"],
+            json!({ "succeeded": 1, "failed": 1 })
+        );
+    }
+
+    #[test]
+    fn test_fetch_avg_feerate() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/blocks",
+                r#"[{"id":"00bb","height":11,"tx_count":3},{"id":"00aa","height":10,"tx_count":2}]"#,
+            )
+            .with(
+                "http://esplora/block/00bb/txs/0",
+                r#"[{"fee":0,"weight":800},{"fee":1000,"weight":400},{"fee":18000,"weight":3600}]"#,
+            )
+            .with(
+                "http://esplora/block/00aa/txs/0",
+                r#"[{"fee":0,"weight":800},{"fee":200,"weight":400}]"#,
+            );
+        let result = fetch_avg_feerate(&client, "http://esplora", 2).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        // 10 sat/vB over 100 vB and 20 sat/vB over 900 vB, weighted by size
+        assert_eq!(result["blocks"][0]["data"]["avg_feerate_sat_vb"], 19.0);
+        assert_eq!(result["blocks"][0]["data"]["sample_size"], 2);
+        assert_eq!(result["blocks"][1]["data"]["avg_feerate_sat_vb"], 2.0);
+        // 19,200 sats over 1,100 vB
+        assert_eq!(result["avg_feerate_sat_vb"], 17.45);
+        assert_eq!(result["sample_size"], 3);
+        assert_eq!(result["sample_vsize"], 1100);
+        assert_eq!(result["complete"], true);
+    }
+
+    #[test]
+    fn test_fetch_avg_feerate_partial_failure() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/blocks",
+                r#"[{"id":"00bb","height":11,"tx_count":2},{"id":"00aa","height":10,"tx_count":2}]"#,
+            )
+            .with(
+                "http://esplora/block/00bb/txs/0",
+                r#"[{"fee":0,"weight":800},{"fee":1000,"weight":400}]"#,
+            );
+        let result = fetch_avg_feerate(&client, "http://esplora", 2).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["avg_feerate_sat_vb"], 10.0);
+        assert_eq!(result["complete"], false);
+        assert_eq!(result["summary"], json!({ "succeeded": 1, "failed": 1 }));
+        assert_eq!(result["blocks"][1]["key"], 10);
+        assert_eq!(result["blocks"][1]["ok"], false);
+
+        let client = MockClient::default().with(
+            "http://esplora/blocks",
+            r#"[{"id":"00bb","height":11,"tx_count":2}]"#,
+        );
+        assert!(fetch_avg_feerate(&client, "http://esplora", 1).is_err());
+    }
+
+    #[test]
+    fn test_fetch_block_txs() {
+        let page: Vec<Value> = (0..BLOCK_TXS_PAGE_SIZE)
+            .map(|i| json!({ "txid": format!("t{i}") }))
+            .collect();
+        let client = MockClient::default()
+            .with("http://esplora/block/00ff", r#"{"tx_count":26}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                &Value::Array(page).to_string(),
+            )
+            .with("http://esplora/block/00ff/txs/25", r#"[{"txid":"t25"}]"#);
+        let result = fetch_block_txs(&client, "http://esplora", "00ff", 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["page_info"],
+            json!({ "count": 25, "has_more": true, "next_cursor": 25 })
+        );
+        let result = fetch_block_txs(&client, "http://esplora", "00ff", 25).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["txs"][0]["txid"], "t25");
+        assert_eq!(result["page_info"]["has_more"], false);
+        assert_eq!(result["page_info"]["next_cursor"], Value::Null);
+        assert!(fetch_block_txs(&client, "http://esplora", "00ff", 50).is_err());
+    }
+
+    #[test]
+    fn test_fetch_block_detailed() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/block/00ff",
+                r#"{"id":"00ff","height":840000}"#,
+            )
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                r#"[{"txid":"cb","status":{"block_height":840000},
+                     "vin":[{"scriptsig":"0340d10c062f466f6f2f","is_coinbase":true}],
+                     "vout":[{"value":312500100}]}]"#,
+            );
+        let result = fetch_block_detailed(&client, "http://esplora", "00ff").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["id"], "00ff");
+        assert_eq!(result["coinbase"]["txid"], "cb");
+        assert_eq!(result["coinbase"]["message"], "@ /Foo/");
+        assert_eq!(result["fees_sat"], 100);
+    }
+
+    #[test]
+    fn test_parse_block_ref() {
+        assert_eq!(parse_block_ref(" 840000 "), Ok(BlockRef::Height(840_000)));
+        let hash = "00".repeat(32);
+        assert_eq!(parse_block_ref(&hash), Ok(BlockRef::Hash(hash.clone())));
+        assert!(parse_block_ref("tip").is_err());
+        assert_eq!(normalize_txid_prefix(" 0xABcd"), Ok("abcd".to_string()));
+        assert!(normalize_txid_prefix("").is_err());
+        assert!(normalize_txid_prefix("xyz").is_err());
+    }
+
+    #[test]
+    fn test_merkle_root_odd_level() {
+        use bitcoin::hashes::Hash;
+        let txids: Vec<bitcoin::Txid> = (1..=3u8)
+            .map(|b| bitcoin::Txid::from_byte_array([b; 32]))
+            .collect();
+        let expected = bitcoin::merkle_tree::calculate_root(txids.iter().copied()).unwrap();
+        let root = merkle_root(txids.iter().map(|txid| txid.to_byte_array()).collect());
+        assert_eq!(root, Some(expected.to_byte_array()));
+        assert_eq!(merkle_root(vec![[7; 32]]), Some([7; 32]));
+        assert_eq!(merkle_root(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_verify_block_merkle() {
+        // Block 100000
+        let client = MockClient::default()
+            .with(
+                "http://esplora/block/00aa",
+                r#"{"merkle_root":"f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766"}"#,
+            )
+            .with(
+                "http://esplora/block/00aa/txids",
+                r#"["8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+                    "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+                    "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+                    "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d"]"#,
+            );
+        let result = verify_block_merkle(&client, "http://esplora", "00aa").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["valid"], true);
+        assert_eq!(result["tx_count"], 4);
+    }
+
+    #[test]
+    fn test_find_txids_by_prefix() {
+        let client = MockClient::default()
+            .with("http://esplora/block-height/7", "00aa")
+            .with(
+                "http://esplora/block/00aa/txids",
+                r#"["ab01","cd02","ab03"]"#,
+            );
+        let result =
+            find_txids_by_prefix(&client, "http://esplora", BlockRef::Height(7), "ab", 100)
+                .unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["block_hash"], "00aa");
+        assert_eq!(result["matches"], json!(["ab01", "ab03"]));
+        assert_eq!(result["txs_searched"], 3);
+        assert_eq!(result["truncated"], false);
+
+        let result =
+            find_txids_by_prefix(&client, "http://esplora", BlockRef::Height(7), "ab", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["matches"], json!(["ab01"]));
+        assert_eq!(result["count"], 2);
+        assert_eq!(result["truncated"], true);
+    }
+
+    #[test]
+    fn test_estimate_block_eta() {
+        let client = MockClient::default().with("http://esplora/blocks/tip/height", "840000");
+        let result = estimate_block_eta(&client, "http://esplora", 840_006, 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["blocks_remaining"], 6);
+        assert_eq!(result["eta_secs"], 3600);
+        assert_eq!(result["eta_utc"], "1970-01-01T01:00:00Z");
+
+        let err = estimate_block_eta(&client, "http://esplora", 840_000, 0).unwrap_err();
+        assert!(err.contains("already exists"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_fetch_total_tx_estimate() {
+        // Constant 1 tx per block up to 900, with a sparse page at each height
+        let mut client = MockClient::default().with("http://esplora/blocks/tip/height", "900");
+        for height in (0..=900).step_by(100) {
+            let tx_count = if height == 900 { 3 } else { 1 };
+            client = client.with(
+                &format!("http://esplora/blocks/{height}"),
+                &json!([{ "height": height, "tx_count": tx_count }]).to_string(),
+            );
+        }
+        let result = fetch_total_tx_estimate(&client, "http://esplora").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["tip_height"], 900);
+        // 1 + 8 spans of 100 blocks at 1 tx + a last one ramping from 1 to 3
+        assert_eq!(result["estimated_total_txs"], 1001);
+        assert_eq!(result["recent_average_txs_per_block"], 3.0);
+        assert_eq!(result["samples"].as_array().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_fetch_difficulty_adjustment() {
+        let client = MockClient::default().with("http://esplora/blocks/tip/height", "839160");
+        let result = fetch_difficulty_adjustment(&client, "http://esplora", 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["epoch"], 416);
+        assert_eq!(result["next_retarget_height"], 840_672);
+        assert_eq!(result["blocks_remaining"], 1512);
+        assert_eq!(result["progress_percent"], 25.0);
+        assert_eq!(result["eta_secs"], 1512 * 600);
+    }
+
+    #[test]
+    fn test_difficulty_from_bits() {
+        assert_eq!(difficulty_from_bits(0x1d00ffff), 1.0);
+        // Block 840000
+        let difficulty = difficulty_from_bits(0x17034219);
+        assert!((difficulty - 86_388_558_925_171.02).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_fetch_difficulty() {
+        let client = MockClient::default()
+            .with("http://esplora/blocks/tip/hash", "00ff\n")
+            .with(
+                "http://esplora/block/00ff",
+                r#"{"id":"00ff","height":840000,"bits":386089497}"#,
+            );
+        let result = fetch_difficulty(&client, "http://esplora").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["bits"], "17034219");
+        assert_eq!(result["hashrate"], "618.39 EH/s");
+    }
+
+    #[test]
+    fn test_fetch_chainwork() {
+        // Block 840000
+        let chainwork = "0000000000000000000000000000000000000000753b8c1eaae701e1f0146360";
+        let client = MockClient::default()
+            .with("http://esplora/blocks/tip/hash", "00ff\n")
+            .with(
+                "http://esplora/block/00ff",
+                &format!(
+                    r#"{{"id":"00ff","height":840000,"bits":386089497,"chainwork":"{chainwork}"}}"#
+                ),
+            );
+        let result = fetch_chainwork(&client, "http://esplora").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["source"], "backend");
+        assert_eq!(result["chainwork_hex"], chainwork);
+        assert_eq!(result["log2"], 94.87);
+        assert!(result.get("note").is_none());
+
+        let client = MockClient::default()
+            .with("http://esplora/blocks/tip/hash", "00ff\n")
+            .with(
+                "http://esplora/block/00ff",
+                r#"{"id":"00ff","height":1,"bits":486604799}"#,
+            );
+        let result = fetch_chainwork(&client, "http://esplora").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["source"], "estimate");
+        // Two blocks at difficulty 1, 0x100010001 hashes each
+        assert_eq!(
+            result["chainwork_hex"],
+            format!("{:064x}", 2 * 0x1_0001_0001u64)
+        );
+        assert!(result["note"].is_string());
+    }
+
+    #[test]
+    fn test_fetch_block_ancestry() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/block/02",
+                r#"{"id":"02","height":2,"previousblockhash":"01"}"#,
+            )
+            .with(
+                "http://esplora/block/01",
+                r#"{"id":"01","height":1,"previousblockhash":"00"}"#,
+            )
+            .with(
+                "http://esplora/block/00",
+                r#"{"id":"00","height":0,"previousblockhash":null}"#,
+            );
+        let result = fetch_block_ancestry(&client, "http://esplora", "02", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result["blocks"],
+            json!([{ "hash": "02", "height": 2 }, { "hash": "01", "height": 1 }])
+        );
+        assert_eq!(result["reached_genesis"], false);
+
+        let result = fetch_block_ancestry(&client, "http://esplora", "02", 20).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["depth"], 2);
+        assert_eq!(result["blocks"][2]["height"], 0);
+        assert_eq!(result["reached_genesis"], true);
+    }
+
+    #[test]
+    fn test_fetch_blocks_by_time() {
+        // A block every 10 minutes from height 0 to 100
+        let mut client = MockClient::default().with("http://esplora/blocks/tip/height", "100");
+        for top in 0..=100u64 {
+            let blocks: Vec<Value> = (0..=top.min(9))
+                .map(|i| json!({ "height": top - i, "id": format!("h{}", top - i), "timestamp": 1_000_000 + (top - i) * 600 }))
+                .collect();
+            client = client.with(
+                &format!("http://esplora/blocks/{top}"),
+                &Value::Array(blocks).to_string(),
+            );
+        }
+        let (start, end) = (1_000_000 + 50 * 600 - 100, 1_000_000 + 60 * 600);
+        let result = fetch_blocks_by_time(&client, "http://esplora", start, end, 50).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["block_count"], 11);
+        assert_eq!(result["start_height"], 50);
+        assert_eq!(result["end_height"], 60);
+        assert_eq!(result["truncated"], false);
+        let heights: Vec<u64> = result["blocks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|block| block["height"].as_u64().unwrap())
+            .collect();
+        assert_eq!(heights, (50..=60).collect::<Vec<_>>());
+        assert_eq!(result["blocks"][0]["hash"], "h50");
+
+        let result = fetch_blocks_by_time(&client, "http://esplora", start, end, 5).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["next_height"], 55);
+        assert_eq!(result["blocks"].as_array().unwrap().len(), 5);
+
+        // A window after the tip
+        let result =
+            fetch_blocks_by_time(&client, "http://esplora", 2_000_000, 2_000_100, 50).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["block_count"], 0);
+        assert_eq!(result["start_height"], Value::Null);
+
+        // A window running to the end of time ends at the tip
+        let result = fetch_blocks_by_time(&client, "http://esplora", start, u64::MAX, 5).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["block_count"], 51);
+        assert_eq!(result["end_height"], 100);
+    }
+
+    #[test]
+    fn test_fetch_block_intervals() {
+        let page = |top: u64| {
+            let blocks: Vec<Value> = (0..10)
+                .map(|i| json!({ "height": top - i, "timestamp": 1_000_000 + (top - i) * 600 }))
+                .collect();
+            Value::Array(blocks).to_string()
+        };
+        let client = MockClient::default()
+            .with("http://esplora/blocks", &page(100))
+            .with("http://esplora/blocks/90", &page(90));
+        let result = fetch_block_intervals(&client, "http://esplora", 12).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["block_count"], 12);
+        assert_eq!(result["from_height"], 89);
+        assert_eq!(result["to_height"], 100);
+        assert_eq!(result["min_interval_secs"], 600);
+        assert_eq!(result["max_interval_secs"], 600);
+        assert_eq!(result["average_interval_secs"], 600.0);
+        assert_eq!(
+            client.requests(),
+            ["http://esplora/blocks", "http://esplora/blocks/90"]
+        );
+    }
+}
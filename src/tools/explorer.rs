@@ -0,0 +1,182 @@
+// Links to block explorers, built locally without any network call
+use super::{ToolDef, make_schema, normalize_hex_id, parse_params};
+use crate::config::Chain;
+use crate::esplora::BitcoinNetwork;
+use rmcp::model::{CallToolResult, Content, ErrorData};
+use rmcp::schemars;
+use rmcp::serde_json::{self, Value, json};
+use serde::Deserialize;
+use std::str::FromStr;
+
+pub(super) const TOOLS: &[ToolDef] = &[ToolDef {
+    name: "get_bitcoin_explorer_links",
+    chain: Some(Chain::Bitcoin),
+    description: "Build links to the pages of block explorers (blockstream.info and mempool.space unless configured otherwise) for a Bitcoin transaction, address, or block, to hand clickable URLs to the user. No network call is made. The kind of object is detected from its format: a Bitcoin address, or a 64-character hex id, taken as a block hash when it starts with 8 zeros (proof of work) and as a txid otherwise; pass kind to override. Returns the detected kind and one URL per explorer.",
+    schema: make_schema::<GetBitcoinExplorerLinksParams>,
+    handler: |server, args| {
+        let params: GetBitcoinExplorerLinksParams = parse_params(args)?;
+        let result = explorer_links(
+            &server.config.explorers,
+            &params.id,
+            params.kind,
+            params.network,
+        )
+        .map_err(|e| ErrorData::invalid_request(e, None))?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    },
+}];
+
+#[derive(Deserialize, schemars::JsonSchema, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum ObjectKind {
+    Tx,
+    Address,
+    Block,
+}
+
+impl ObjectKind {
+    // Path segment of the object's page, the same on both explorers
+    fn path(&self) -> &'static str {
+        match self {
+            ObjectKind::Tx => "tx",
+            ObjectKind::Address => "address",
+            ObjectKind::Block => "block",
+        }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinExplorerLinksParams {
+    #[schemars(description = "The txid, address, or block hash to link to.")]
+    id: String,
+    #[schemars(
+        description = "What id is: 'tx', 'address', or 'block' (optional, detected from its format)."
+    )]
+    #[serde(default)]
+    kind: Option<ObjectKind>,
+    #[schemars(
+        description = "The Bitcoin network of the object: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+// Block hashes are below the proof-of-work target, which is never above
+// difficulty 1's 32 leading zero bits
+fn detect_kind(id: &str) -> Result<(ObjectKind, String), String> {
+    if let Ok(hex) = normalize_hex_id(id) {
+        let kind = if hex.starts_with("00000000") {
+            ObjectKind::Block
+        } else {
+            ObjectKind::Tx
+        };
+        return Ok((kind, hex));
+    }
+    if bitcoin::Address::from_str(id.trim()).is_ok() {
+        return Ok((ObjectKind::Address, id.trim().to_string()));
+    }
+    Err(format!(
+        "'{id}' is neither a Bitcoin address nor a 64-character hex txid or block hash"
+    ))
+}
+
+fn explorer_links(
+    explorers: &[(String, String)],
+    id: &str,
+    kind: Option<ObjectKind>,
+    network: BitcoinNetwork,
+) -> Result<String, String> {
+    let (detected, id) = detect_kind(id)?;
+    let kind = kind.unwrap_or(detected);
+    if (kind == ObjectKind::Address) != (detected == ObjectKind::Address) {
+        return Err(format!("'{id}' is not a valid {} id", kind.path()));
+    }
+    if kind == ObjectKind::Address {
+        let address = bitcoin::Address::from_str(&id).map_err(|e| e.to_string())?;
+        if !address.is_valid_for_network(network.to_bitcoin_network()) {
+            return Err(format!(
+                "Address '{id}' is not a {} address",
+                network.as_str()
+            ));
+        }
+    }
+    let network_path = match network {
+        BitcoinNetwork::Mainnet => "",
+        BitcoinNetwork::Testnet => "/testnet",
+        BitcoinNetwork::Signet => "/signet",
+    };
+    let links: serde_json::Map<String, Value> = explorers
+        .iter()
+        .map(|(name, base)| {
+            let url = format!("{base}{network_path}/{}/{id}", kind.path());
+            (name.clone(), json!(url))
+        })
+        .collect();
+    let result = json!({
+        "kind": kind.path(),
+        "id": id,
+        "network": network.as_str(),
+        "links": links,
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn links(id: &str, kind: Option<ObjectKind>, network: BitcoinNetwork) -> Value {
+        let result = explorer_links(&Config::default().explorers, id, kind, network).unwrap();
+        serde_json::from_str(&result).unwrap()
+    }
+
+    #[test]
+    fn test_explorer_links() {
+        let genesis = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        let result = links(genesis, None, BitcoinNetwork::Mainnet);
+        assert_eq!(result["kind"], "block");
+        assert_eq!(
+            result["links"]["mempool"],
+            format!("https://mempool.space/block/{genesis}")
+        );
+
+        let txid = "4A5E1E4BAAB89F3A32518A88C31BC87F618F76673E2CC77AB2127B7AFDEDA33B";
+        let result = links(txid, None, BitcoinNetwork::Testnet);
+        assert_eq!(result["kind"], "tx");
+        assert_eq!(
+            result["links"]["blockstream"],
+            format!(
+                "https://blockstream.info/testnet/tx/{}",
+                txid.to_lowercase()
+            )
+        );
+        let result = links(genesis, Some(ObjectKind::Tx), BitcoinNetwork::Mainnet);
+        assert_eq!(result["kind"], "tx");
+
+        let address = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+        let result = links(address, None, BitcoinNetwork::Mainnet);
+        assert_eq!(result["kind"], "address");
+        assert_eq!(
+            result["links"]["mempool"],
+            format!("https://mempool.space/address/{address}")
+        );
+    }
+
+    #[test]
+    fn test_explorer_links_rejects_mismatches() {
+        let explorers = Config::default().explorers;
+        let address = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+        assert!(explorer_links(&explorers, address, None, BitcoinNetwork::Signet).is_err());
+        assert!(
+            explorer_links(
+                &explorers,
+                address,
+                Some(ObjectKind::Tx),
+                BitcoinNetwork::Mainnet
+            )
+            .is_err()
+        );
+        assert!(explorer_links(&explorers, "hello", None, BitcoinNetwork::Mainnet).is_err());
+    }
+}
@@ -0,0 +1,632 @@
+// Liquid-only tools
+use super::{ToolDef, format_decimal, hex_id, make_schema, page_info, parse_params, text_result};
+use crate::config::Chain;
+use crate::esplora::{EsploraClient, LiquidNetwork, fetch_json, is_not_found};
+use rmcp::schemars;
+use rmcp::serde_json::{self, Value, json};
+use serde::Deserialize;
+
+pub(super) const TOOLS: &[ToolDef] = &[
+    ToolDef {
+        name: "list_liquid_assets",
+        chain: Some(Chain::Liquid),
+        description: "List assets from the Liquid asset registry with their ticker, name, precision, and domain. Supports an optional name/ticker search filter and paging: pass the returned next_cursor (also in page_info, with has_more and count) to get the following page (null when there are no more results). Liquid only.",
+        schema: make_schema::<ListLiquidAssetsParams>,
+        handler: |server, args| {
+            let params: ListLiquidAssetsParams = parse_params(args)?;
+            let limit = params.limit.clamp(1, LIQUID_REGISTRY_PAGE_SIZE);
+            text_result(list_liquid_assets(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.search.as_deref(),
+                params.cursor,
+                limit,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_liquid_asset_metadata",
+        chain: Some(Chain::Liquid),
+        description: "Get the human-readable metadata of a Liquid asset, to present its amounts: Esplora amounts are integers in the asset's base unit, divide them by 10^precision. The registry section holds the ticker, name, precision, and issuer domain submitted by the issuer to the Liquid asset registry (community-submitted, verified only against the issuer's domain), or null for an unregistered asset. The on_chain section holds the issuance facts recorded in the chain: the issuance transaction and input, and the contract hash. The native asset (L-BTC, precision 8) is reported as such. Liquid only.",
+        schema: make_schema::<GetLiquidAssetIssuanceParams>,
+        handler: |server, args| {
+            let params: GetLiquidAssetIssuanceParams = parse_params(args)?;
+            text_result(fetch_asset_metadata(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.asset_id,
+            ))
+        },
+    },
+    ToolDef {
+        name: "format_liquid_asset_amounts",
+        chain: Some(Chain::Liquid),
+        description: "Convert raw Liquid asset amounts, integers in the asset's base unit as returned by Esplora, into decimal amounts using the asset's precision from the Liquid asset registry (8 for L-BTC). Returns the asset's ticker and precision, and each amount both raw and formatted. For an asset without registry metadata, precision 0 is assumed, i.e. amounts are left as they are, and a warning says so. Liquid only.",
+        schema: make_schema::<FormatLiquidAssetAmountsParams>,
+        handler: |server, args| {
+            let params: FormatLiquidAssetAmountsParams = parse_params(args)?;
+            text_result(format_asset_amounts(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.asset_id,
+                &params.amounts,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_liquid_tx_amounts",
+        chain: Some(Chain::Liquid),
+        description: "Break a Liquid transaction down into the amounts and assets of its inputs and outputs, telling explicit fields from confidential ones. Explicit fields carry their cleartext value (in the asset's base unit) or asset id; blinded ones are null, with blinded true and the commitment, which is not an amount. Also returns per-asset totals of the explicit inputs and outputs, and the fee. Inputs without a prevout (peg-ins, coinbases) have no amounts. Liquid only.",
+        schema: make_schema::<LiquidTxidParams>,
+        handler: |server, args| {
+            let params: LiquidTxidParams = parse_params(args)?;
+            text_result(fetch_tx_amounts(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_liquid_asset_issuance",
+        chain: Some(Chain::Liquid),
+        description: "Get the transaction that issued a Liquid asset, to see how it was created: the issued amount, the reissuance token, and the contract hash. Looks up the asset to find its issuance input, then returns the asset info, the index of the issuance input, and the full issuance transaction. The native asset (L-BTC) has no issuance transaction. Liquid only.",
+        schema: make_schema::<GetLiquidAssetIssuanceParams>,
+        handler: |server, args| {
+            let params: GetLiquidAssetIssuanceParams = parse_params(args)?;
+            text_result(fetch_asset_issuance(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.asset_id,
+            ))
+        },
+    },
+];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ListLiquidAssetsParams {
+    #[schemars(
+        description = "Optional case-insensitive substring to match against asset names and tickers."
+    )]
+    #[serde(default)]
+    search: Option<String>,
+    #[schemars(
+        description = "Registry position to continue from, as returned in next_cursor by a previous call. Defaults to 0."
+    )]
+    #[serde(default)]
+    cursor: usize,
+    #[schemars(description = "Maximum number of assets to return (default 25, maximum 100).")]
+    #[serde(default = "default_assets_limit")]
+    limit: usize,
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FormatLiquidAssetAmountsParams {
+    #[schemars(description = "The asset id, as 64 hex characters.")]
+    #[serde(deserialize_with = "hex_id")]
+    asset_id: String,
+    #[schemars(description = "The raw amounts to format, in the asset's base unit.")]
+    amounts: Vec<u64>,
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetLiquidAssetIssuanceParams {
+    #[schemars(description = "The asset id, as 64 hex characters.")]
+    #[serde(deserialize_with = "hex_id")]
+    asset_id: String,
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct LiquidTxidParams {
+    #[schemars(description = "The transaction ID (txid) hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+}
+
+// The Liquid asset registry returns at most this many assets per request
+const LIQUID_REGISTRY_PAGE_SIZE: usize = 100;
+// Bound on the registry pages scanned by a single search
+const LIQUID_REGISTRY_MAX_PAGES: usize = 10;
+const LIQUID_ASSETS_DEFAULT_LIMIT: usize = 25;
+
+fn default_assets_limit() -> usize {
+    LIQUID_ASSETS_DEFAULT_LIMIT
+}
+
+fn list_liquid_assets(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    search: Option<&str>,
+    cursor: usize,
+    limit: usize,
+) -> Result<String, String> {
+    let search = search.map(|s| s.to_lowercase());
+    let matches = |asset: &Value| match &search {
+        None => true,
+        Some(needle) => ["name", "ticker"].iter().any(|field| {
+            asset[field]
+                .as_str()
+                .is_some_and(|v| v.to_lowercase().contains(needle))
+        }),
+    };
+    // Without a filter every asset matches, so a single page of `limit` is enough
+    let page_size = if search.is_none() {
+        limit
+    } else {
+        LIQUID_REGISTRY_PAGE_SIZE
+    };
+
+    let mut assets = Vec::new();
+    let mut start_index = cursor;
+    let mut next_cursor = None;
+    for page in 0..LIQUID_REGISTRY_MAX_PAGES {
+        let url = format!("{base_url}/assets/registry?start_index={start_index}&limit={page_size}");
+        let page_assets = fetch_json(client, &url)?;
+        let page_assets = page_assets
+            .as_array()
+            .ok_or("Asset registry response is not an array")?;
+        for (i, asset) in page_assets.iter().enumerate() {
+            if !matches(asset) {
+                continue;
+            }
+            assets.push(json!({
+                "asset_id": asset["asset_id"],
+                "ticker": asset["ticker"],
+                "name": asset["name"],
+                "precision": asset["precision"],
+                "domain": asset["entity"]["domain"],
+            }));
+            if assets.len() == limit {
+                next_cursor = Some(start_index + i + 1);
+                break;
+            }
+        }
+        if assets.len() == limit || page_assets.len() < page_size {
+            break;
+        }
+        start_index += page_assets.len();
+        if page + 1 == LIQUID_REGISTRY_MAX_PAGES {
+            next_cursor = Some(start_index);
+        }
+    }
+    let result = json!({
+        "count": assets.len(),
+        "assets": assets,
+        "next_cursor": next_cursor,
+        "page_info": page_info(assets.len(), next_cursor.map(Value::from)),
+    });
+    Ok(result.to_string())
+}
+
+// Indexes of the entries whose `field` is blinded: Esplora then omits the
+// cleartext field and only returns the commitment
+fn blinded_indexes<'a>(entries: impl Iterator<Item = &'a Value>, field: &str) -> Vec<usize> {
+    entries
+        .enumerate()
+        .filter(|(_, entry)| entry.is_object() && entry[field].is_null())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Explanations for the confidential amounts and assets of a Liquid
+// transaction, so that agents don't read missing values as zero
+pub(super) fn blinded_warnings(tx: &Value) -> Vec<String> {
+    let empty = Vec::new();
+    let outputs = tx["vout"].as_array().unwrap_or(&empty);
+    let prevouts = tx["vin"]
+        .as_array()
+        .unwrap_or(&empty)
+        .iter()
+        .map(|input| &input["prevout"]);
+    let mut warnings = Vec::new();
+    for (side, field, indexes) in [
+        ("Outputs", "value", blinded_indexes(outputs.iter(), "value")),
+        ("Outputs", "asset", blinded_indexes(outputs.iter(), "asset")),
+        (
+            "Inputs",
+            "value",
+            blinded_indexes(prevouts.clone(), "value"),
+        ),
+        ("Inputs", "asset", blinded_indexes(prevouts, "asset")),
+    ] {
+        if !indexes.is_empty() {
+            warnings.push(format!(
+                "{side} {indexes:?} have a confidential {field}: it is blinded ({field}commitment) and not available in cleartext, it is not zero"
+            ));
+        }
+    }
+    warnings
+}
+
+// Attach a top-level `warnings` array to a Liquid transaction JSON body when
+// some of its values are confidential
+// Native assets of Liquid mainnet and testnet, pegged to bitcoin
+pub(super) const NATIVE_ASSETS: &[&str] = &[
+    "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d",
+    "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49",
+];
+
+// Esplora merges the registry entry, if any, into the asset's chain data:
+// split them apart again so that their provenance is clear
+fn fetch_asset_metadata(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    asset_id: &str,
+) -> Result<String, String> {
+    let asset = fetch_json(client, &format!("{base_url}/asset/{asset_id}"))?;
+    let native = NATIVE_ASSETS.contains(&asset_id);
+    let registry = registry_entry(asset_id, &asset);
+    let result = json!({
+        "asset_id": asset_id,
+        "native": native,
+        "registered": !registry.is_null(),
+        "registry": registry,
+        "on_chain": {
+            "issuance_txid": asset["issuance_txin"]["txid"],
+            "issuance_vin": asset["issuance_txin"]["vin"],
+            "contract_hash": asset["contract_hash"],
+            "reissuance_token": asset["reissuance_token"],
+        },
+    });
+    Ok(result.to_string())
+}
+
+// Registry metadata of an /asset response, null for an unregistered asset
+fn registry_entry(asset_id: &str, asset: &Value) -> Value {
+    if NATIVE_ASSETS.contains(&asset_id) {
+        json!({
+            "ticker": "L-BTC",
+            "name": "Liquid Bitcoin",
+            "precision": 8,
+            "domain": null,
+        })
+    } else if asset["contract"].is_object() || !asset["ticker"].is_null() {
+        json!({
+            "ticker": asset["ticker"],
+            "name": asset["name"],
+            "precision": asset["precision"],
+            "domain": asset["entity"]["domain"],
+        })
+    } else {
+        Value::Null
+    }
+}
+
+// Registry precisions range from 0 to 8
+const MAX_ASSET_PRECISION: u64 = 8;
+
+fn format_asset_amounts(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    asset_id: &str,
+    amounts: &[u64],
+) -> Result<String, String> {
+    let registry = match fetch_json(client, &format!("{base_url}/asset/{asset_id}")) {
+        Ok(asset) => registry_entry(asset_id, &asset),
+        Err(e) if is_not_found(&e) => Value::Null,
+        Err(e) => return Err(e),
+    };
+    let precision = registry["precision"]
+        .as_u64()
+        .filter(|precision| *precision <= MAX_ASSET_PRECISION);
+    let formatted: Vec<Value> = amounts
+        .iter()
+        .map(|&raw| json!({ "raw": raw, "formatted": format_decimal(raw, precision.unwrap_or(0) as u32) }))
+        .collect();
+    let mut result = json!({
+        "asset_id": asset_id,
+        "ticker": registry["ticker"],
+        "precision": precision.unwrap_or(0),
+        "amounts": formatted,
+    });
+    if precision.is_none() {
+        result["warning"] = json!(
+            "No registry metadata for this asset, precision 0 assumed: the formatted amounts are the raw ones and may be off by a power of ten"
+        );
+    }
+    Ok(result.to_string())
+}
+
+// Follow an asset's issuance_txin to the transaction that issued it
+fn fetch_asset_issuance(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    asset_id: &str,
+) -> Result<String, String> {
+    let asset = fetch_json(client, &format!("{base_url}/asset/{asset_id}"))?;
+    let txid = asset["issuance_txin"]["txid"].as_str().ok_or_else(|| {
+        format!("Asset {asset_id} has no issuance transaction, it may be the native asset")
+    })?;
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let mut result = json!({
+        "asset": asset,
+        "issuance_vin": asset["issuance_txin"]["vin"],
+        "issuance_tx": tx,
+    });
+    let warnings = blinded_warnings(&result["issuance_tx"]);
+    if !warnings.is_empty() {
+        result["warnings"] = json!(warnings);
+    }
+    Ok(result.to_string())
+}
+
+// The value and asset of an output or prevout, each either explicit or blinded
+fn entry_amounts(entry: &Value) -> Value {
+    json!({
+        "value": entry["value"],
+        "value_blinded": entry["value"].is_null(),
+        "valuecommitment": entry["valuecommitment"],
+        "asset": entry["asset"],
+        "asset_blinded": entry["asset"].is_null(),
+        "assetcommitment": entry["assetcommitment"],
+    })
+}
+
+// Sum the explicit values per explicit asset
+fn add_explicit(totals: &mut serde_json::Map<String, Value>, entry: &Value) {
+    if let (Some(asset), Some(value)) = (entry["asset"].as_str(), entry["value"].as_u64()) {
+        let total = totals.entry(asset).or_insert(json!(0));
+        *total = json!(total.as_u64().unwrap_or(0) + value);
+    }
+}
+
+fn fetch_tx_amounts(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let empty = Vec::new();
+    let mut input_totals = serde_json::Map::new();
+    let mut output_totals = serde_json::Map::new();
+    let mut inputs = Vec::new();
+    for (i, input) in tx["vin"].as_array().unwrap_or(&empty).iter().enumerate() {
+        let prevout = &input["prevout"];
+        let mut entry = json!({ "vin": i, "txid": input["txid"], "vout": input["vout"] });
+        if prevout.is_object() {
+            entry["amounts"] = entry_amounts(prevout);
+            add_explicit(&mut input_totals, prevout);
+        } else {
+            entry["amounts"] = Value::Null;
+            entry["note"] = json!(if input["is_pegin"] == true {
+                "Peg-in input: its value comes from the Bitcoin chain, there is no Liquid prevout"
+            } else {
+                "No prevout: this input spends no Liquid output"
+            });
+        }
+        inputs.push(entry);
+    }
+    let mut outputs = Vec::new();
+    let mut fee = Value::Null;
+    for (i, output) in tx["vout"].as_array().unwrap_or(&empty).iter().enumerate() {
+        if output["scriptpubkey_type"] == "fee" {
+            fee = json!({ "value": output["value"], "asset": output["asset"] });
+        }
+        let mut entry = entry_amounts(output);
+        entry["vout"] = json!(i);
+        entry["address"] = output["scriptpubkey_address"].clone();
+        entry["scriptpubkey_type"] = output["scriptpubkey_type"].clone();
+        add_explicit(&mut output_totals, output);
+        outputs.push(entry);
+    }
+    let mut result = json!({
+        "txid": txid,
+        "inputs": inputs,
+        "outputs": outputs,
+        "explicit_input_totals": input_totals,
+        "explicit_output_totals": output_totals,
+        "fee": fee,
+    });
+    let warnings = blinded_warnings(&tx);
+    if !warnings.is_empty() {
+        result["warnings"] = json!(warnings);
+    }
+    Ok(result.to_string())
+}
+
+pub(super) fn annotate_blinded_tx(body: String) -> Result<String, String> {
+    let mut tx: Value =
+        serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))?;
+    let warnings = blinded_warnings(&tx);
+    if warnings.is_empty() {
+        return Ok(body);
+    }
+    tx["warnings"] = json!(warnings);
+    Ok(tx.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esplora::testing::MockClient;
+
+    #[test]
+    fn test_list_liquid_assets_search() {
+        let client = MockClient::default()
+            .with(
+                "http://liquid/assets/registry?start_index=0&limit=100",
+                &format!(
+                    "[{}]",
+                    (0..100)
+                        .map(|i| format!(r#"{{"asset_id":"a{i}","ticker":"T{i}","name":"Asset {i}"}}"#))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+            )
+            .with(
+                "http://liquid/assets/registry?start_index=100&limit=100",
+                r#"[{"asset_id":"usdt","ticker":"USDt","name":"Tether USD","entity":{"domain":"tether.to"}}]"#,
+            );
+        let result = list_liquid_assets(&client, "http://liquid", Some("tether"), 0, 25).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["count"], 1);
+        assert_eq!(result["assets"][0]["ticker"], "USDt");
+        assert_eq!(result["assets"][0]["domain"], "tether.to");
+        assert_eq!(result["next_cursor"], Value::Null);
+
+        let result = list_liquid_assets(&client, "http://liquid", Some("asset 1"), 0, 2).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["assets"][1]["asset_id"], "a10");
+        assert_eq!(result["next_cursor"], 11);
+        assert_eq!(result["page_info"]["next_cursor"], 11);
+        assert_eq!(result["page_info"]["has_more"], true);
+        assert_eq!(result["page_info"]["count"], 2);
+    }
+
+    #[test]
+    fn test_annotate_blinded_tx() {
+        let tx = r#"{"vin":[{"prevout":{"valuecommitment":"08aa","assetcommitment":"0baa"}}],
+            "vout":[{"valuecommitment":"09bb","asset":"6f02"},{"value":250,"asset":"6f02","scriptpubkey_type":"fee"}]}"#;
+        let result: Value =
+            serde_json::from_str(&annotate_blinded_tx(tx.to_string()).unwrap()).unwrap();
+        let warnings = result["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 3);
+        assert!(
+            warnings[0]
+                .as_str()
+                .unwrap()
+                .starts_with("Outputs [0] have a confidential value")
+        );
+
+        // Explicit transactions are passed through untouched
+        let tx =
+            r#"{"vin":[{"prevout":null,"is_coinbase":true}],"vout":[{"value":1,"asset":"6f02"}]}"#;
+        assert_eq!(annotate_blinded_tx(tx.to_string()).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_fetch_tx_amounts() {
+        let client = MockClient::default().with(
+            "http://liquid/tx/ab",
+            r#"{"vin":[{"txid":"ff","vout":0,"prevout":{"value":1000,"asset":"6f02"}},
+                       {"txid":"ee","vout":1,"prevout":{"valuecommitment":"08aa","assetcommitment":"0baa"}},
+                       {"txid":"dd","vout":0,"is_pegin":true,"prevout":null}],
+                "vout":[{"valuecommitment":"09bb","asset":"6f02","scriptpubkey_address":"ex1q"},
+                        {"value":700,"asset":"6f02"},
+                        {"value":250,"asset":"6f02","scriptpubkey_type":"fee"}]}"#,
+        );
+        let result = fetch_tx_amounts(&client, "http://liquid", "ab").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["inputs"][0]["amounts"]["value"], 1000);
+        assert_eq!(result["inputs"][1]["amounts"]["value_blinded"], true);
+        assert_eq!(result["inputs"][1]["amounts"]["value"], Value::Null);
+        assert_eq!(result["inputs"][1]["amounts"]["assetcommitment"], "0baa");
+        assert_eq!(result["inputs"][2]["amounts"], Value::Null);
+        assert_eq!(result["outputs"][0]["value_blinded"], true);
+        assert_eq!(result["outputs"][0]["asset_blinded"], false);
+        assert_eq!(result["explicit_input_totals"]["6f02"], 1000);
+        assert_eq!(result["explicit_output_totals"]["6f02"], 950);
+        assert_eq!(result["fee"]["value"], 250);
+        assert_eq!(result["warnings"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_fetch_asset_metadata() {
+        let registered = "ce".repeat(32);
+        let unregistered = "ab".repeat(32);
+        let native = NATIVE_ASSETS[0];
+        let client = MockClient::default()
+            .with(
+                &format!("http://liquid/asset/{registered}"),
+                r#"{"issuance_txin":{"txid":"0a","vin":0},"contract_hash":"ff","contract":{},
+                    "ticker":"USDt","name":"Tether USD","precision":8,
+                    "entity":{"domain":"tether.to"}}"#,
+            )
+            .with(
+                &format!("http://liquid/asset/{unregistered}"),
+                r#"{"issuance_txin":{"txid":"0b","vin":1},"contract_hash":"00"}"#,
+            )
+            .with(
+                &format!("http://liquid/asset/{native}"),
+                r#"{"chain_stats":{}}"#,
+            );
+        let metadata = |asset_id: &str| {
+            let result = fetch_asset_metadata(&client, "http://liquid", asset_id).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+
+        let result = metadata(&registered);
+        assert_eq!(result["registered"], true);
+        assert_eq!(result["registry"]["ticker"], "USDt");
+        assert_eq!(result["registry"]["domain"], "tether.to");
+        assert_eq!(result["on_chain"]["issuance_txid"], "0a");
+        let result = metadata(&unregistered);
+        assert_eq!(result["registered"], false);
+        assert_eq!(result["registry"], Value::Null);
+        assert_eq!(result["on_chain"]["issuance_vin"], 1);
+        let result = metadata(native);
+        assert_eq!(result["native"], true);
+        assert_eq!(result["registry"]["ticker"], "L-BTC");
+    }
+
+    #[test]
+    fn test_format_asset_amounts() {
+        let registered = "ce".repeat(32);
+        let unregistered = "ab".repeat(32);
+        let client = MockClient::default().with(
+            &format!("http://liquid/asset/{registered}"),
+            r#"{"contract":{},"ticker":"USDt","precision":8}"#,
+        );
+        let format = |asset_id: &str| {
+            let result =
+                format_asset_amounts(&client, "http://liquid", asset_id, &[150_000_000, 1])
+                    .unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+
+        let result = format(&registered);
+        assert_eq!(result["ticker"], "USDt");
+        assert_eq!(result["amounts"][0]["raw"], 150_000_000);
+        assert_eq!(result["amounts"][0]["formatted"], "1.5");
+        assert_eq!(result["amounts"][1]["formatted"], "0.00000001");
+        assert!(result.get("warning").is_none());
+        // The mock answers unknown URLs with a 404
+        let result = format(&unregistered);
+        assert_eq!(result["precision"], 0);
+        assert_eq!(result["amounts"][0]["formatted"], "150000000");
+        assert!(result["warning"].as_str().unwrap().contains("precision 0"));
+    }
+
+    #[test]
+    fn test_fetch_asset_issuance() {
+        let asset_id = "ce".repeat(32);
+        let client = MockClient::default()
+            .with(
+                &format!("http://liquid/asset/{asset_id}"),
+                r#"{"issuance_txin":{"txid":"0a","vin":0},"contract_hash":"ff"}"#,
+            )
+            .with(
+                "http://liquid/tx/0a",
+                r#"{"txid":"0a","vin":[{"is_pegin":false,"issuance":{"assetamount":1000}}],"vout":[]}"#,
+            );
+        let result = fetch_asset_issuance(&client, "http://liquid", &asset_id).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["issuance_vin"], 0);
+        assert_eq!(result["issuance_tx"]["txid"], "0a");
+        assert_eq!(result["asset"]["contract_hash"], "ff");
+
+        // The native asset was never issued
+        let native = "6f".repeat(32);
+        let client = MockClient::default().with(
+            &format!("http://liquid/asset/{native}"),
+            r#"{"asset_id":"6f","chain_stats":{}}"#,
+        );
+        let err = fetch_asset_issuance(&client, "http://liquid", &native).unwrap_err();
+        assert!(
+            err.contains("no issuance transaction"),
+            "unexpected error: {err}"
+        );
+    }
+}
@@ -0,0 +1,446 @@
+// Mempool tools
+use super::{
+    AMOUNTS_IN_BTC_DESCRIPTION, BLOCK_INTERVAL_SECS, CHUNK_SIZE_DESCRIPTION, ToolDef, check_cap,
+    chunked_result, default_list_limit, make_schema, parse_params, text_result, with_btc_amounts,
+};
+use crate::config::Chain;
+use crate::esplora::{BitcoinNetwork, EsploraClient, LiquidNetwork, fetch_json, fetch_mempool};
+use rmcp::model::ErrorData;
+use rmcp::schemars;
+use rmcp::serde_json::{Value, json};
+use serde::Deserialize;
+
+pub(super) const TOOLS: &[ToolDef] = &[
+    ToolDef {
+        name: "get_bitcoin_mempool",
+        chain: Some(Chain::Bitcoin),
+        description: "Get Bitcoin mempool statistics from the Esplora API. Returns tx count, total vsize, total fees, and fee histogram.",
+        schema: make_schema::<GetBitcoinMempoolParams>,
+        handler: |server, args| {
+            let params: GetBitcoinMempoolParams = parse_params(args)?;
+            text_result(with_btc_amounts(
+                fetch_mempool(server.client.as_ref(), &params.network.api_base()),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_liquid_mempool",
+        chain: Some(Chain::Liquid),
+        description: "Get Liquid mempool statistics from the Esplora API. Returns tx count, total vsize, total fees, and fee histogram.",
+        schema: make_schema::<GetLiquidMempoolParams>,
+        handler: |server, args| {
+            let params: GetLiquidMempoolParams = parse_params(args)?;
+            text_result(fetch_mempool(
+                server.client.as_ref(),
+                &params.network.api_base(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_mempool_txids",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the txids of the transactions in the Bitcoin mempool. On mainnet this is typically tens of thousands of txids, so only the first `limit` (default 100) are returned, further capped by the server's response size limit; truncated is true and count gives the full mempool size when that happens.",
+        schema: make_schema::<GetBitcoinMempoolTxidsParams>,
+        handler: |server, args| {
+            let params: GetBitcoinMempoolTxidsParams = parse_params(args)?;
+            check_cap(
+                "limit",
+                params.limit as u64,
+                server.config.max_list_limit as u64,
+            )?;
+            chunked_result(
+                fetch_mempool_txids(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    params.limit,
+                    server.config.max_response_bytes,
+                ),
+                "txids",
+                params.chunk_size,
+            )
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_feerate_for_target",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the estimated Bitcoin fee rate, in sat/vB, for a transaction to confirm within a number of blocks. When there is no estimate for that exact target, the nearest available one is used (the shorter one on ties, to err on the side of confirming in time). Returns the requested target, the target actually used, and the fee rate.",
+        schema: make_schema::<GetBitcoinFeerateForTargetParams>,
+        handler: |server, args| {
+            let params: GetBitcoinFeerateForTargetParams = parse_params(args)?;
+            if params.blocks == 0 {
+                return Err(ErrorData::invalid_request(
+                    "blocks must be at least 1",
+                    None,
+                ));
+            }
+            text_result(fetch_feerate_for_target(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.blocks,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_confirmation_probability",
+        chain: Some(Chain::Bitcoin),
+        description: "Roughly estimate the chance that a Bitcoin transaction paying a given fee rate confirms within a number of blocks, from the mempool fee histogram. The vsize queued at strictly higher fee rates is compared to the capacity of the blocks (1,000,000 vB each): probability is the share of that capacity left for the transaction, from 0 to 1. This is a heuristic, not a prediction: it ignores transactions arriving later, miners' own selection, and block intervals. Also returns the queued-ahead vsize and the block the transaction would land in if the mempool stayed as it is.",
+        schema: make_schema::<GetBitcoinConfirmationProbabilityParams>,
+        handler: |server, args| {
+            let params: GetBitcoinConfirmationProbabilityParams = parse_params(args)?;
+            if params.blocks == 0 {
+                return Err(ErrorData::invalid_request(
+                    "blocks must be at least 1",
+                    None,
+                ));
+            }
+            if !(params.feerate.is_finite() && params.feerate > 0.0) {
+                return Err(ErrorData::invalid_request(
+                    "feerate must be a positive number of sat/vB",
+                    None,
+                ));
+            }
+            text_result(fetch_confirmation_probability(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.feerate,
+                params.blocks,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_congestion",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the Bitcoin mempool congestion as a label: low, moderate, high, or extreme, from the total vsize waiting in the mempool. It is moderate from moderate_vsize (default 1,000,000 vB, one full block), high from high_vsize (default 10,000,000 vB) and extreme from extreme_vsize (default 50,000,000 vB). Returns the level, the backlog vsize and transaction count, the thresholds used, and the blocks needed to clear the backlog (1,000,000 vB each) with the time that takes at one block every 10 minutes, assuming no new transactions arrive.",
+        schema: make_schema::<GetBitcoinCongestionParams>,
+        handler: |server, args| {
+            let params: GetBitcoinCongestionParams = parse_params(args)?;
+            let thresholds = [
+                params.moderate_vsize,
+                params.high_vsize,
+                params.extreme_vsize,
+            ];
+            if !thresholds.is_sorted() {
+                return Err(ErrorData::invalid_request(
+                    "Thresholds must be in ascending order: moderate_vsize <= high_vsize <= extreme_vsize",
+                    None,
+                ));
+            }
+            text_result(fetch_congestion(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                thresholds,
+            ))
+        },
+    },
+];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinMempoolParams {
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinMempoolTxidsParams {
+    #[schemars(description = "Maximum number of txids to return (default 100).")]
+    #[serde(default = "default_list_limit")]
+    limit: usize,
+    #[schemars(description = CHUNK_SIZE_DESCRIPTION)]
+    #[serde(default)]
+    chunk_size: Option<usize>,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinFeerateForTargetParams {
+    #[schemars(description = "The confirmation target, in blocks (at least 1).")]
+    blocks: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinConfirmationProbabilityParams {
+    #[schemars(description = "The fee rate of the transaction, in sat/vB.")]
+    feerate: f64,
+    #[schemars(description = "The number of blocks to confirm within (at least 1).")]
+    blocks: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+fn default_moderate_vsize() -> u64 {
+    BLOCK_MAX_VSIZE
+}
+
+fn default_high_vsize() -> u64 {
+    10 * BLOCK_MAX_VSIZE
+}
+
+fn default_extreme_vsize() -> u64 {
+    50 * BLOCK_MAX_VSIZE
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinCongestionParams {
+    #[schemars(
+        description = "The backlog vsize, in vB, from which congestion is moderate (default 1,000,000)."
+    )]
+    #[serde(default = "default_moderate_vsize")]
+    moderate_vsize: u64,
+    #[schemars(
+        description = "The backlog vsize, in vB, from which congestion is high (default 10,000,000)."
+    )]
+    #[serde(default = "default_high_vsize")]
+    high_vsize: u64,
+    #[schemars(
+        description = "The backlog vsize, in vB, from which congestion is extreme (default 50,000,000)."
+    )]
+    #[serde(default = "default_extreme_vsize")]
+    extreme_vsize: u64,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetLiquidMempoolParams {
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+}
+
+fn fetch_mempool_txids(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    limit: usize,
+    max_bytes: usize,
+) -> Result<String, String> {
+    let txids = fetch_json(client, &format!("{base_url}/mempool/txids"))?;
+    let txids = txids
+        .as_array()
+        .ok_or("Mempool txids response is not an array")?;
+    // Each txid takes 64 hex chars plus quotes and a comma
+    let max_txids = limit.min(max_bytes / 67);
+    let kept: Vec<&Value> = txids.iter().take(max_txids).collect();
+    let result = json!({
+        "count": txids.len(),
+        "truncated": kept.len() < txids.len(),
+        "txids": kept,
+    });
+    Ok(result.to_string())
+}
+
+// Esplora maps confirmation targets ("1" to "25", "144", "504", "1008") to fee
+// rates: the target nearest to `blocks` and its fee rate
+pub(super) fn fetch_target_feerate(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    blocks: u32,
+) -> Result<(u32, f64), String> {
+    let estimates = fetch_json(client, &format!("{base_url}/fee-estimates"))?;
+    let estimates = estimates
+        .as_object()
+        .ok_or("Fee estimates response is not an object")?;
+    let (target, feerate) = estimates
+        .iter()
+        .filter_map(|(target, rate)| Some((target.parse::<u32>().ok()?, rate.as_f64()?)))
+        .min_by_key(|(target, _)| (target.abs_diff(blocks), *target))
+        .ok_or("No fee estimates available")?;
+    Ok((target, feerate))
+}
+
+fn fetch_feerate_for_target(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    blocks: u32,
+) -> Result<String, String> {
+    let (target, feerate) = fetch_target_feerate(client, base_url, blocks)?;
+    let result = json!({
+        "requested_target": blocks,
+        "target": target,
+        "feerate_sat_vb": feerate,
+    });
+    Ok(result.to_string())
+}
+
+// Room for transactions in a block: 4M weight units
+const BLOCK_MAX_VSIZE: u64 = 1_000_000;
+
+fn fetch_confirmation_probability(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    feerate: f64,
+    blocks: u32,
+) -> Result<String, String> {
+    let mempool = fetch_json(client, &format!("{base_url}/mempool"))?;
+    let histogram = mempool["fee_histogram"]
+        .as_array()
+        .ok_or("Mempool response has no fee_histogram")?;
+    // Each [rate, vsize] entry groups the transactions paying at least rate,
+    // and less than the previous entry's rate: the entries above our fee rate
+    // only hold transactions that would be mined first
+    let queued_ahead: u64 = histogram
+        .iter()
+        .filter_map(|entry| Some((entry[0].as_f64()?, entry[1].as_u64()?)))
+        .filter(|(rate, _)| *rate > feerate)
+        .map(|(_, vsize)| vsize)
+        .sum();
+    let capacity = blocks as u64 * BLOCK_MAX_VSIZE;
+    let probability = capacity.saturating_sub(queued_ahead) as f64 / capacity as f64;
+    let result = json!({
+        "feerate_sat_vb": feerate,
+        "blocks": blocks,
+        "queued_ahead_vsize": queued_ahead,
+        "capacity_vsize": capacity,
+        "blocks_to_clear_ahead": (queued_ahead as f64 / BLOCK_MAX_VSIZE as f64 * 100.0).round() / 100.0,
+        "expected_block": queued_ahead / BLOCK_MAX_VSIZE + 1,
+        "probability": (probability * 100.0).round() / 100.0,
+        "note": "Heuristic from the current mempool only: transactions arriving later at higher fee rates push this one back, so treat the probability as an upper bound",
+    });
+    Ok(result.to_string())
+}
+
+// Congestion levels, from the backlog vsize thresholds of the last three
+const CONGESTION_LEVELS: [&str; 4] = ["low", "moderate", "high", "extreme"];
+
+fn fetch_congestion(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    thresholds: [u64; 3],
+) -> Result<String, String> {
+    let mempool = fetch_json(client, &format!("{base_url}/mempool"))?;
+    let vsize = mempool["vsize"]
+        .as_u64()
+        .ok_or("Mempool response has no vsize")?;
+    let level = CONGESTION_LEVELS[thresholds.iter().filter(|&&t| vsize >= t).count()];
+    let blocks_to_clear = vsize.div_ceil(BLOCK_MAX_VSIZE);
+    let result = json!({
+        "level": level,
+        "vsize": vsize,
+        "tx_count": mempool["count"],
+        "thresholds": {
+            "moderate_vsize": thresholds[0],
+            "high_vsize": thresholds[1],
+            "extreme_vsize": thresholds[2],
+        },
+        "blocks_to_clear": blocks_to_clear,
+        "eta_secs": blocks_to_clear * BLOCK_INTERVAL_SECS,
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esplora::testing::MockClient;
+    use rmcp::serde_json;
+
+    #[test]
+    fn test_fetch_feerate_for_target() {
+        let client = MockClient::default().with(
+            "http://esplora/fee-estimates",
+            r#"{"1":20.5,"2":18.1,"6":10.0,"144":2.1,"1008":1.0}"#,
+        );
+        let feerate = |blocks| {
+            let result = fetch_feerate_for_target(&client, "http://esplora", blocks).unwrap();
+            let result: Value = serde_json::from_str(&result).unwrap();
+            (
+                result["target"].as_u64().unwrap(),
+                result["feerate_sat_vb"].as_f64().unwrap(),
+            )
+        };
+        assert_eq!(feerate(6), (6, 10.0));
+        // 4 is as far from 2 as from 6, the shorter target wins
+        assert_eq!(feerate(4), (2, 18.1));
+        assert_eq!(feerate(100), (144, 2.1));
+        assert_eq!(feerate(5000), (1008, 1.0));
+    }
+
+    #[test]
+    fn test_fetch_congestion() {
+        let level = |vsize: u64| {
+            let client = MockClient::default().with(
+                "http://esplora/mempool",
+                &json!({ "count": 10, "vsize": vsize }).to_string(),
+            );
+            let result = fetch_congestion(
+                &client,
+                "http://esplora",
+                [1_000_000, 10_000_000, 50_000_000],
+            )
+            .unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        assert_eq!(level(999_999)["level"], "low");
+        assert_eq!(level(1_000_000)["level"], "moderate");
+        let result = level(12_500_000);
+        assert_eq!(result["level"], "high");
+        assert_eq!(result["blocks_to_clear"], 13);
+        assert_eq!(result["eta_secs"], 13 * 600);
+        assert_eq!(level(80_000_000)["level"], "extreme");
+    }
+
+    #[test]
+    fn test_fetch_confirmation_probability() {
+        let client = MockClient::default().with(
+            "http://esplora/mempool",
+            r#"{"count":9,"vsize":2600000,"fee_histogram":[[50.0,600000],[20.0,900000],[10.0,100000],[2.0,1000000]]}"#,
+        );
+        let estimate = |feerate, blocks| {
+            let result =
+                fetch_confirmation_probability(&client, "http://esplora", feerate, blocks).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = estimate(15.0, 2);
+        assert_eq!(result["queued_ahead_vsize"], 1_500_000);
+        assert_eq!(result["expected_block"], 2);
+        assert_eq!(result["probability"], 0.25);
+        assert_eq!(estimate(15.0, 1)["probability"], 0.0);
+        assert_eq!(estimate(100.0, 1)["probability"], 1.0);
+        assert_eq!(estimate(1.0, 3)["blocks_to_clear_ahead"], 2.6);
+    }
+
+    #[test]
+    fn test_fetch_mempool_txids_truncates() {
+        let txids: Vec<String> = (0..3).map(|i| format!("{i:064x}")).collect();
+        let client = MockClient::default().with(
+            "http://esplora/mempool/txids",
+            &serde_json::to_string(&txids).unwrap(),
+        );
+        let result = fetch_mempool_txids(&client, "http://esplora", 100, 1_000).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["truncated"], false);
+        assert_eq!(result["txids"].as_array().unwrap().len(), 3);
+
+        let result = fetch_mempool_txids(&client, "http://esplora", 100, 150).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["count"], 3);
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["txids"], json!([txids[0], txids[1]]));
+
+        let result = fetch_mempool_txids(&client, "http://esplora", 1, 1_000).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["txids"], json!([txids[0]]));
+    }
+}
@@ -0,0 +1,722 @@
+// Registry of the tools exposed by the server.
+//
+// Each domain module declares its tools as a `TOOLS` slice of `ToolDef`
+// entries, so adding a tool is a single entry: `list_tools` and `call_tool`
+// both read from this registry and can't drift apart.
+use crate::MyServer;
+use crate::config::Chain;
+use rmcp::model::{CallToolResult, Content, ErrorData, JsonObject, RawContent};
+use rmcp::schemars;
+use rmcp::serde_json::{self, Value, json};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+mod address;
+mod batch;
+mod block;
+mod explorer;
+mod liquid;
+mod mempool;
+mod network;
+mod price;
+mod script;
+mod server;
+mod signature;
+mod tx;
+
+pub use server::instructions;
+
+// How a tool renders its result
+#[derive(Deserialize, Serialize, schemars::JsonSchema, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    #[default]
+    Json,
+    Text,
+}
+
+// Parameters of tools that take no arguments
+#[derive(Deserialize, schemars::JsonSchema)]
+struct EmptyParams {}
+
+pub struct ToolDef {
+    pub name: &'static str,
+    // The chain the tool queries, hidden and rejected when ENABLED_NETWORKS
+    // leaves it out; None for tools not tied to one chain
+    pub chain: Option<Chain>,
+    pub description: &'static str,
+    // Builds the JSON schema of the tool's parameters
+    pub schema: fn() -> Result<Arc<JsonObject>, ErrorData>,
+    // Runs the tool with the raw JSON arguments of the call
+    pub handler: fn(&MyServer, Value) -> Result<CallToolResult, ErrorData>,
+}
+
+// All tools, in the order they are listed to clients
+pub fn registry() -> impl Iterator<Item = &'static ToolDef> {
+    [
+        tx::TOOLS,
+        block::TOOLS,
+        explorer::TOOLS,
+        network::TOOLS,
+        price::TOOLS,
+        mempool::TOOLS,
+        address::TOOLS,
+        script::TOOLS,
+        signature::TOOLS,
+        liquid::TOOLS,
+        server::TOOLS,
+        batch::TOOLS,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+pub fn find(name: &str) -> Option<&'static ToolDef> {
+    registry().find(|tool| tool.name == name)
+}
+
+// Former names of renamed tools, as (old name, current name), so that clients
+// calling an old name keep working. Add an entry here when renaming a tool.
+const ALIASES: &[(&str, &str)] = &[];
+
+fn resolve_alias(name: &str, aliases: &[(&str, &str)]) -> Option<&'static ToolDef> {
+    aliases
+        .iter()
+        .find(|(old, _)| *old == name)
+        .and_then(|(_, current)| find(current))
+}
+
+// The tool formerly known as `name`, for calls using a deprecated name
+pub fn find_alias(name: &str) -> Option<&'static ToolDef> {
+    resolve_alias(name, ALIASES)
+}
+
+// The tool's description, naming the deprecated aliases it still answers to
+pub fn describe(tool: &ToolDef) -> String {
+    let aliases: Vec<String> = ALIASES
+        .iter()
+        .filter(|(_, current)| *current == tool.name)
+        .map(|(old, _)| format!("'{old}'"))
+        .collect();
+    if aliases.is_empty() {
+        tool.description.to_string()
+    } else {
+        format!(
+            "{} Also callable under its deprecated former name {}.",
+            tool.description,
+            aliases.join(", ")
+        )
+    }
+}
+
+// Note in a JSON object result that the call used a deprecated name
+pub fn add_alias_warning(mut result: CallToolResult, alias: &str, name: &str) -> CallToolResult {
+    let warning = format!("Tool '{alias}' is deprecated, call '{name}' instead");
+    let Some(RawContent::Text(text)) = result.content.first_mut().map(|c| &mut c.raw) else {
+        return result;
+    };
+    if let Ok(Value::Object(mut object)) = serde_json::from_str::<Value>(&text.text) {
+        match object.get_mut("warnings") {
+            Some(Value::Array(warnings)) => warnings.push(json!(warning)),
+            _ => {
+                object.insert("warnings".to_string(), json!([warning]));
+            }
+        }
+        text.text = Value::Object(object).to_string();
+    }
+    result
+}
+
+// Helper function to create a schema map from a JsonSchema type
+fn make_schema<T: schemars::JsonSchema>() -> Result<Arc<JsonObject>, ErrorData> {
+    let schema = schemars::schema_for!(T);
+    let input_schema = serde_json::to_value(schema)
+        .map_err(|e| ErrorData::internal_error(format!("Failed to serialize schema: {e}"), None))?;
+    if let Value::Object(map) = input_schema {
+        Ok(Arc::new(map))
+    } else {
+        Err(ErrorData::internal_error("Schema is not an object", None))
+    }
+}
+
+// Accept ids pasted with stray whitespace, a 0x prefix, or uppercase hex,
+// while still rejecting anything that isn't a 32-byte hex id afterwards
+fn normalize_hex_id(input: &str) -> Result<String, String> {
+    let trimmed = input.trim();
+    let hex = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed)
+        .to_lowercase();
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("'{input}' is not a 64-character hex id"));
+    }
+    Ok(hex)
+}
+
+// serde `deserialize_with` helper for txid, block hash, and asset id fields
+fn hex_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let input = String::deserialize(deserializer)?;
+    normalize_hex_id(&input).map_err(serde::de::Error::custom)
+}
+
+// `hex_id` for optional fields, which also need `#[serde(default)]`
+fn optional_hex_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|input| normalize_hex_id(&input).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn parse_params<T: DeserializeOwned>(args: Value) -> Result<T, ErrorData> {
+    serde_json::from_value(args)
+        .map_err(|e| ErrorData::invalid_request(format!("Invalid parameters: {e}"), None))
+}
+
+// Failures the client can act on by retrying later carry a `kind` in their
+// data, to tell them apart from other upstream errors. HTTP errors carry the
+// upstream status, and the delay to back off for when the backend set one.
+fn upstream_error(err: String) -> ErrorData {
+    let mut data = serde_json::Map::new();
+    if crate::limit::is_server_busy(&err) {
+        data.insert("kind".to_string(), json!("server_busy"));
+    } else if crate::breaker::is_circuit_open(&err) {
+        data.insert("kind".to_string(), json!("backend_unavailable"));
+    }
+    if let Some(status) = crate::esplora::status_from_error(&err) {
+        data.insert("status".to_string(), json!(status));
+    }
+    if let Some(secs) = crate::esplora::retry_after_from_error(&err) {
+        data.insert("retry_after_secs".to_string(), json!(secs));
+    }
+    let data = (!data.is_empty()).then_some(Value::Object(data));
+    ErrorData::internal_error(err, data)
+}
+
+// Turn the outcome of an upstream fetch into a tool result
+fn text_result(result: Result<String, String>) -> Result<CallToolResult, ErrorData> {
+    let text = result.map_err(upstream_error)?;
+    Ok(CallToolResult::success(vec![Content::text(text)]))
+}
+
+// Paging of list results, the same for every paginated tool: the number of
+// items in this page, and the cursor to pass back for the next one, null on
+// the last page
+fn page_info(count: usize, next_cursor: Option<Value>) -> Value {
+    json!({
+        "count": count,
+        "has_more": next_cursor.is_some(),
+        "next_cursor": next_cursor,
+    })
+}
+
+// Description of the `chunk_size` parameter of tools returning large arrays
+const CHUNK_SIZE_DESCRIPTION: &str = "Optionally split the result into several content items, in order: a first one with the other fields and the number of chunks, then the array in chunks of at most this many elements. By default the whole result is a single item.";
+
+// Like `text_result`, but when `chunk_size` is set the `field` array of the
+// JSON result is split across several content items for clients consuming
+// them incrementally: the first item holds the other fields and the number
+// of chunks, then each chunk follows in order as an array of at most
+// `chunk_size` elements
+fn chunked_result(
+    result: Result<String, String>,
+    field: &str,
+    chunk_size: Option<usize>,
+) -> Result<CallToolResult, ErrorData> {
+    let Some(chunk_size) = chunk_size else {
+        return text_result(result);
+    };
+    if chunk_size == 0 {
+        return Err(ErrorData::invalid_request(
+            "chunk_size must be at least 1",
+            None,
+        ));
+    }
+    let text = result.map_err(upstream_error)?;
+    let mut header: serde_json::Map<String, Value> = serde_json::from_str(&text)
+        .map_err(|e| ErrorData::internal_error(format!("Result is not an object: {e}"), None))?;
+    let items = match header.remove(field) {
+        Some(Value::Array(items)) => items,
+        _ => {
+            return Err(ErrorData::internal_error(
+                format!("Result has no {field} array"),
+                None,
+            ));
+        }
+    };
+    let chunks: Vec<&[Value]> = items.chunks(chunk_size).collect();
+    header.insert("chunks".to_string(), json!(chunks.len()));
+    let mut content = vec![Content::text(Value::Object(header).to_string())];
+    content.extend(
+        chunks
+            .into_iter()
+            .map(|chunk| Content::text(json!(chunk).to_string())),
+    );
+    Ok(CallToolResult::success(content))
+}
+
+// Description of the `amounts_in_btc` parameter of tools returning amounts
+const AMOUNTS_IN_BTC_DESCRIPTION: &str = "Also report every satoshi amount in BTC, in a sibling field with a _btc suffix (e.g. value_btc next to value, fee_sat and fee_btc), leaving the satoshi value intact (default false).";
+
+// Satoshi amounts whose name doesn't end in `_sat`, as Esplora names them
+const SAT_FIELDS: [&str; 5] = [
+    "value",
+    "fee",
+    "total_fee",
+    "funded_txo_sum",
+    "spent_txo_sum",
+];
+
+// Name of the sibling of a satoshi amount field in another unit, e.g.
+// value_btc, if `key` is one
+fn amount_field(key: &str, unit: &str) -> Option<String> {
+    if let Some(stem) = key.strip_suffix("_sat") {
+        Some(format!("{stem}_{unit}"))
+    } else if SAT_FIELDS.contains(&key) {
+        Some(format!("{key}_{unit}"))
+    } else {
+        None
+    }
+}
+
+// Recursively add a sibling in `unit` next to every integer satoshi amount
+pub fn add_amount_siblings(value: &mut Value, unit: &str, convert: &dyn Fn(i64) -> Value) {
+    match value {
+        Value::Object(map) => {
+            let siblings: Vec<(String, Value)> = map
+                .iter()
+                .filter_map(|(key, amount)| {
+                    let sat = amount.as_i64()?;
+                    Some((amount_field(key, unit)?, convert(sat)))
+                })
+                .collect();
+            map.values_mut()
+                .for_each(|value| add_amount_siblings(value, unit, convert));
+            map.extend(siblings);
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|value| add_amount_siblings(value, unit, convert)),
+        _ => {}
+    }
+}
+
+fn add_btc_amounts(value: &mut Value) {
+    add_amount_siblings(value, "btc", &|sat| json!(sat as f64 / 100_000_000.0));
+}
+
+// Apply `amounts_in_btc` to a JSON result, leaving text results alone
+fn with_btc_amounts(result: Result<String, String>, enabled: bool) -> Result<String, String> {
+    if !enabled {
+        return result;
+    }
+    result.map(|text| match serde_json::from_str::<Value>(&text) {
+        Ok(mut value @ (Value::Object(_) | Value::Array(_))) => {
+            add_btc_amounts(&mut value);
+            value.to_string()
+        }
+        _ => text,
+    })
+}
+
+// ISO 8601 UTC timestamp of a Unix time, using the proleptic Gregorian calendar
+fn format_utc(unix_secs: u64) -> String {
+    let days = (unix_secs / 86_400) as i64;
+    let secs_of_day = unix_secs % 86_400;
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+// Description of the `format_timestamp` parameter of tools returning times
+const FORMAT_TIMESTAMP_DESCRIPTION: &str = "Also report every Unix timestamp (block_time, timestamp, mediantime) as an ISO 8601 UTC string, in a sibling field with a _utc suffix (e.g. block_time_utc), leaving the raw value intact (default false).";
+
+// Unix timestamps as Esplora names them
+const TIMESTAMP_FIELDS: [&str; 3] = ["block_time", "timestamp", "mediantime"];
+
+// Recursively add an ISO 8601 sibling next to every Unix timestamp
+fn add_utc_timestamps(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let siblings: Vec<(String, Value)> = map
+                .iter()
+                .filter(|(key, _)| TIMESTAMP_FIELDS.contains(&key.as_str()))
+                .filter_map(|(key, time)| {
+                    Some((format!("{key}_utc"), json!(format_utc(time.as_u64()?))))
+                })
+                .collect();
+            map.values_mut().for_each(add_utc_timestamps);
+            map.extend(siblings);
+        }
+        Value::Array(items) => items.iter_mut().for_each(add_utc_timestamps),
+        _ => {}
+    }
+}
+
+// Apply `format_timestamp` to a JSON result, leaving text results alone
+fn with_utc_timestamps(result: Result<String, String>, enabled: bool) -> Result<String, String> {
+    if !enabled {
+        return result;
+    }
+    result.map(|text| match serde_json::from_str::<Value>(&text) {
+        Ok(mut value @ (Value::Object(_) | Value::Array(_))) => {
+            add_utc_timestamps(&mut value);
+            value.to_string()
+        }
+        _ => text,
+    })
+}
+
+// Recursively rebuild objects with their keys in sorted order
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<String, Value> =
+                map.into_iter().map(|(k, v)| (k, sort_keys(v))).collect();
+            Value::Object(sorted.into_iter().collect())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+// Re-serialize JSON text payloads with sorted keys, so that identical data
+// always produces identical output whatever the upstream key order
+pub fn canonicalize_json(mut result: CallToolResult) -> CallToolResult {
+    for content in result.content.iter_mut() {
+        if let RawContent::Text(text) = &mut content.raw {
+            // Scalars are left alone, e.g. a hex string that happens to parse as a number
+            if let Ok(value @ (Value::Object(_) | Value::Array(_))) =
+                serde_json::from_str::<Value>(&text.text)
+            {
+                text.text = sort_keys(value).to_string();
+            }
+        }
+    }
+    result
+}
+
+// Reject a request over one of the configured caps, naming the maximum so
+// that the caller can retry within bounds
+fn check_cap(what: &str, requested: u64, max: u64) -> Result<(), ErrorData> {
+    if requested > max {
+        return Err(ErrorData::invalid_request(
+            format!(
+                "{what} {requested} exceeds the configured maximum of {max}, retry with at most {max}"
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+// Target spacing between Bitcoin blocks
+const BLOCK_INTERVAL_SECS: u64 = 600;
+
+// Items returned by list tools when the caller doesn't ask for more, to keep
+// responses within an LLM client's token budget
+const DEFAULT_LIST_LIMIT: usize = 100;
+
+fn default_list_limit() -> usize {
+    DEFAULT_LIST_LIMIT
+}
+
+// Format a satoshi amount as a decimal coin amount without trailing zeros
+fn format_coin(sat: u64) -> String {
+    format_decimal(sat, 8)
+}
+
+// Format an amount in base units with `precision` decimals, without trailing zeros
+fn format_decimal(amount: u64, precision: u32) -> String {
+    if precision == 0 {
+        return amount.to_string();
+    }
+    let divisor = 10u64.pow(precision);
+    let width = precision as usize;
+    let amount = format!("{}.{:0width$}", amount / divisor, amount % divisor);
+    amount
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+fn hex_decode(hex: &str) -> Result<Vec<u8>, String> {
+    if hex.len() % 2 != 0 {
+        return Err("Hex string has odd length".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| format!("Invalid hex at position {i}"))
+        })
+        .collect()
+}
+
+// Per-item outcomes of a multi-fetch tool, so that one failing sub-request
+// doesn't abort the whole call. Each item is `{ key, ok: true, data }` or
+// `{ key, ok: false, error }`.
+#[derive(Default)]
+struct BatchResults {
+    items: Vec<Value>,
+    succeeded: usize,
+    failed: usize,
+}
+
+impl BatchResults {
+    fn push(&mut self, key: impl Into<Value>, result: Result<Value, String>) {
+        let item = match result {
+            Ok(data) => {
+                self.succeeded += 1;
+                json!({ "key": key.into(), "ok": true, "data": data })
+            }
+            Err(error) => {
+                self.failed += 1;
+                json!({ "key": key.into(), "ok": false, "error": error })
+            }
+        };
+        self.items.push(item);
+    }
+
+    fn summary(&self) -> Value {
+        json!({ "succeeded": self.succeeded, "failed": self.failed })
+    }
+
+    // The call as a whole only fails when every item failed
+    fn check_any_succeeded(&self) -> Result<(), String> {
+        if self.succeeded == 0 && self.failed > 0 {
+            let first_error = self.items[0]["error"].as_str().unwrap_or_default();
+            return Err(format!(
+                "All {} sub-requests failed, first error: {first_error}",
+                self.failed
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn texts(result: &CallToolResult) -> Vec<&str> {
+        result
+            .content
+            .iter()
+            .filter_map(|content| match &content.raw {
+                RawContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_upstream_error_kind() {
+        let busy = upstream_error("Server busy: all 8 upstream request slots stayed in use".into());
+        assert_eq!(busy.data, Some(json!({ "kind": "server_busy" })));
+        let open =
+            upstream_error("Backend http://e unavailable after 5 consecutive failures".into());
+        assert_eq!(open.data, Some(json!({ "kind": "backend_unavailable" })));
+        assert_eq!(
+            upstream_error("status code 404".into()).data,
+            Some(json!({ "status": 404 }))
+        );
+        assert_eq!(
+            upstream_error("http://e/tx: status code 429, retry after 30s".into()).data,
+            Some(json!({ "status": 429, "retry_after_secs": 30 }))
+        );
+        assert_eq!(upstream_error("Failed to parse response".into()).data, None);
+    }
+
+    #[test]
+    fn test_check_cap() {
+        assert!(check_cap("limit", 1000, 1000).is_ok());
+        let err = check_cap("limit", 1001, 1000).unwrap_err();
+        assert_eq!(
+            err.message,
+            "limit 1001 exceeds the configured maximum of 1000, retry with at most 1000"
+        );
+    }
+
+    #[test]
+    fn test_chunked_result() {
+        let result = || Ok(r#"{"count":3,"txids":["a","b","c"]}"#.to_string());
+        let single = chunked_result(result(), "txids", None).unwrap();
+        assert_eq!(texts(&single), [r#"{"count":3,"txids":["a","b","c"]}"#]);
+
+        let chunked = chunked_result(result(), "txids", Some(2)).unwrap();
+        assert_eq!(
+            texts(&chunked),
+            [r#"{"chunks":2,"count":3}"#, r#"["a","b"]"#, r#"["c"]"#]
+        );
+        assert!(chunked_result(result(), "txids", Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_with_btc_amounts() {
+        let result = r#"{"fee":2100,"vout":[{"value":50000000,"scriptpubkey":"00"}],"delta_sat":-12000,"height":800000}"#;
+        let result: Value =
+            serde_json::from_str(&with_btc_amounts(Ok(result.to_string()), true).unwrap()).unwrap();
+        assert_eq!(result["fee"], 2100);
+        assert_eq!(result["fee_btc"], 0.000021);
+        assert_eq!(result["vout"][0]["value_btc"], 0.5);
+        assert_eq!(result["delta_btc"], -0.00012);
+        assert!(result.get("height_btc").is_none());
+
+        // Off by default, and text results are passed through
+        let text = "This transaction moved 0.5 BTC".to_string();
+        assert_eq!(with_btc_amounts(Ok(text.clone()), true).unwrap(), text);
+        assert_eq!(
+            with_btc_amounts(Ok(r#"{"fee":1}"#.to_string()), false).unwrap(),
+            r#"{"fee":1}"#
+        );
+    }
+
+    #[test]
+    fn test_aliases_point_to_current_tools() {
+        for (old, current) in ALIASES {
+            assert!(find(old).is_none(), "alias {old} shadows a tool");
+            assert!(
+                find(current).is_some(),
+                "alias {old} points to unknown {current}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_alias() {
+        let aliases = [("get_btc_tx", "get_bitcoin_tx")];
+        let tool = resolve_alias("get_btc_tx", &aliases).unwrap();
+        assert_eq!(tool.name, "get_bitcoin_tx");
+        assert!(resolve_alias("get_bitcoin_tx", &aliases).is_none());
+    }
+
+    #[test]
+    fn test_add_alias_warning() {
+        let result = CallToolResult::success(vec![Content::text(r#"{"txid":"ab"}"#)]);
+        let result = add_alias_warning(result, "get_btc_tx", "get_bitcoin_tx");
+        assert_eq!(
+            texts(&result),
+            [
+                r#"{"txid":"ab","warnings":["Tool 'get_btc_tx' is deprecated, call 'get_bitcoin_tx' instead"]}"#
+            ]
+        );
+
+        // Results that are not JSON objects are left alone
+        let result = CallToolResult::success(vec![Content::text("800000")]);
+        let result = add_alias_warning(result, "old", "new");
+        assert_eq!(texts(&result), ["800000"]);
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(1_713_571_767), "2024-04-20T00:09:27Z");
+        assert_eq!(format_utc(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_with_utc_timestamps() {
+        let result = r#"{"status":{"block_time":1713571767},"height":840000}"#;
+        let result = with_utc_timestamps(Ok(result.to_string()), true).unwrap();
+        assert_eq!(
+            result,
+            r#"{"height":840000,"status":{"block_time":1713571767,"block_time_utc":"2024-04-20T00:09:27Z"}}"#
+        );
+        let raw = r#"{"timestamp":0}"#.to_string();
+        assert_eq!(with_utc_timestamps(Ok(raw.clone()), false).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_registry_names_are_unique() {
+        let mut names = HashSet::new();
+        for tool in registry() {
+            assert!(names.insert(tool.name), "duplicate tool {}", tool.name);
+            assert!((tool.schema)().is_ok(), "{} has no valid schema", tool.name);
+        }
+        assert!(find("get_bitcoin_tx").is_some());
+        assert!(find("no_such_tool").is_none());
+    }
+
+    #[test]
+    fn test_schema_advertises_defaults() {
+        let schema = (find("get_bitcoin_tx").unwrap().schema)().unwrap();
+        assert_eq!(schema["properties"]["network"]["default"], "mainnet");
+        assert_eq!(schema["properties"]["format"]["default"], "json");
+        let schema = (find("list_liquid_assets").unwrap().schema)().unwrap();
+        assert_eq!(schema["properties"]["limit"]["default"], 25);
+        assert_eq!(schema["properties"]["cursor"]["default"], 0);
+    }
+
+    #[test]
+    fn test_canonicalize_json() {
+        let result = CallToolResult::success(vec![
+            Content::text(r#"{"b":1,"a":[{"d":2,"c":3}]}"#),
+            Content::text("1e5"),
+        ]);
+        let result = canonicalize_json(result);
+        assert_eq!(
+            result.content[0].as_text().unwrap().text,
+            r#"{"a":[{"c":3,"d":2}],"b":1}"#
+        );
+        assert_eq!(result.content[1].as_text().unwrap().text, "1e5");
+    }
+
+    #[test]
+    fn test_normalize_hex_id() {
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let pasted = format!("  0x{}\n", txid.to_uppercase());
+        assert_eq!(normalize_hex_id(&pasted).unwrap(), txid);
+        assert!(normalize_hex_id("abcd").is_err());
+        assert!(normalize_hex_id(&txid.replace('a', "z")).is_err());
+    }
+
+    #[test]
+    fn test_format_coin() {
+        assert_eq!(format_coin(50_000_000), "0.5");
+        assert_eq!(format_coin(12_000), "0.00012");
+        assert_eq!(format_coin(2_100_000_000_000_000), "21000000");
+        assert_eq!(format_coin(0), "0");
+    }
+
+    #[test]
+    fn test_format_decimal() {
+        assert_eq!(format_decimal(1234, 0), "1234");
+        assert_eq!(format_decimal(1234, 2), "12.34");
+        assert_eq!(format_decimal(5, 3), "0.005");
+        assert_eq!(format_decimal(1000, 3), "1");
+    }
+
+    #[test]
+    fn test_batch_results_all_failed() {
+        let mut batch = BatchResults::default();
+        batch.push("a", Err("boom".to_string()));
+        batch.push("b", Err("bang".to_string()));
+        let err = batch.check_any_succeeded().unwrap_err();
+        assert_eq!(err, "All 2 sub-requests failed, first error: boom");
+    }
+}
@@ -0,0 +1,162 @@
+// Network constants, computed locally without any network call
+use super::{ToolDef, make_schema, parse_params};
+use crate::config::Chain;
+use bitcoin::Network;
+use bitcoin::blockdata::constants::{
+    PUBKEY_ADDRESS_PREFIX_MAIN, PUBKEY_ADDRESS_PREFIX_TEST, SCRIPT_ADDRESS_PREFIX_MAIN,
+    SCRIPT_ADDRESS_PREFIX_TEST, genesis_block,
+};
+use rmcp::model::{CallToolResult, Content};
+use rmcp::schemars;
+use rmcp::serde_json::{Value, json};
+use serde::Deserialize;
+
+pub(super) const TOOLS: &[ToolDef] = &[ToolDef {
+    name: "get_bitcoin_network_params",
+    chain: None,
+    description: "Get the constants of a network, to build or validate addresses and to check which network is meant, without any network call: the bech32 HRP, the base58 version bytes of p2pkh and p2sh addresses, the P2P message magic (hex) and default port, and the genesis block hash. Bitcoin values come from the bitcoin crate. Liquid networks also report their blinded address prefix and blech32 HRP (confidential addresses), and no magic.",
+    schema: make_schema::<GetBitcoinNetworkParamsParams>,
+    handler: |server, args| {
+        let params: GetBitcoinNetworkParamsParams = parse_params(args)?;
+        let result = match params.network() {
+            ParamsNetwork::Bitcoin(network) => {
+                server.require_chain(Chain::Bitcoin)?;
+                bitcoin_params(network)
+            }
+            ParamsNetwork::Liquid(liquid) => {
+                server.require_chain(Chain::Liquid)?;
+                liquid_params(liquid)
+            }
+        };
+        Ok(CallToolResult::success(vec![Content::text(
+            result.to_string(),
+        )]))
+    },
+}];
+
+#[derive(Deserialize, schemars::JsonSchema, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum NetworkName {
+    #[default]
+    Mainnet,
+    Testnet,
+    Signet,
+    Liquid,
+    Liquidtestnet,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinNetworkParamsParams {
+    #[schemars(
+        description = "The network: 'mainnet' (default), 'testnet', or 'signet' for Bitcoin, 'liquid' or 'liquidtestnet' for Liquid."
+    )]
+    #[serde(default)]
+    network: NetworkName,
+}
+
+enum ParamsNetwork {
+    Bitcoin(Network),
+    // Liquid testnet when false
+    Liquid(bool),
+}
+
+impl GetBitcoinNetworkParamsParams {
+    fn network(&self) -> ParamsNetwork {
+        match self.network {
+            NetworkName::Mainnet => ParamsNetwork::Bitcoin(Network::Bitcoin),
+            NetworkName::Testnet => ParamsNetwork::Bitcoin(Network::Testnet),
+            NetworkName::Signet => ParamsNetwork::Bitcoin(Network::Signet),
+            NetworkName::Liquid => ParamsNetwork::Liquid(true),
+            NetworkName::Liquidtestnet => ParamsNetwork::Liquid(false),
+        }
+    }
+}
+
+fn bitcoin_params(network: Network) -> Value {
+    let (hrp, p2pkh, p2sh, port, name) = match network {
+        Network::Bitcoin => (
+            bitcoin::bech32::hrp::BC,
+            PUBKEY_ADDRESS_PREFIX_MAIN,
+            SCRIPT_ADDRESS_PREFIX_MAIN,
+            8333,
+            "mainnet",
+        ),
+        Network::Signet => (
+            bitcoin::bech32::hrp::TB,
+            PUBKEY_ADDRESS_PREFIX_TEST,
+            SCRIPT_ADDRESS_PREFIX_TEST,
+            38333,
+            "signet",
+        ),
+        _ => (
+            bitcoin::bech32::hrp::TB,
+            PUBKEY_ADDRESS_PREFIX_TEST,
+            SCRIPT_ADDRESS_PREFIX_TEST,
+            18333,
+            "testnet",
+        ),
+    };
+    json!({
+        "chain": "bitcoin",
+        "network": name,
+        "bech32_hrp": hrp.to_lowercase(),
+        "p2pkh_version": p2pkh,
+        "p2sh_version": p2sh,
+        "magic": network.magic().to_string(),
+        "default_port": port,
+        "genesis_block_hash": genesis_block(network).block_hash().to_string(),
+    })
+}
+
+// From the Elements chain parameters of liquidv1 and liquidtestnet
+fn liquid_params(mainnet: bool) -> Value {
+    if mainnet {
+        json!({
+            "chain": "liquid",
+            "network": "mainnet",
+            "bech32_hrp": "ex",
+            "blech32_hrp": "lq",
+            "p2pkh_version": 57,
+            "p2sh_version": 39,
+            "blinded_prefix": 12,
+            "magic": null,
+            "default_port": 7042,
+            "genesis_block_hash": "1466275836220db2944ca059a3a10ef6fd2ea684b0688d2c379296888a206003",
+        })
+    } else {
+        json!({
+            "chain": "liquid",
+            "network": "testnet",
+            "bech32_hrp": "tex",
+            "blech32_hrp": "tlq",
+            "p2pkh_version": 36,
+            "p2sh_version": 19,
+            "blinded_prefix": 23,
+            "magic": null,
+            "default_port": 18891,
+            "genesis_block_hash": "a771da8e52ee6ad581ed1e9a99825e5b3b7992225534eaa2ae23244fe26ab1c1",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitcoin_params() {
+        let params = bitcoin_params(Network::Bitcoin);
+        assert_eq!(params["bech32_hrp"], "bc");
+        assert_eq!(params["p2pkh_version"], 0);
+        assert_eq!(params["p2sh_version"], 5);
+        assert_eq!(params["magic"], "f9beb4d9");
+        assert_eq!(
+            params["genesis_block_hash"],
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        let params = bitcoin_params(Network::Signet);
+        assert_eq!(params["bech32_hrp"], "tb");
+        assert_eq!(params["p2pkh_version"], 111);
+        assert_eq!(params["default_port"], 38333);
+    }
+}
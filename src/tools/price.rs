@@ -0,0 +1,41 @@
+// Fiat price of BTC, from the external price API enabling fiat amounts
+use super::{ToolDef, make_schema, parse_params, text_result};
+use crate::MyServer;
+use crate::fiat::{btc_price, fetch_prices, parse_currency};
+use rmcp::schemars;
+use rmcp::serde_json::json;
+use serde::Deserialize;
+
+pub(super) const TOOLS: &[ToolDef] = &[ToolDef {
+    name: "get_btc_price",
+    chain: None,
+    description: "Get the approximate price of one BTC in a fiat currency (default USD), from the external price API configured on this server, not from Esplora. Only available when the server enables fiat prices. The price is cached for a short while (price_cache_ttl_secs in server_capabilities) and is only indicative. Returns the currency, the price, and the time the API gives for it, if any. Tools returning amounts also take a fiat argument converting them at this price.",
+    schema: make_schema::<GetBtcPriceParams>,
+    handler: |server, args| {
+        let params: GetBtcPriceParams = parse_params(args)?;
+        let currency = parse_currency(&params.currency)?;
+        text_result(fetch_btc_price(server, &currency))
+    },
+}];
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBtcPriceParams {
+    #[schemars(description = "The 3-letter fiat currency code, e.g. 'USD' (default) or 'EUR'.")]
+    #[serde(default = "default_currency")]
+    currency: String,
+}
+
+fn fetch_btc_price(server: &MyServer, currency: &str) -> Result<String, String> {
+    let prices = fetch_prices(server)?;
+    let result = json!({
+        "currency": currency,
+        "btc_price": btc_price(&prices, currency)?,
+        "time": prices["time"],
+        "approximate": true,
+    });
+    Ok(result.to_string())
+}
@@ -0,0 +1,225 @@
+// Script tools, computed locally without any network call
+use super::{ToolDef, hex_decode, make_schema, parse_params};
+use crate::config::Chain;
+use crate::esplora::BitcoinNetwork;
+use rmcp::model::{CallToolResult, Content, ErrorData};
+use rmcp::schemars;
+use rmcp::serde_json::json;
+use serde::Deserialize;
+
+pub(super) const TOOLS: &[ToolDef] = &[
+    ToolDef {
+        name: "decode_bitcoin_script",
+        chain: Some(Chain::Bitcoin),
+        description: "Disassemble a hex-encoded Bitcoin script (scriptPubKey, scriptSig, redeem or witness script) locally without any network call. Returns the opcodes as asm, the size in bytes, and the detected type using Esplora's scriptpubkey_type names (p2pk, p2pkh, p2sh, v0_p2wpkh, v0_p2wsh, v1_p2tr, op_return, multisig), or nonstandard.",
+        schema: make_schema::<DecodeBitcoinScriptParams>,
+        handler: |_server, args| {
+            let params: DecodeBitcoinScriptParams = parse_params(args)?;
+            let result =
+                decode_script(&params.hex).map_err(|e| ErrorData::invalid_request(e, None))?;
+            Ok(CallToolResult::success(vec![Content::text(result)]))
+        },
+    },
+    ToolDef {
+        name: "script_to_address",
+        chain: Some(Chain::Bitcoin),
+        description: "Derive the address paying to a hex-encoded redeem or witness script, e.g. a multisig script, locally without any network call, to check that a script matches an expected deposit address. The type is p2sh (legacy, redeem script of at most 520 bytes), p2wsh (native segwit), or p2sh-p2wsh (segwit wrapped in p2sh). Returns the address and its scriptPubKey hex.",
+        schema: make_schema::<ScriptToAddressParams>,
+        handler: |_server, args| {
+            let params: ScriptToAddressParams = parse_params(args)?;
+            let result = script_to_address(&params.script, params.script_type, params.network)
+                .map_err(|e| ErrorData::invalid_request(e, None))?;
+            Ok(CallToolResult::success(vec![Content::text(result)]))
+        },
+    },
+];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DecodeBitcoinScriptParams {
+    #[schemars(description = "The script to decode, as hex.")]
+    hex: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema, Clone, Copy)]
+enum ScriptAddressType {
+    #[serde(rename = "p2sh")]
+    P2sh,
+    #[serde(rename = "p2wsh")]
+    P2wsh,
+    #[serde(rename = "p2sh-p2wsh")]
+    P2shP2wsh,
+}
+
+impl ScriptAddressType {
+    fn as_str(self) -> &'static str {
+        match self {
+            ScriptAddressType::P2sh => "p2sh",
+            ScriptAddressType::P2wsh => "p2wsh",
+            ScriptAddressType::P2shP2wsh => "p2sh-p2wsh",
+        }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ScriptToAddressParams {
+    #[schemars(
+        description = "The redeem script (p2sh) or witness script (p2wsh, p2sh-p2wsh), as hex."
+    )]
+    script: String,
+    #[schemars(description = "How the script is paid to: 'p2sh', 'p2wsh', or 'p2sh-p2wsh'.")]
+    #[serde(rename = "type")]
+    script_type: ScriptAddressType,
+    #[schemars(
+        description = "The Bitcoin network of the address: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+// Consensus limit on the size of a witness script
+const MAX_WITNESS_SCRIPT_SIZE: usize = 10_000;
+
+fn script_type_name(script: &bitcoin::Script) -> &'static str {
+    if script.is_p2pk() {
+        "p2pk"
+    } else if script.is_p2pkh() {
+        "p2pkh"
+    } else if script.is_p2sh() {
+        "p2sh"
+    } else if script.is_p2wpkh() {
+        "v0_p2wpkh"
+    } else if script.is_p2wsh() {
+        "v0_p2wsh"
+    } else if script.is_p2tr() {
+        "v1_p2tr"
+    } else if script.is_op_return() {
+        "op_return"
+    } else if script.is_multisig() {
+        "multisig"
+    } else {
+        "nonstandard"
+    }
+}
+
+fn decode_script(hex: &str) -> Result<String, String> {
+    let bytes = hex_decode(hex.trim()).map_err(|e| format!("Invalid script hex: {e}"))?;
+    let script = bitcoin::Script::from_bytes(&bytes);
+    // Malformed pushes are rendered inline by to_asm_string rather than failing
+    let result = json!({
+        "asm": script.to_asm_string(),
+        "type": script_type_name(script),
+        "size": bytes.len(),
+    });
+    Ok(result.to_string())
+}
+
+fn script_to_address(
+    hex: &str,
+    script_type: ScriptAddressType,
+    network: BitcoinNetwork,
+) -> Result<String, String> {
+    let bytes = hex_decode(hex.trim()).map_err(|e| format!("Invalid script hex: {e}"))?;
+    let script = bitcoin::Script::from_bytes(&bytes);
+    if script.is_empty() {
+        return Err("The script is empty".to_string());
+    }
+    // Unlike decode_bitcoin_script, a script that can't be parsed is refused:
+    // its address would be unspendable
+    if let Some(Err(e)) = script.instructions().find(Result::is_err) {
+        return Err(format!("Invalid script: {e}"));
+    }
+    let network = network.to_bitcoin_network();
+    let address = match script_type {
+        ScriptAddressType::P2sh => bitcoin::Address::p2sh(script, network).map_err(|_| {
+            format!(
+                "The script is {} bytes, over the 520 bytes a p2sh redeem script may have: use p2wsh or p2sh-p2wsh",
+                bytes.len()
+            )
+        })?,
+        ScriptAddressType::P2wsh | ScriptAddressType::P2shP2wsh
+            if bytes.len() > MAX_WITNESS_SCRIPT_SIZE =>
+        {
+            return Err(format!(
+                "The script is {} bytes, over the {MAX_WITNESS_SCRIPT_SIZE} bytes a witness script may have",
+                bytes.len()
+            ));
+        }
+        ScriptAddressType::P2wsh => bitcoin::Address::p2wsh(script, network),
+        ScriptAddressType::P2shP2wsh => bitcoin::Address::p2shwsh(script, network),
+    };
+    let result = json!({
+        "address": address.to_string(),
+        "type": script_type.as_str(),
+        "script_pubkey": address.script_pubkey().to_hex_string(),
+        "script_type": script_type_name(script),
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::serde_json::{self, Value};
+
+    #[test]
+    fn test_decode_script() {
+        let result = decode_script("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["type"], "p2pkh");
+        assert_eq!(
+            result["asm"],
+            "OP_DUP OP_HASH160 OP_PUSHBYTES_20 62e907b15cbf27d5425399ebf6f0fb50ebb88f18 OP_EQUALVERIFY OP_CHECKSIG"
+        );
+        assert_eq!(result["size"], 25);
+
+        // Truncated push: still disassembled, typed nonstandard
+        let result: Value = serde_json::from_str(&decode_script("514c05ab").unwrap()).unwrap();
+        assert_eq!(result["type"], "nonstandard");
+        assert!(result["asm"].as_str().unwrap().starts_with("OP_PUSHNUM_1"));
+
+        assert!(decode_script("zz").is_err());
+    }
+
+    #[test]
+    fn test_script_to_address() {
+        // 1-of-1 multisig
+        let script = "512102f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f951ae";
+        let address = |script_type, network| -> Value {
+            serde_json::from_str(&script_to_address(script, script_type, network).unwrap()).unwrap()
+        };
+        let result = address(ScriptAddressType::P2sh, BitcoinNetwork::Mainnet);
+        assert!(result["address"].as_str().unwrap().starts_with('3'));
+        assert!(
+            result["script_pubkey"]
+                .as_str()
+                .unwrap()
+                .starts_with("a914")
+        );
+        assert_eq!(result["script_type"], "multisig");
+        let result = address(ScriptAddressType::P2wsh, BitcoinNetwork::Testnet);
+        assert!(result["address"].as_str().unwrap().starts_with("tb1q"));
+        assert!(
+            result["script_pubkey"]
+                .as_str()
+                .unwrap()
+                .starts_with("0020")
+        );
+        let result = address(ScriptAddressType::P2shP2wsh, BitcoinNetwork::Mainnet);
+        assert!(result["address"].as_str().unwrap().starts_with('3'));
+
+        let big = "51".repeat(521);
+        let err =
+            script_to_address(&big, ScriptAddressType::P2sh, BitcoinNetwork::Mainnet).unwrap_err();
+        assert!(err.contains("520 bytes"));
+        assert!(script_to_address(&big, ScriptAddressType::P2wsh, BitcoinNetwork::Mainnet).is_ok());
+        assert!(
+            script_to_address(
+                "514c05ab",
+                ScriptAddressType::P2wsh,
+                BitcoinNetwork::Mainnet
+            )
+            .is_err()
+        );
+        assert!(script_to_address("", ScriptAddressType::P2sh, BitcoinNetwork::Mainnet).is_err());
+    }
+}
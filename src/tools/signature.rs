@@ -0,0 +1,98 @@
+// Signature tools, computed locally without any network call
+use super::{ToolDef, hex_decode, make_schema, parse_params};
+use bitcoin::secp256k1::{Message, Secp256k1, XOnlyPublicKey, schnorr};
+use rmcp::model::{CallToolResult, Content, ErrorData};
+use rmcp::schemars;
+use rmcp::serde_json::json;
+use serde::Deserialize;
+
+pub(super) const TOOLS: &[ToolDef] = &[ToolDef {
+    name: "verify_schnorr_signature",
+    chain: None,
+    description: "Verify a BIP340 Schnorr signature, as used by taproot and nostr, locally without any network call. Takes the 32-byte x-only public key, the 32-byte message hash that was signed, and the 64-byte signature, all as hex. Returns valid (bool), with a reason when it is false, e.g. a public key that isn't a point on the curve. Inputs of the wrong length fail with the expected length. BIP340 signs a 32-byte message directly: hash longer messages first, the way the protocol at hand specifies (e.g. a tagged hash).",
+    schema: make_schema::<VerifySchnorrSignatureParams>,
+    handler: |_server, args| {
+        let params: VerifySchnorrSignatureParams = parse_params(args)?;
+        let result = verify_schnorr(&params.pubkey, &params.message_hash, &params.signature)
+            .map_err(|e| ErrorData::invalid_request(e, None))?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    },
+}];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct VerifySchnorrSignatureParams {
+    #[schemars(description = "The 32-byte x-only public key, as 64 hex characters.")]
+    pubkey: String,
+    #[schemars(description = "The 32-byte message hash that was signed, as 64 hex characters.")]
+    message_hash: String,
+    #[schemars(description = "The 64-byte BIP340 signature, as 128 hex characters.")]
+    signature: String,
+}
+
+// Decode `hex` into exactly N bytes
+fn fixed_bytes<const N: usize>(what: &str, hex: &str) -> Result<[u8; N], String> {
+    let bytes = hex_decode(hex.trim()).map_err(|e| format!("Invalid {what} hex: {e}"))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("{what} must be {N} bytes, got {}", bytes.len()))
+}
+
+fn verify_schnorr(pubkey: &str, message_hash: &str, signature: &str) -> Result<String, String> {
+    let pubkey = fixed_bytes::<32>("pubkey", pubkey)?;
+    let message = Message::from_digest(fixed_bytes::<32>("message_hash", message_hash)?);
+    let signature = fixed_bytes::<64>("signature", signature)?;
+    let outcome = XOnlyPublicKey::from_slice(&pubkey)
+        .map_err(|_| "The public key is not a valid x-only point on the curve")
+        .and_then(|pubkey| {
+            let signature = schnorr::Signature::from_slice(&signature)
+                .map_err(|_| "The signature is malformed")?;
+            Secp256k1::verification_only()
+                .verify_schnorr(&signature, &message, &pubkey)
+                .map_err(|_| "The signature doesn't match this public key and message hash")
+        });
+    let result = match outcome {
+        Ok(()) => json!({ "valid": true }),
+        Err(reason) => json!({ "valid": false, "reason": reason }),
+    };
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::serde_json::{self, Value};
+
+    #[test]
+    fn test_verify_schnorr() {
+        // BIP340 test vector 0
+        let pubkey = "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+        let message = "0".repeat(64);
+        let signature = "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0";
+        let verify = |pubkey, message: &str, signature| {
+            let result = verify_schnorr(pubkey, message, signature).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        assert_eq!(
+            verify(pubkey, &message, signature),
+            json!({ "valid": true })
+        );
+        let result = verify(pubkey, &"1".repeat(64), signature);
+        assert_eq!(result["valid"], false);
+        assert_eq!(
+            result["reason"],
+            "The signature doesn't match this public key and message hash"
+        );
+        // Not the x coordinate of a point on the curve
+        let result = verify(&"0".repeat(64), &message, signature);
+        assert_eq!(result["valid"], false);
+
+        assert_eq!(
+            verify_schnorr(pubkey, &message, &signature[1..]).unwrap_err(),
+            "Invalid signature hex: Hex string has odd length"
+        );
+        assert_eq!(
+            verify_schnorr(&pubkey[..62], &message, signature).unwrap_err(),
+            "pubkey must be 32 bytes, got 31"
+        );
+    }
+}
@@ -0,0 +1,4143 @@
+// Transaction tools
+use super::address::parse_address;
+use super::liquid::{annotate_blinded_tx, blinded_warnings};
+use super::mempool::fetch_target_feerate;
+use super::{
+    AMOUNTS_IN_BTC_DESCRIPTION, FORMAT_TIMESTAMP_DESCRIPTION, OutputFormat, ToolDef, format_coin,
+    hex_decode, hex_encode, hex_id, make_schema, parse_params, text_result, upstream_error,
+    with_btc_amounts, with_utc_timestamps,
+};
+use crate::config::{Chain, Config};
+use crate::esplora::{
+    BLOCK_TXS_PAGE_SIZE, BitcoinNetwork, EsploraClient, LiquidNetwork, fetch_esplora,
+    fetch_esplora_bytes, fetch_json, fetch_tip_height, fetch_transaction, is_not_found,
+    status_from_error,
+};
+use crate::render::Locale;
+use bitcoin::Witness;
+use bitcoin::hashes::{Hash, sha256};
+use bitcoin::secp256k1::{Parity, Secp256k1, XOnlyPublicKey};
+use bitcoin::sighash::TapSighashType;
+use bitcoin::taproot::{ControlBlock, LeafVersion, TapLeafHash};
+use rmcp::model::{CallToolResult, Content, ErrorData};
+use rmcp::schemars;
+use rmcp::serde_json::{self, Value, json};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+pub(super) const TOOLS: &[ToolDef] = &[
+    ToolDef {
+        name: "get_bitcoin_tx",
+        chain: Some(Chain::Bitcoin),
+        description: "Get a Bitcoin transaction by its txid from the Esplora API. Returns full transaction data including confirmation status and block height, or a one-paragraph plain-English summary with format 'text'.",
+        schema: make_schema::<GetBitcoinTxParams>,
+        handler: |server, args| {
+            let params: GetBitcoinTxParams = parse_params(args)?;
+            let result = fetch_transaction(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            );
+            text_result(with_utc_timestamps(
+                with_btc_amounts(
+                    render_tx(result, params.format, "BTC", server.config.locale),
+                    params.amounts_in_btc,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_liquid_tx",
+        chain: Some(Chain::Liquid),
+        description: "Get a Liquid transaction by its txid from the Esplora API. Returns full transaction data including confirmation status and block height, or a one-paragraph plain-English summary with format 'text'. Confidential amounts and assets are blinded: they have no value/asset field, only a commitment, and a top-level warnings array lists them.",
+        schema: make_schema::<GetLiquidTxParams>,
+        handler: |server, args| {
+            let params: GetLiquidTxParams = parse_params(args)?;
+            let result = fetch_transaction(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            );
+            let result = match params.format {
+                OutputFormat::Json => result.and_then(annotate_blinded_tx),
+                OutputFormat::Text => result,
+            };
+            text_result(render_tx(
+                result,
+                params.format,
+                "L-BTC",
+                server.config.locale,
+            ))
+        },
+    },
+    ToolDef {
+        name: "find_tx",
+        chain: None,
+        description: "Find a transaction by txid without knowing its chain. Searches Bitcoin and Liquid concurrently (only the networks enabled on this server) and returns the transaction annotated with the chain it was found on.",
+        schema: make_schema::<FindTxParams>,
+        handler: |server, args| {
+            let params: FindTxParams = parse_params(args)?;
+            text_result(find_transaction(
+                server.client.as_ref(),
+                &server.config,
+                &params.txid,
+                &params.bitcoin_network.api_base(),
+                &params.liquid_network.api_base(),
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_opreturn",
+        chain: Some(Chain::Bitcoin),
+        description: "Extract the data embedded in a Bitcoin transaction's OP_RETURN outputs. Returns an array with the output index, the data as hex, and a best-effort UTF-8 rendering for each OP_RETURN output (empty if there are none).",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_opreturn(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_coinjoin_heuristic",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a Bitcoin transaction looks like a coinjoin, from its most repeated output value. Returns likely_coinjoin, the equal_output_value (sats) and equal_output_count, and the input and output counts. It is flagged when at least 3 outputs share a value and there are at least as many inputs. This is a heuristic: the result is indicative, not definitive, as batched payments can match and coinjoins can be crafted to avoid it.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_coinjoin_heuristic(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_self_transfer",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a Bitcoin transaction pays back to its own input addresses, i.e. has change sent to a reused address or is a self-send. Returns self_transfer (bool, true when any input address also receives an output), matched_addresses (sorted), the indexes of the outputs paying them, and all_outputs_to_inputs, true when every output goes back to an input address, as in a consolidation. This is a heuristic for telling payments from internal moves: wallets sending change to fresh addresses never match.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_self_transfer(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_consolidation",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a Bitcoin transaction is a consolidation, merging many UTXOs into few outputs, as wallets and exchanges do for housekeeping when fees are low. It is flagged when it has at least min_inputs inputs (default 10) and at most max_outputs outputs (default 2), OP_RETURN outputs left out. Returns consolidation (bool), the input and output counts, the thresholds used, consolidated_value (the sum of the inputs, in sats), output_value and fee. This is a heuristic: a batched payment from many coins looks the same.",
+        schema: make_schema::<GetBitcoinTxConsolidationParams>,
+        handler: |server, args| {
+            let params: GetBitcoinTxConsolidationParams = parse_params(args)?;
+            text_result(fetch_tx_consolidation(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                params.min_inputs,
+                params.max_outputs,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_privacy_score",
+        chain: Some(Chain::Bitcoin),
+        description: "Rate the privacy of a Bitcoin transaction from 0 (worst) to 100, for an at-a-glance assessment. The score starts at 100 and loses the penalty of each heuristic that flags: address_reuse (35, an input address is paid again), change_by_script_type (25, a single output shares the inputs' script type, likely the change), round_amounts (20, some outputs are round amounts and others not, telling the payment from the change), and no_equal_outputs (20, no two outputs share a value, unlike a coinjoin). Returns the score and each heuristic with flagged, its penalty, and a detail. This is a heuristic: it only looks at this transaction, a high score doesn't mean the payment can't be traced.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_privacy_score(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_fingerprint",
+        chain: Some(Chain::Bitcoin),
+        description: "Get a deterministic fingerprint of a Bitcoin transaction's structure, independent of its addresses and exact amounts, e.g. to group transactions likely built by the same wallet software. The fingerprint reads like v2-lth-rbf-sw-i2[v0_p2wpkh,v0_p2wpkh]-o2[v1_p2tr:e5r,v0_p2wpkh:e6]: the version, the locktime kind (lt0 none, lth block height, ltt timestamp), rbf when an input signals BIP125, sw when it has witness data, then the input count with the spent script type of each input, and the output count with each output's script type and value bucket, in transaction order. A value bucket eN means N+1 digits in sats, i.e. at least 10^N, with r when it is a multiple of 100,000 sats. Returns the fingerprint, a short SHA256 fingerprint_hash of it to compare, and the components broken down.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_fingerprint(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_raw",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the raw serialized bytes of a Bitcoin transaction, hex-encoded. Esplora serves these both as binary (/tx/:txid/raw, used here) and as hex text (/tx/:txid/hex); the result is the same consensus serialization either way, suitable for decoding or rebroadcasting. Use get_bitcoin_tx for the decoded JSON instead.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_raw(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "tx_to_psbt",
+        chain: Some(Chain::Bitcoin),
+        description: "Export a Bitcoin transaction as an unsigned PSBT skeleton, base64-encoded, e.g. as a template to build a similar spend. Inputs reference the same prevouts with their scriptSig and witness stripped, outputs are copied as-is. Segwit inputs carry their witness_utxo (amount and script of the prevout); legacy inputs have no prevout data, which signers need to fetch as the full previous transaction.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(tx_to_psbt(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_fee_context",
+        chain: Some(Chain::Bitcoin),
+        description: "Compare the fee rate of a confirmed Bitcoin transaction to the other transactions of its block, to tell whether it overpaid. Returns the transaction's fee, vsize, and fee rate (sat/vB), the median fee rate of a sample of the block's transactions, and the share of sampled transactions paying less (percentile). Warning: besides the transaction and its block, up to 4 pages of 25 block transactions spread across the block are fetched, so the median is an estimate from at most 100 transactions.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_fee_context(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_fee_rank",
+        chain: Some(Chain::Bitcoin),
+        description: "Rank the fee rate of a confirmed Bitcoin transaction among the other transactions of its block, to tell whether it paid more or less than them. Returns its fee rate (sat/vB), its rank (1 = highest fee rate), the counts of transactions paying more, less, and the same, the percentile (share paying less), and the block's lowest and highest fee rates. Warning: up to 4 pages of 25 block transactions spread across the block are fetched; for larger blocks complete is false and the rank is an estimate from at most 100 transactions.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_fee_rank(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_is_coinbase",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a Bitcoin transaction is a coinbase, i.e. has a single input spending the null prevout. Coinbases have no real inputs and pay no fee, so fee and input value logic doesn't apply to them. Returns is_coinbase (bool) and, for a coinbase, the height of the block it rewards.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_is_coinbase(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_coinbase_maturity",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether the outputs of a Bitcoin coinbase transaction can be spent yet: consensus rules require coinbase outputs to have 100 confirmations, i.e. they can first be spent in the block 100 above the coinbase. Returns mature (bool), confirmations, confirmations_remaining, the coinbase's block height, and spendable_at_height, the first block that may include a spend. Fails if the transaction is not a coinbase.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_coinbase_maturity(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_rbf_status",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a Bitcoin transaction signals BIP125 replace-by-fee, i.e. has any input with a sequence number below 0xfffffffe. Returns rbf (bool), the indexes of the signaling inputs, and whether the tx is confirmed: for a confirmed tx the signal is only informational, it can no longer be replaced.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_rbf_status(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_finality",
+        chain: Some(Chain::Bitcoin),
+        description: "Check in one call whether a Bitcoin transaction can be considered settled, e.g. before releasing goods: final is true when it is confirmed with at least min_confirmations confirmations (default 6). An unconfirmed transaction is never final: it can be replaced, whether or not it signals BIP125 replace-by-fee (many nodes accept full RBF), or dropped. Returns final with the reasoning fields: confirmed, confirmations, min_confirmations, confirmations_remaining, rbf_signaling, replaceable (unconfirmed and signaling RBF), and a one-line reason. Confirmations count from the current tip, so a reorg can still lower them.",
+        schema: make_schema::<GetBitcoinTxFinalityParams>,
+        handler: |server, args| {
+            let params: GetBitcoinTxFinalityParams = parse_params(args)?;
+            if params.min_confirmations == 0 {
+                return Err(ErrorData::invalid_request(
+                    "min_confirmations must be at least 1, an unconfirmed transaction is never final",
+                    None,
+                ));
+            }
+            text_result(fetch_tx_finality(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                params.min_confirmations,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_rbf_bump_feerate",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the fee rate needed to replace an unconfirmed Bitcoin transaction signaling BIP125 replace-by-fee. The suggested rate is the current estimate for the confirmation target (default 6 blocks), but at least the original rate plus the 1 sat/vB incremental relay fee. Returns the original and suggested fee rates (sat/vB), the target used, and the extra absolute fee in sats, assuming the replacement has the same size. Fails if the transaction is confirmed or doesn't signal RBF.",
+        schema: make_schema::<GetBitcoinRbfBumpFeerateParams>,
+        handler: |server, args| {
+            let params: GetBitcoinRbfBumpFeerateParams = parse_params(args)?;
+            if params.target_blocks == 0 {
+                return Err(ErrorData::invalid_request(
+                    "target_blocks must be at least 1",
+                    None,
+                ));
+            }
+            text_result(fetch_rbf_bump_feerate(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                params.target_blocks,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_rbf_chain",
+        chain: Some(Chain::Bitcoin),
+        description: "Look for the transactions conflicting with a Bitcoin transaction, i.e. its replacement candidates: its inputs are followed to whatever now spends them, and every other transaction spending one of the same outputs is returned with its fee rate, the inputs it shares, whether it signals BIP125 and whether it is confirmed. Warning: this is a heuristic, Esplora doesn't track replacements. Only the spender currently known for each output is seen, so only the latest replacement shows up, never the intermediate ones, and a replaced transaction is usually no longer known at all: query the replacement to find out which inputs it took over. Inputs found unspent mean the transaction is not in the backend's mempool. Every input costs a request, made concurrently, up to the configured maximum (MAX_SPEND_LOOKUPS): truncated is true when inputs were left unexamined.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_rbf_chain(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                server.config.max_spend_lookups,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_cpfp_feerate",
+        chain: Some(Chain::Bitcoin),
+        description: "Plan a child-pays-for-parent fee bump of an unconfirmed Bitcoin transaction: given the package fee rate to reach (sat/vB) and the vsize of the child spending one of its outputs (default 110 vB, a 1-input 1-output p2wpkh transaction), compute the fee the child must pay so that parent and child together reach it. The child always pays at least the 1 sat/vB minimum relay fee. Returns the parent's fee, vsize, and fee rate, the required child fee and fee rate, and the resulting package fee rate. Fails if the parent is confirmed.",
+        schema: make_schema::<GetBitcoinCpfpFeerateParams>,
+        handler: |server, args| {
+            let params: GetBitcoinCpfpFeerateParams = parse_params(args)?;
+            if !(params.target_feerate.is_finite() && params.target_feerate > 0.0) {
+                return Err(ErrorData::invalid_request(
+                    "target_feerate must be a positive number of sat/vB",
+                    None,
+                ));
+            }
+            if params.child_vsize == 0 {
+                return Err(ErrorData::invalid_request(
+                    "child_vsize must be at least 1",
+                    None,
+                ));
+            }
+            text_result(fetch_cpfp_feerate(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                params.target_feerate,
+                params.child_vsize,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_mempool_info",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the mempool details of an unconfirmed Bitcoin transaction, e.g. to decide between RBF and CPFP. The tx section comes from the transaction itself: vsize, fee, and fee rate (sat/vB). The mempool section comes from the backend's /v1/cpfp endpoint when it exposes one (mempool.space does, plain Esplora doesn't): the counts, fees, and vsizes of the unconfirmed ancestors and descendants, and the effective fee rate of the package. When the endpoint isn't available, mempool.available is false with the reason, and only the tx section is filled. Fails if the transaction is confirmed.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_mempool_info(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_dust",
+        chain: Some(Chain::Bitcoin),
+        description: "Find the dust outputs of a Bitcoin transaction: outputs worth less than Bitcoin Core's default dust threshold for their script type, i.e. less than spending them would cost at 3 sat/vB (546 sats for p2pkh, 294 for p2wpkh, 330 for p2tr). Dust outputs are non-standard and often a sign of spam or a misconfigured wallet. OP_RETURN outputs are never dust. Returns the number of dust outputs and, for each, its vout index, value, script type, and threshold in sats.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_dust(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "diff_bitcoin_txs",
+        chain: Some(Chain::Bitcoin),
+        description: "Compare two Bitcoin transactions, typically an original and its RBF replacement, fetched concurrently. Returns the inputs they share and those only in either one (as txid:vout), the outputs per script: only in either one or changed in value, and the fee and fee rate (sat/vB) of each with the difference from a to b. Transactions sharing no input are unrelated, which a note points out.",
+        schema: make_schema::<DiffBitcoinTxsParams>,
+        handler: |server, args| {
+            let params: DiffBitcoinTxsParams = parse_params(args)?;
+            text_result(diff_txs(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid_a,
+                &params.txid_b,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_witness",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the witness stack of one input of a Bitcoin transaction, as hex items, with a best-effort classification from the spent output type and the stack shape: p2wpkh, p2sh-p2wpkh, p2wsh, p2sh-p2wsh, taproot_keypath, taproot_scriptpath, or unknown. roles names each item (e.g. signature, pubkey, witness_script, leaf_script, control_block, annex). Inputs without a witness (legacy or coinbase) return an empty witness with a note.",
+        schema: make_schema::<BitcoinTxInputParams>,
+        handler: |server, args| {
+            let params: BitcoinTxInputParams = parse_params(args)?;
+            text_result(fetch_tx_witness(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                params.vin,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_taproot_detail",
+        chain: Some(Chain::Bitcoin),
+        description: "Decode the taproot witness of one input of a Bitcoin transaction, following BIP341. Tells a key-path spend (a single Schnorr signature, with its sighash type) from a script-path spend, for which it returns the leaf script (hex and asm), its leaf version and tapleaf hash, the script inputs, and the decoded control block: internal key, output key parity, and merkle path. It also checks that the control block commits to the spent output key. Reports the annex, if any (a last item starting with 0x50). Inputs not spending a p2tr output return taproot: false with a note.",
+        schema: make_schema::<BitcoinTxInputParams>,
+        handler: |server, args| {
+            let params: BitcoinTxInputParams = parse_params(args)?;
+            text_result(fetch_tx_taproot_detail(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                params.vin,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_input_addresses",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the value flow of a Bitcoin transaction, who paid whom, without the rest of the transaction: the ordered inputs with the address and value (sat) of the output each spends, read from the prevouts Esplora embeds, and the ordered outputs with their address, value, and script type, plus the fee. A coinbase input has a null address and value and is_coinbase: true; outputs without an address, e.g. OP_RETURN, have a null address.",
+        schema: make_schema::<BitcoinTxidParams>,
+        handler: |server, args| {
+            let params: BitcoinTxidParams = parse_params(args)?;
+            text_result(fetch_tx_input_addresses(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_input_prevout",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the output funding one input of a Bitcoin transaction, read from the prevout Esplora embeds in the transaction so no extra request is made. Returns the previous txid and vout, its value (sat), address, script type, and scriptPubKey hex. Coinbase inputs have no prevout: is_coinbase is true and prevout is null.",
+        schema: make_schema::<BitcoinTxInputParams>,
+        handler: |server, args| {
+            let params: BitcoinTxInputParams = parse_params(args)?;
+            text_result(fetch_input_prevout(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.txid,
+                params.vin,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_block",
+        chain: Some(Chain::Bitcoin),
+        description: "Get the block containing a Bitcoin transaction, without the transaction body. Returns confirmed: true with the block's height, hash, and timestamp, or confirmed: false if the transaction is still in the mempool.",
+        schema: make_schema::<GetBitcoinTxBlockParams>,
+        handler: |server, args| {
+            let params: GetBitcoinTxBlockParams = parse_params(args)?;
+            text_result(with_utc_timestamps(
+                fetch_tx_block(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.txid,
+                ),
+                params.format_timestamp,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_tx_size",
+        chain: Some(Chain::Bitcoin),
+        description: "Compute the size of a raw Bitcoin transaction given as hex, e.g. one built locally and not broadcast yet, without any network call. Returns the serialized size in bytes, the size without witness data, the weight (witness bytes count once, the others four times), and the virtual size (weight / 4, rounded up) to multiply by a sat/vB fee rate.",
+        schema: make_schema::<GetBitcoinTxSizeParams>,
+        handler: |_server, args| {
+            let params: GetBitcoinTxSizeParams = parse_params(args)?;
+            let result = tx_size(&params.hex).map_err(|e| ErrorData::invalid_request(e, None))?;
+            Ok(CallToolResult::success(vec![Content::text(result)]))
+        },
+    },
+    ToolDef {
+        name: "broadcast_bitcoin_tx",
+        chain: Some(Chain::Bitcoin),
+        description: "Broadcast a signed raw Bitcoin transaction, given as hex, through the Esplora backend (POST /tx). Returns the txid with already_known: false. Broadcasting a transaction the node already has, in its mempool or in the chain, also succeeds with already_known: true, so retries are safe. Genuine rejections fail with the node's reject reason, and a category in the error data (e.g. insufficient_fee, inputs_missing_or_spent, mempool_conflict, invalid_script, dust, non_standard).",
+        schema: make_schema::<BroadcastBitcoinTxParams>,
+        handler: |server, args| {
+            let params: BroadcastBitcoinTxParams = parse_params(args)?;
+            let tx = decode_tx(&params.hex).map_err(|e| ErrorData::invalid_request(e, None))?;
+            let result = broadcast_tx(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                params.hex.trim(),
+                &tx.compute_txid().to_string(),
+            )?;
+            Ok(CallToolResult::success(vec![Content::text(result)]))
+        },
+    },
+    ToolDef {
+        name: "validate_bitcoin_tx",
+        chain: Some(Chain::Bitcoin),
+        description: "Dry-run a signed raw Bitcoin transaction, given as hex, before broadcasting it. Nothing is broadcast. Decodes it locally and checks that it is well formed: it has inputs and outputs, no input is spent twice, output values are within the money range, and it is within the standard weight limit. With check_prevouts, also checks that every output it spends exists and is unspent, and that the inputs cover the outputs. Returns valid (bool) and a checklist of each check with passed and a detail. Signatures and scripts are not verified.",
+        schema: make_schema::<ValidateBitcoinTxParams>,
+        handler: |server, args| {
+            let params: ValidateBitcoinTxParams = parse_params(args)?;
+            text_result(validate_tx(
+                server.client.as_ref(),
+                &params.network.api_base(),
+                &params.hex,
+                params.check_prevouts,
+            ))
+        },
+    },
+    ToolDef {
+        name: "verify_bitcoin_payment",
+        chain: Some(Chain::Bitcoin),
+        description: "Check whether a Bitcoin transaction pays at least a given amount to an address, as a merchant would before accepting a payment. Returns paid (bool), the total amount_sat paid to the address, the indexes (vout) of the outputs paying it, and whether the transaction is confirmed. If no output pays the address, paid is false with a reason.",
+        schema: make_schema::<VerifyBitcoinPaymentParams>,
+        handler: |server, args| {
+            let params: VerifyBitcoinPaymentParams = parse_params(args)?;
+            // Canonical form, e.g. lowercase for bech32, as Esplora reports it
+            let address = parse_address(&params.address)
+                .map_err(|e| ErrorData::invalid_request(e, None))?
+                .to_string();
+            text_result(with_btc_amounts(
+                verify_payment(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.txid,
+                    &address,
+                    params.min_amount_sat,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_output_spender",
+        chain: Some(Chain::Bitcoin),
+        description: "Follow a Bitcoin transaction output to the transaction spending it. If the output is spent, returns spent: true, the spending input index (vin), and the full spending transaction (tx); if unspent, returns spent: false.",
+        schema: make_schema::<BitcoinOutpointParams>,
+        handler: |server, args| {
+            let params: BitcoinOutpointParams = parse_params(args)?;
+            text_result(with_btc_amounts(
+                fetch_output_spender(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.txid,
+                    params.vout,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "trace_bitcoin_output_forward",
+        chain: Some(Chain::Bitcoin),
+        description: "Follow a Bitcoin output forward through up to 3 spends: find the transaction spending it, pick one of that transaction's outputs, find the transaction spending that one, and so on. Since transactions usually have several outputs, the caller picks the output followed out of each spending transaction, in follow: one entry per hop after the first, each 'largest' for the output with the largest value or an output index. Returns the chain of outputs in order, each with its txid, vout, value, address, and the spending txid and input (null when unspent), and why the trace ended: 'unspent' or 'hops_reached'. Each hop takes 2 requests.",
+        schema: make_schema::<TraceBitcoinOutputForwardParams>,
+        handler: |server, args| {
+            let params: TraceBitcoinOutputForwardParams = parse_params(args)?;
+            if !(1..=MAX_TRACE_HOPS).contains(&params.hops) {
+                return Err(ErrorData::invalid_request(
+                    format!("hops must be between 1 and {MAX_TRACE_HOPS}"),
+                    None,
+                ));
+            }
+            if params.follow.len() + 1 < params.hops {
+                return Err(ErrorData::invalid_request(
+                    format!(
+                        "follow must pick the output to follow after each hop but the last: {} entries for {} hops, 'largest' or an output index",
+                        params.hops - 1,
+                        params.hops
+                    ),
+                    None,
+                ));
+            }
+            text_result(with_btc_amounts(
+                trace_output_forward(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.txid,
+                    params.vout,
+                    &params.follow[..params.hops - 1],
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+    ToolDef {
+        name: "get_bitcoin_utxo_status",
+        chain: Some(Chain::Bitcoin),
+        description: "Inspect one Bitcoin transaction output in a single call: its value (sat), address, and script type from the creating transaction, whether that transaction is confirmed, and whether the output is spent. Returns spent with the spending_txid and spending_vin when spent, null otherwise. A vout past the transaction's outputs is an error naming how many it has.",
+        schema: make_schema::<BitcoinOutpointParams>,
+        handler: |server, args| {
+            let params: BitcoinOutpointParams = parse_params(args)?;
+            text_result(with_btc_amounts(
+                fetch_utxo_status(
+                    server.client.as_ref(),
+                    &params.network.api_base(),
+                    &params.txid,
+                    params.vout,
+                ),
+                params.amounts_in_btc,
+            ))
+        },
+    },
+];
+
+// 1. DEFINE YOUR TOOL'S INPUT PARAMETERS
+// The AI will see this and know what to provide.
+// 'schemars::JsonSchema' automatically builds the "menu" for the AI.
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinTxParams {
+    #[schemars(description = "The transaction ID (txid) hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(
+        description = "Output format: 'json' (default) for the full transaction, or 'text' for a plain-English summary."
+    )]
+    #[serde(default)]
+    format: OutputFormat,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+fn default_bump_target() -> u32 {
+    6
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinRbfBumpFeerateParams {
+    #[schemars(
+        description = "The transaction ID (txid) of the unconfirmed transaction to replace."
+    )]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The confirmation target of the replacement, in blocks (default 6).")]
+    #[serde(default = "default_bump_target")]
+    target_blocks: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+fn default_consolidation_min_inputs() -> usize {
+    10
+}
+
+fn default_consolidation_max_outputs() -> usize {
+    2
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinTxConsolidationParams {
+    #[schemars(description = "The transaction ID (txid) hash to check.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The fewest inputs a consolidation has (default 10).")]
+    #[serde(default = "default_consolidation_min_inputs")]
+    min_inputs: usize,
+    #[schemars(
+        description = "The most outputs a consolidation has, OP_RETURN outputs left out (default 2)."
+    )]
+    #[serde(default = "default_consolidation_max_outputs")]
+    max_outputs: usize,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+fn default_min_confirmations() -> u64 {
+    6
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinTxFinalityParams {
+    #[schemars(description = "The transaction ID (txid) hash to check.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(
+        description = "The confirmations required to consider the transaction final, at least 1 (default 6)."
+    )]
+    #[serde(default = "default_min_confirmations")]
+    min_confirmations: u64,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+fn default_child_vsize() -> u64 {
+    110
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinCpfpFeerateParams {
+    #[schemars(description = "The transaction ID (txid) of the unconfirmed parent transaction.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The fee rate the parent and child package should reach, in sat/vB.")]
+    target_feerate: f64,
+    #[schemars(description = "The expected vsize of the child transaction, in vB (default 110).")]
+    #[serde(default = "default_child_vsize")]
+    child_vsize: u64,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetLiquidTxParams {
+    #[schemars(description = "The transaction ID (txid) hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The Liquid network to query: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    network: LiquidNetwork,
+    #[schemars(
+        description = "Output format: 'json' (default) for the full transaction, or 'text' for a plain-English summary."
+    )]
+    #[serde(default)]
+    format: OutputFormat,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FindTxParams {
+    #[schemars(description = "The transaction ID (txid) hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(
+        description = "The Bitcoin network to search: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    bitcoin_network: BitcoinNetwork,
+    #[schemars(description = "The Liquid network to search: 'mainnet' (default) or 'testnet'.")]
+    #[serde(default)]
+    liquid_network: LiquidNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BitcoinTxidParams {
+    #[schemars(description = "The transaction ID (txid) hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DiffBitcoinTxsParams {
+    #[schemars(description = "The txid of the first transaction, e.g. the original.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid_a: String,
+    #[schemars(description = "The txid of the second transaction, e.g. the replacement.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid_b: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BitcoinTxInputParams {
+    #[schemars(description = "The transaction ID (txid) hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The index of the input within the transaction.")]
+    vin: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinTxBlockParams {
+    #[schemars(description = "The transaction ID (txid) hash to look up.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = FORMAT_TIMESTAMP_DESCRIPTION)]
+    #[serde(default)]
+    format_timestamp: bool,
+}
+
+// Spends followed by trace_bitcoin_output_forward at most, two requests each
+const MAX_TRACE_HOPS: usize = 3;
+
+fn default_trace_hops() -> usize {
+    2
+}
+
+#[derive(Deserialize, schemars::JsonSchema, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum OutputPick {
+    Largest,
+}
+
+// "largest" or an output index
+#[derive(Deserialize, schemars::JsonSchema, Clone, Copy)]
+#[serde(untagged)]
+enum FollowOutput {
+    Pick(OutputPick),
+    Index(u32),
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct TraceBitcoinOutputForwardParams {
+    #[schemars(description = "The transaction ID (txid) of the output to trace.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The index of the output within the transaction.")]
+    vout: u32,
+    #[schemars(description = "The number of spends to follow, from 1 to 3 (default 2).")]
+    #[serde(default = "default_trace_hops")]
+    hops: usize,
+    #[schemars(
+        description = "The output to follow out of each spending transaction but the last, in order: 'largest' for the output with the largest value, or an output index, e.g. ['largest'] for 2 hops."
+    )]
+    #[serde(default)]
+    follow: Vec<FollowOutput>,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BitcoinOutpointParams {
+    #[schemars(description = "The transaction ID (txid) of the output's transaction.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The index of the output within the transaction.")]
+    vout: u32,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct VerifyBitcoinPaymentParams {
+    #[schemars(description = "The transaction ID (txid) of the payment.")]
+    #[serde(deserialize_with = "hex_id")]
+    txid: String,
+    #[schemars(description = "The address that should be paid.")]
+    address: String,
+    #[schemars(description = "The minimum amount the address should receive, in satoshis.")]
+    min_amount_sat: u64,
+    #[schemars(
+        description = "The Bitcoin network to query: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+    #[schemars(description = AMOUNTS_IN_BTC_DESCRIPTION)]
+    #[serde(default)]
+    amounts_in_btc: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct GetBitcoinTxSizeParams {
+    #[schemars(description = "The raw serialized transaction, as hex.")]
+    hex: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BroadcastBitcoinTxParams {
+    #[schemars(description = "The signed raw serialized transaction, as hex.")]
+    hex: String,
+    #[schemars(
+        description = "The Bitcoin network to broadcast on: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ValidateBitcoinTxParams {
+    #[schemars(description = "The signed raw serialized transaction, as hex.")]
+    hex: String,
+    #[schemars(
+        description = "Also check that the outputs it spends exist and are unspent (default false, one or two requests per input)."
+    )]
+    #[serde(default)]
+    check_prevouts: bool,
+    #[schemars(
+        description = "The Bitcoin network to check prevouts on: 'mainnet' (default), 'testnet', or 'signet'."
+    )]
+    #[serde(default)]
+    network: BitcoinNetwork,
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+// One-paragraph description of an Esplora transaction, amounts in `unit`
+// and numbers formatted for `locale`
+fn summarize_tx(tx: &Value, unit: &str, locale: Locale) -> String {
+    let coin = |sat: u64| locale.number(&format_coin(sat));
+    let empty = Vec::new();
+    let inputs = tx["vin"].as_array().unwrap_or(&empty);
+    let outputs = tx["vout"].as_array().unwrap_or(&empty);
+    // Liquid pays fees through an explicit output, which is not value moved
+    let moved: Vec<&Value> = outputs
+        .iter()
+        .filter(|o| o["scriptpubkey_type"] != "fee")
+        .collect();
+    let moved_sat: u64 = moved.iter().filter_map(|o| o["value"].as_u64()).sum();
+    // Confidential Liquid outputs don't expose their value
+    let confidential = moved.iter().any(|o| o["value"].is_null());
+    let is_coinbase = inputs
+        .first()
+        .is_some_and(|i| i["is_coinbase"].as_bool().unwrap_or(false));
+
+    let mut summary = if confidential {
+        format!(
+            "This transaction moved at least {} {unit} (some amounts are confidential)",
+            coin(moved_sat)
+        )
+    } else {
+        format!("This transaction moved {} {unit}", coin(moved_sat))
+    };
+    summary += &format!(
+        " across {} and {}",
+        plural(inputs.len(), "input"),
+        plural(moved.len(), "output")
+    );
+    let fee = tx["fee"].as_u64().unwrap_or(0);
+    if is_coinbase {
+        summary += ", is a coinbase transaction paying no fee";
+    } else {
+        summary += &format!(", paid a {} {unit} fee", coin(fee));
+        if let Some(weight) = tx["weight"].as_u64().filter(|w| *w > 0) {
+            let vsize = weight.div_ceil(4);
+            let feerate = (fee as f64 / vsize as f64).round();
+            summary += &format!(" (~{} sat/vB)", locale.number(&feerate.to_string()));
+        }
+    }
+    match tx["status"]["block_height"].as_u64() {
+        Some(height) if tx["status"]["confirmed"] == true => {
+            summary += &format!(", and confirmed in block {height}.");
+        }
+        _ => summary += ", and is still unconfirmed.",
+    }
+    summary
+}
+
+// Render a fetched transaction in the requested format
+fn render_tx(
+    result: Result<String, String>,
+    format: OutputFormat,
+    unit: &str,
+    locale: Locale,
+) -> Result<String, String> {
+    let body = result?;
+    if format == OutputFormat::Json {
+        return Ok(body);
+    }
+    let tx: Value =
+        serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))?;
+    Ok(summarize_tx(&tx, unit, locale))
+}
+
+// Look up a transaction on every enabled chain concurrently, preferring Bitcoin
+// when the txid is found on both
+fn find_transaction(
+    client: &dyn EsploraClient,
+    config: &Config,
+    txid: &str,
+    bitcoin_base_url: &str,
+    liquid_base_url: &str,
+) -> Result<String, String> {
+    let lookups: Vec<(Chain, &str)> = [
+        (Chain::Bitcoin, bitcoin_base_url),
+        (Chain::Liquid, liquid_base_url),
+    ]
+    .into_iter()
+    .filter(|(chain, _)| config.is_enabled(*chain))
+    .collect();
+    if lookups.is_empty() {
+        return Err("No networks are enabled on this server".to_string());
+    }
+
+    let results: Vec<(Chain, Result<Value, String>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = lookups
+            .iter()
+            .map(|(chain, base_url)| {
+                let url = format!("{base_url}/tx/{txid}");
+                let span = tracing::Span::current();
+                let handle = scope.spawn(move || span.in_scope(|| fetch_json(client, &url)));
+                (*chain, handle)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(chain, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()));
+                (chain, result)
+            })
+            .collect()
+    });
+
+    let mut errors = Vec::new();
+    for (chain, result) in results {
+        match result {
+            Ok(tx) => {
+                let mut result = json!({ "chain": chain.as_str(), "tx": tx });
+                if chain == Chain::Liquid {
+                    let warnings = blinded_warnings(&result["tx"]);
+                    if !warnings.is_empty() {
+                        result["warnings"] = json!(warnings);
+                    }
+                }
+                return Ok(result.to_string());
+            }
+            Err(e) if is_not_found(&e) => {}
+            Err(e) => errors.push(format!("{}: {e}", chain.as_str())),
+        }
+    }
+    if errors.is_empty() {
+        Err(format!("Transaction {txid} not found on any chain"))
+    } else {
+        Err(format!("Transaction lookup failed: {}", errors.join("; ")))
+    }
+}
+
+// Concatenate the data pushed after OP_RETURN in an output script
+pub(super) fn op_return_data(script: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut i = 1; // skip OP_RETURN
+    while i < script.len() {
+        let opcode = script[i] as usize;
+        i += 1;
+        let (len, header) = match opcode {
+            0x01..=0x4b => (opcode, 0),
+            0x4c if i < script.len() => (script[i] as usize, 1),
+            0x4d if i + 1 < script.len() => {
+                (u16::from_le_bytes([script[i], script[i + 1]]) as usize, 2)
+            }
+            0x4e if i + 3 < script.len() => (
+                u32::from_le_bytes([script[i], script[i + 1], script[i + 2], script[i + 3]])
+                    as usize,
+                4,
+            ),
+            _ => continue, // OP_0 or other non-push opcodes carry no data
+        };
+        i += header;
+        let end = (i + len).min(script.len());
+        data.extend_from_slice(&script[i..end]);
+        i = end;
+    }
+    data
+}
+
+fn fetch_tx_opreturn(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let mut results = Vec::new();
+    for (vout, output) in outputs.iter().enumerate() {
+        if output["scriptpubkey_type"] != "op_return" {
+            continue;
+        }
+        let script = hex_decode(output["scriptpubkey"].as_str().unwrap_or_default())?;
+        let data = op_return_data(&script);
+        results.push(json!({
+            "vout": vout,
+            "hex": hex_encode(&data),
+            "utf8": String::from_utf8_lossy(&data),
+        }));
+    }
+    Ok(Value::Array(results).to_string())
+}
+
+// Fewest outputs of the same value for a transaction to look like a coinjoin
+const COINJOIN_MIN_EQUAL_OUTPUTS: usize = 3;
+
+// The most repeated output value and its count, the largest value on ties
+fn most_repeated_output_value(outputs: &[Value]) -> (u64, usize) {
+    // OP_RETURN outputs carry no value and would all count as equal
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for output in outputs {
+        if let Some(value) = output["value"].as_u64().filter(|value| *value > 0) {
+            *counts.entry(value).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(value, count)| (*count, *value))
+        .unwrap_or_default()
+}
+
+fn fetch_coinjoin_heuristic(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let input_count = tx["vin"].as_array().map_or(0, Vec::len);
+    let (equal_output_value, equal_output_count) = most_repeated_output_value(outputs);
+    // Each participant brings at least one input per equal output
+    let likely_coinjoin =
+        equal_output_count >= COINJOIN_MIN_EQUAL_OUTPUTS && input_count >= equal_output_count;
+    let result = json!({
+        "txid": txid,
+        "likely_coinjoin": likely_coinjoin,
+        "equal_output_value": equal_output_value,
+        "equal_output_count": equal_output_count,
+        "input_count": input_count,
+        "output_count": outputs.len(),
+    });
+    Ok(result.to_string())
+}
+
+// The addresses of the outputs spent by a transaction
+fn input_addresses(tx: &Value) -> BTreeSet<&str> {
+    tx["vin"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|input| input["prevout"]["scriptpubkey_address"].as_str())
+        .collect()
+}
+
+// Input addresses that the transaction also pays to
+fn reused_addresses(tx: &Value) -> BTreeSet<&str> {
+    let input_addresses = input_addresses(tx);
+    tx["vout"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|output| output["scriptpubkey_address"].as_str())
+        .filter(|address| input_addresses.contains(address))
+        .collect()
+}
+
+fn fetch_tx_self_transfer(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let input_addresses = input_addresses(&tx);
+    let output_addresses: Vec<Option<&str>> = outputs
+        .iter()
+        .map(|output| output["scriptpubkey_address"].as_str())
+        .collect();
+    let matched = reused_addresses(&tx);
+    let matched_outputs: Vec<usize> = output_addresses
+        .iter()
+        .enumerate()
+        .filter(|(_, address)| address.is_some_and(|address| matched.contains(address)))
+        .map(|(vout, _)| vout)
+        .collect();
+    // OP_RETURN outputs have no address and don't count against it
+    let addressed_outputs = output_addresses.iter().flatten().count();
+    let result = json!({
+        "txid": txid,
+        "self_transfer": !matched.is_empty(),
+        "matched_addresses": matched,
+        "matched_outputs": matched_outputs,
+        "all_outputs_to_inputs": addressed_outputs > 0 && matched_outputs.len() == addressed_outputs,
+        "input_address_count": input_addresses.len(),
+        "output_count": outputs.len(),
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_tx_consolidation(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    min_inputs: usize,
+    max_outputs: usize,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let inputs = tx["vin"].as_array().ok_or("Transaction has no vin array")?;
+    let outputs = valued_outputs(&tx);
+    let consolidated_value: u64 = inputs
+        .iter()
+        .filter_map(|input| input["prevout"]["value"].as_u64())
+        .sum();
+    let output_value: u64 = outputs
+        .iter()
+        .filter_map(|(_, output)| output["value"].as_u64())
+        .sum();
+    let result = json!({
+        "txid": txid,
+        "consolidation": inputs.len() >= min_inputs && outputs.len() <= max_outputs,
+        "input_count": inputs.len(),
+        "output_count": outputs.len(),
+        "min_inputs": min_inputs,
+        "max_outputs": max_outputs,
+        "consolidated_value": consolidated_value,
+        "output_value": output_value,
+        "fee": tx["fee"],
+    });
+    Ok(result.to_string())
+}
+
+// Payments in round amounts, e.g. 0.001 BTC, stand out from the change
+const ROUND_AMOUNT_SAT: u64 = 100_000;
+
+// The outputs carrying value, i.e. leaving OP_RETURN outputs out
+fn valued_outputs(tx: &Value) -> Vec<(usize, &Value)> {
+    tx["vout"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, output)| output["value"].as_u64().is_some_and(|value| value > 0))
+        .collect()
+}
+
+fn address_reuse_leak(tx: &Value) -> Option<String> {
+    let reused = reused_addresses(tx);
+    (!reused.is_empty()).then(|| {
+        format!(
+            "Pays back to its input address {}",
+            reused.into_iter().collect::<Vec<_>>().join(", ")
+        )
+    })
+}
+
+fn round_amount_leak(tx: &Value) -> Option<String> {
+    let outputs = valued_outputs(tx);
+    let round: Vec<String> = outputs
+        .iter()
+        .filter(|(_, output)| output["value"].as_u64().unwrap_or(0) % ROUND_AMOUNT_SAT == 0)
+        .map(|(vout, _)| vout.to_string())
+        .collect();
+    (!round.is_empty() && round.len() < outputs.len()).then(|| {
+        format!(
+            "Output {} has a round amount, unlike the others",
+            round.join(", ")
+        )
+    })
+}
+
+fn script_type_change_leak(tx: &Value) -> Option<String> {
+    let input_types: BTreeSet<&str> = tx["vin"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|input| input["prevout"]["scriptpubkey_type"].as_str())
+        .collect();
+    let [input_type] = input_types.into_iter().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let outputs = valued_outputs(tx);
+    let matching: Vec<usize> = outputs
+        .iter()
+        .filter(|(_, output)| output["scriptpubkey_type"] == input_type)
+        .map(|(vout, _)| *vout)
+        .collect();
+    match matching[..] {
+        [vout] if outputs.len() >= 2 => Some(format!(
+            "Output {vout} is the only one of the inputs' script type {input_type}, likely the change"
+        )),
+        _ => None,
+    }
+}
+
+fn no_equal_outputs_leak(tx: &Value) -> Option<String> {
+    let outputs: Vec<Value> = valued_outputs(tx)
+        .into_iter()
+        .map(|(_, output)| output.clone())
+        .collect();
+    let (_, count) = most_repeated_output_value(&outputs);
+    (count < 2).then(|| "No two outputs share a value to hide the payment among".to_string())
+}
+
+// Privacy heuristics as (name, penalty, check), a check returning what it
+// found when it flags the transaction
+type PrivacyHeuristic = (&'static str, u32, fn(&Value) -> Option<String>);
+
+const PRIVACY_HEURISTICS: [PrivacyHeuristic; 4] = [
+    ("address_reuse", 35, address_reuse_leak),
+    ("change_by_script_type", 25, script_type_change_leak),
+    ("round_amounts", 20, round_amount_leak),
+    ("no_equal_outputs", 20, no_equal_outputs_leak),
+];
+
+fn fetch_tx_privacy_score(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let mut score = 100;
+    let heuristics: Vec<Value> = PRIVACY_HEURISTICS
+        .iter()
+        .map(|(name, penalty, check)| {
+            let detail = check(&tx);
+            if detail.is_some() {
+                score -= penalty;
+            }
+            json!({
+                "heuristic": name,
+                "flagged": detail.is_some(),
+                "penalty": penalty,
+                "detail": detail,
+            })
+        })
+        .collect();
+    let result = json!({
+        "txid": txid,
+        "score": score,
+        "heuristics": heuristics,
+        "note": "Heuristic score from this transaction alone, indicative only",
+    });
+    Ok(result.to_string())
+}
+
+// Order of magnitude of an output value, flagged when round
+fn value_bucket(value: u64) -> String {
+    let magnitude = value.checked_ilog10().unwrap_or(0);
+    let round = value > 0 && value % ROUND_AMOUNT_SAT == 0;
+    format!("e{magnitude}{}", if round { "r" } else { "" })
+}
+
+// Locktimes below this are block heights, timestamps otherwise
+const LOCKTIME_THRESHOLD: u64 = 500_000_000;
+
+// The structure of a transaction as a string, and its components
+fn tx_fingerprint(tx: &Value) -> Result<(String, Value), String> {
+    let inputs = tx["vin"].as_array().ok_or("Transaction has no vin array")?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let version = tx["version"].as_u64().ok_or("Transaction has no version")?;
+    let locktime = match tx["locktime"].as_u64().unwrap_or(0) {
+        0 => "lt0",
+        locktime if locktime < LOCKTIME_THRESHOLD => "lth",
+        _ => "ltt",
+    };
+    let rbf = !rbf_signaling_inputs(tx)?.is_empty();
+    let segwit = inputs
+        .iter()
+        .any(|input| input["witness"].as_array().is_some_and(|w| !w.is_empty()));
+    let input_types: Vec<&str> = inputs
+        .iter()
+        .map(|input| {
+            if input["is_coinbase"] == true {
+                "coinbase"
+            } else {
+                input["prevout"]["scriptpubkey_type"]
+                    .as_str()
+                    .unwrap_or("unknown")
+            }
+        })
+        .collect();
+    let output_shapes: Vec<String> = outputs
+        .iter()
+        .map(|output| {
+            format!(
+                "{}:{}",
+                output["scriptpubkey_type"].as_str().unwrap_or("unknown"),
+                value_bucket(output["value"].as_u64().unwrap_or(0))
+            )
+        })
+        .collect();
+    let mut parts = vec![format!("v{version}"), locktime.to_string()];
+    if rbf {
+        parts.push("rbf".to_string());
+    }
+    if segwit {
+        parts.push("sw".to_string());
+    }
+    parts.push(format!("i{}[{}]", inputs.len(), input_types.join(",")));
+    parts.push(format!("o{}[{}]", outputs.len(), output_shapes.join(",")));
+    let components = json!({
+        "version": version,
+        "locktime": locktime,
+        "rbf": rbf,
+        "segwit": segwit,
+        "input_count": inputs.len(),
+        "input_types": input_types,
+        "output_count": outputs.len(),
+        "outputs": output_shapes,
+    });
+    Ok((parts.join("-"), components))
+}
+
+fn fetch_tx_fingerprint(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let (fingerprint, components) = tx_fingerprint(&tx)?;
+    let hash = sha256::Hash::hash(fingerprint.as_bytes()).to_string();
+    let result = json!({
+        "txid": txid,
+        "fingerprint": fingerprint,
+        "fingerprint_hash": &hash[..16],
+        "components": components,
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_tx_raw(client: &dyn EsploraClient, base_url: &str, txid: &str) -> Result<String, String> {
+    let raw = fetch_esplora_bytes(client, &format!("{base_url}/tx/{txid}/raw"))?;
+    Ok(hex_encode(&raw))
+}
+
+// The transaction as an unsigned PSBT, with the prevouts of segwit inputs
+// taken from the Esplora JSON since the raw transaction doesn't include them
+fn tx_to_psbt(client: &dyn EsploraClient, base_url: &str, txid: &str) -> Result<String, String> {
+    let mut tx = decode_tx(&fetch_esplora(
+        client,
+        &format!("{base_url}/tx/{txid}/hex"),
+    )?)?;
+    let decoded = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    for input in &mut tx.input {
+        input.script_sig = bitcoin::ScriptBuf::new();
+        input.witness = bitcoin::Witness::new();
+    }
+    let mut psbt = bitcoin::Psbt::from_unsigned_tx(tx)
+        .map_err(|e| format!("Cannot build a PSBT from transaction {txid}: {e}"))?;
+    let mut witness_utxos = 0;
+    for (input, vin) in psbt
+        .inputs
+        .iter_mut()
+        .zip(decoded["vin"].as_array().into_iter().flatten())
+    {
+        let prevout = &vin["prevout"];
+        let is_segwit = prevout["scriptpubkey_type"]
+            .as_str()
+            .is_some_and(|t| t.starts_with("v0_") || t.starts_with("v1_"));
+        let (Some(value), Some(script)) =
+            (prevout["value"].as_u64(), prevout["scriptpubkey"].as_str())
+        else {
+            continue;
+        };
+        if is_segwit {
+            input.witness_utxo = Some(bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(value),
+                script_pubkey: bitcoin::ScriptBuf::from_bytes(hex_decode(script)?),
+            });
+            witness_utxos += 1;
+        }
+    }
+    let result = json!({
+        "txid": txid,
+        "psbt": psbt.to_string(),
+        "inputs": psbt.inputs.len(),
+        "outputs": psbt.outputs.len(),
+        "inputs_with_witness_utxo": witness_utxos,
+    });
+    Ok(result.to_string())
+}
+
+// Pages of block transactions sampled to estimate the block's median fee rate
+const FEE_SAMPLE_PAGES: usize = 4;
+
+// Fee rate in sat/vB of an Esplora transaction, None for the coinbase
+pub(super) fn tx_feerate(tx: &Value) -> Option<f64> {
+    let fee = tx["fee"].as_u64().filter(|fee| *fee > 0)?;
+    let weight = tx["weight"].as_u64().filter(|weight| *weight > 0)?;
+    Some(fee as f64 / weight.div_ceil(4) as f64)
+}
+
+// Transactions of a block, along with its tx_count, sampled as by
+// sample_block_txs
+pub(super) fn fetch_block_fee_sample(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    block_hash: &str,
+) -> Result<(Vec<Value>, usize), String> {
+    let block = fetch_json(client, &format!("{base_url}/block/{block_hash}"))?;
+    let tx_count = block["tx_count"]
+        .as_u64()
+        .ok_or("Block response is missing tx_count")? as usize;
+    let sample = sample_block_txs(client, base_url, block_hash, tx_count)?;
+    Ok((sample, tx_count))
+}
+
+// Transactions of up to FEE_SAMPLE_PAGES pages of a block of `tx_count`
+// transactions; the sample is the whole block if it has few enough pages
+pub(super) fn sample_block_txs(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    block_hash: &str,
+    tx_count: usize,
+) -> Result<Vec<Value>, String> {
+    // Pages evenly spread across the block, as fee rates tend to decrease
+    // from its first transactions to its last ones
+    let page_count = tx_count.div_ceil(BLOCK_TXS_PAGE_SIZE);
+    let sampled_pages = page_count.min(FEE_SAMPLE_PAGES);
+    let mut sample = Vec::new();
+    for i in 0..sampled_pages {
+        let start_index = i * page_count / sampled_pages * BLOCK_TXS_PAGE_SIZE;
+        let txs = fetch_json(
+            client,
+            &format!("{base_url}/block/{block_hash}/txs/{start_index}"),
+        )?;
+        match txs {
+            Value::Array(txs) => sample.extend(txs),
+            _ => return Err("Block txs response is not an array".to_string()),
+        }
+    }
+    Ok(sample)
+}
+
+fn fetch_tx_fee_context(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    if tx["status"]["confirmed"] != true {
+        return Err(format!(
+            "Transaction {txid} is not confirmed yet, there is no block to compare it to"
+        ));
+    }
+    let feerate = tx_feerate(&tx).ok_or("Transaction has no fee, it may be a coinbase")?;
+    let block_hash = tx["status"]["block_hash"]
+        .as_str()
+        .ok_or("Transaction status is missing block_hash")?;
+    let (txs, _) = fetch_block_fee_sample(client, base_url, block_hash)?;
+    let mut sample: Vec<f64> = txs.iter().filter_map(tx_feerate).collect();
+    if sample.is_empty() {
+        return Err(format!(
+            "Block {block_hash} has no other fee-paying transaction to compare to"
+        ));
+    }
+    sample.sort_by(f64::total_cmp);
+    let median = if sample.len() % 2 == 0 {
+        (sample[sample.len() / 2 - 1] + sample[sample.len() / 2]) / 2.0
+    } else {
+        sample[sample.len() / 2]
+    };
+    let paying_less = sample.iter().filter(|rate| **rate < feerate).count();
+    let result = json!({
+        "txid": txid,
+        "fee_sat": tx["fee"],
+        "vsize": tx["weight"].as_u64().map(|weight| weight.div_ceil(4)),
+        "feerate_sat_vb": feerate,
+        "block_hash": block_hash,
+        "block_height": tx["status"]["block_height"],
+        "sample_size": sample.len(),
+        "block_median_feerate_sat_vb": median,
+        "percentile": (paying_less * 100 / sample.len()),
+        "above_median": feerate > median,
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_tx_fee_rank(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    if tx["status"]["confirmed"] != true {
+        return Err(format!(
+            "Transaction {txid} is not confirmed yet, there is no block to rank it in"
+        ));
+    }
+    let feerate = tx_feerate(&tx).ok_or("Transaction has no fee, it may be a coinbase")?;
+    let block_hash = tx["status"]["block_hash"]
+        .as_str()
+        .ok_or("Transaction status is missing block_hash")?;
+    let (txs, tx_count) = fetch_block_fee_sample(client, base_url, block_hash)?;
+    let complete = txs.len() >= tx_count;
+    // The transaction itself is left out when its page is sampled
+    let mut sample: Vec<f64> = txs
+        .iter()
+        .filter(|other| other["txid"].as_str() != Some(txid))
+        .filter_map(tx_feerate)
+        .collect();
+    if sample.is_empty() {
+        return Err(format!(
+            "Block {block_hash} has no other fee-paying transaction to rank against"
+        ));
+    }
+    sample.sort_by(f64::total_cmp);
+    let paying_less = sample.partition_point(|rate| *rate < feerate);
+    let paying_more = sample.len() - sample.partition_point(|rate| *rate <= feerate);
+    let mut result = json!({
+        "txid": txid,
+        "feerate_sat_vb": feerate,
+        "block_hash": block_hash,
+        "block_height": tx["status"]["block_height"],
+        "block_tx_count": tx_count,
+        "sample_size": sample.len(),
+        "complete": complete,
+        "rank": paying_more + 1,
+        "paying_more": paying_more,
+        "paying_less": paying_less,
+        "paying_same": sample.len() - paying_less - paying_more,
+        "percentile": (paying_less * 100 / sample.len()),
+        "min_feerate_sat_vb": sample[0],
+        "max_feerate_sat_vb": sample[sample.len() - 1],
+    });
+    if !complete {
+        result["note"] = json!(format!(
+            "The block has {tx_count} transactions, only {} spread across it were sampled, so the rank is an estimate",
+            txs.len()
+        ));
+    }
+    Ok(result.to_string())
+}
+
+// A coinbase has a single input spending the null prevout
+fn is_coinbase_tx(tx: &Value) -> bool {
+    match tx["vin"].as_array().map(Vec::as_slice) {
+        Some([input]) => {
+            input["is_coinbase"] == true
+                || (input["txid"]
+                    .as_str()
+                    .is_some_and(|txid| txid.len() == 64 && txid.bytes().all(|b| b == b'0'))
+                    && input["vout"] == 0xffff_ffffu64)
+        }
+        _ => false,
+    }
+}
+
+fn fetch_tx_is_coinbase(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let is_coinbase = is_coinbase_tx(&tx);
+    let mut result = json!({ "txid": txid, "is_coinbase": is_coinbase });
+    if is_coinbase {
+        result["block_height"] = tx["status"]["block_height"].clone();
+    }
+    Ok(result.to_string())
+}
+
+// Confirmations a coinbase output needs before it can be spent
+const COINBASE_MATURITY: u64 = 100;
+
+fn fetch_coinbase_maturity(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    if !is_coinbase_tx(&tx) {
+        return Err(format!("Transaction {txid} is not a coinbase"));
+    }
+    // A coinbase is only ever seen confirmed, unless its block was reorged out
+    let Some(height) = tx["status"]["block_height"].as_u64() else {
+        return Ok(json!({
+            "txid": txid,
+            "mature": false,
+            "confirmations": 0,
+            "confirmations_remaining": COINBASE_MATURITY,
+            "block_height": null,
+            "spendable_at_height": null,
+        })
+        .to_string());
+    };
+    let tip_height: u64 = fetch_tip_height(client, base_url)?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to parse tip height: {e}"))?;
+    let confirmations = (tip_height + 1).saturating_sub(height);
+    let result = json!({
+        "txid": txid,
+        "mature": confirmations >= COINBASE_MATURITY,
+        "confirmations": confirmations,
+        "confirmations_remaining": COINBASE_MATURITY.saturating_sub(confirmations),
+        "block_height": height,
+        "spendable_at_height": height + COINBASE_MATURITY,
+    });
+    Ok(result.to_string())
+}
+
+// BIP125: a transaction is replaceable if any input has a sequence number below this
+const BIP125_MAX_SEQUENCE: u64 = 0xffff_fffe;
+
+// BIP125 rule 4: a replacement pays for its own relay at this rate on top of
+// the original fee
+const INCREMENTAL_RELAY_FEERATE: f64 = 1.0;
+
+// Indexes of the inputs signaling BIP125
+fn rbf_signaling_inputs(tx: &Value) -> Result<Vec<usize>, String> {
+    let inputs = tx["vin"].as_array().ok_or("Transaction has no vin array")?;
+    Ok(inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| {
+            input["sequence"]
+                .as_u64()
+                .is_some_and(|sequence| sequence < BIP125_MAX_SEQUENCE)
+        })
+        .map(|(vin, _)| vin)
+        .collect())
+}
+
+fn fetch_tx_rbf_status(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let signaling = rbf_signaling_inputs(&tx)?;
+    let result = json!({
+        "txid": txid,
+        "rbf": !signaling.is_empty(),
+        "signaling_inputs": signaling,
+        "confirmed": tx["status"]["confirmed"].as_bool().unwrap_or(false),
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_tx_finality(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    min_confirmations: u64,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let rbf_signaling = !rbf_signaling_inputs(&tx)?.is_empty();
+    let confirmations = match tx["status"]["block_height"].as_u64() {
+        Some(height) if tx["status"]["confirmed"] == true => {
+            let tip_height: u64 = fetch_tip_height(client, base_url)?
+                .trim()
+                .parse()
+                .map_err(|e| format!("Failed to parse tip height: {e}"))?;
+            (tip_height + 1).saturating_sub(height)
+        }
+        _ => 0,
+    };
+    let confirmed = confirmations > 0;
+    let is_final = confirmations >= min_confirmations;
+    let reason = if is_final {
+        format!("Confirmed {confirmations} times, at least the {min_confirmations} required")
+    } else if confirmed {
+        format!("Confirmed only {confirmations} of the {min_confirmations} times required")
+    } else if rbf_signaling {
+        "Unconfirmed and signaling replace-by-fee, it can be replaced until it confirms".to_string()
+    } else {
+        "Unconfirmed, it can still be replaced or dropped until it confirms".to_string()
+    };
+    let result = json!({
+        "txid": txid,
+        "final": is_final,
+        "confirmed": confirmed,
+        "confirmations": confirmations,
+        "min_confirmations": min_confirmations,
+        "confirmations_remaining": min_confirmations.saturating_sub(confirmations),
+        "rbf_signaling": rbf_signaling,
+        "replaceable": !confirmed && rbf_signaling,
+        "reason": reason,
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_rbf_bump_feerate(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    target_blocks: u32,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    if tx["status"]["confirmed"] == true {
+        return Err(format!(
+            "Transaction {txid} is already confirmed, it can no longer be replaced"
+        ));
+    }
+    if rbf_signaling_inputs(&tx)?.is_empty() {
+        return Err(format!(
+            "Transaction {txid} doesn't signal BIP125 replace-by-fee"
+        ));
+    }
+    let fee = tx["fee"].as_u64().ok_or("Transaction has no fee")?;
+    let vsize = tx["weight"]
+        .as_u64()
+        .filter(|weight| *weight > 0)
+        .ok_or("Transaction has no weight")?
+        .div_ceil(4);
+    let feerate = fee as f64 / vsize as f64;
+    let (target, estimate) = fetch_target_feerate(client, base_url, target_blocks)?;
+    let suggested = estimate.max(feerate + INCREMENTAL_RELAY_FEERATE);
+    let new_fee = ((suggested * vsize as f64).ceil() as u64)
+        .max(fee + (INCREMENTAL_RELAY_FEERATE * vsize as f64).ceil() as u64);
+    let result = json!({
+        "txid": txid,
+        "vsize": vsize,
+        "fee": fee,
+        "feerate_sat_vb": (feerate * 100.0).round() / 100.0,
+        "target": target,
+        "estimate_sat_vb": estimate,
+        "suggested_feerate_sat_vb": (suggested * 100.0).round() / 100.0,
+        "suggested_fee": new_fee,
+        "extra_fee": new_fee - fee,
+    });
+    Ok(result.to_string())
+}
+
+// Fetch urls concurrently, results in the same order
+fn fetch_json_all(client: &dyn EsploraClient, urls: &[String]) -> Vec<Result<Value, String>> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = urls
+            .iter()
+            .map(|url| {
+                let span = tracing::Span::current();
+                scope.spawn(move || {
+                    let _span = span.entered();
+                    fetch_json(client, url)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()))
+            })
+            .collect()
+    })
+}
+
+// The other spenders of the outputs spent by a transaction: a spender
+// differing from the transaction itself is a conflict, i.e. a replacement
+// when the transaction is unconfirmed
+fn fetch_rbf_chain(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    max_lookups: usize,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let inputs = tx["vin"].as_array().ok_or("Transaction has no vin array")?;
+    let outpoints: Vec<(String, u64)> = if is_coinbase_tx(&tx) {
+        Vec::new()
+    } else {
+        inputs
+            .iter()
+            .filter_map(|input| {
+                Some((input["txid"].as_str()?.to_string(), input["vout"].as_u64()?))
+            })
+            .collect()
+    };
+    let examined = &outpoints[..outpoints.len().min(max_lookups)];
+    let urls: Vec<String> = examined
+        .iter()
+        .map(|(prev_txid, vout)| format!("{base_url}/tx/{prev_txid}/outspend/{vout}"))
+        .collect();
+    let outspends = fetch_json_all(client, &urls);
+
+    // Conflicting txids, in the order they are first seen, with the inputs
+    // they share
+    let mut conflicts: Vec<(String, Vec<Value>)> = Vec::new();
+    let mut unspent_inputs = Vec::new();
+    for (vin, outspend) in outspends.into_iter().enumerate() {
+        let outspend = outspend?;
+        let (prev_txid, vout) = &examined[vin];
+        let shared = json!({ "vin": vin, "outpoint": format!("{prev_txid}:{vout}") });
+        match outspend["txid"].as_str() {
+            _ if outspend["spent"] != true => unspent_inputs.push(vin),
+            Some(spender) if spender != txid => {
+                match conflicts
+                    .iter_mut()
+                    .find(|(conflict, _)| conflict == spender)
+                {
+                    Some((_, shared_inputs)) => shared_inputs.push(shared),
+                    None => conflicts.push((spender.to_string(), vec![shared])),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let urls: Vec<String> = conflicts
+        .iter()
+        .map(|(conflict, _)| format!("{base_url}/tx/{conflict}"))
+        .collect();
+    let round = |feerate: f64| (feerate * 100.0).round() / 100.0;
+    let mut candidates = Vec::new();
+    for ((conflict, shared_inputs), conflict_tx) in
+        conflicts.into_iter().zip(fetch_json_all(client, &urls))
+    {
+        let conflict_tx = conflict_tx?;
+        candidates.push(json!({
+            "txid": conflict,
+            "shared_inputs": shared_inputs,
+            "confirmed": conflict_tx["status"]["confirmed"].as_bool().unwrap_or(false),
+            "rbf": !rbf_signaling_inputs(&conflict_tx)?.is_empty(),
+            "fee": conflict_tx["fee"],
+            "vsize": conflict_tx["weight"].as_u64().map(|weight| weight.div_ceil(4)),
+            "feerate_sat_vb": tx_feerate(&conflict_tx).map(round),
+        }));
+    }
+    let result = json!({
+        "txid": txid,
+        "confirmed": tx["status"]["confirmed"].as_bool().unwrap_or(false),
+        "rbf": !rbf_signaling_inputs(&tx)?.is_empty(),
+        "fee": tx["fee"],
+        "feerate_sat_vb": tx_feerate(&tx).map(round),
+        "inputs_examined": examined.len(),
+        "truncated": examined.len() < outpoints.len(),
+        "unspent_inputs": unspent_inputs,
+        "replaced": !candidates.is_empty(),
+        "candidates": candidates,
+        "note": "Heuristic: Esplora doesn't track replacements, only the current spender of each input is seen",
+    });
+    Ok(result.to_string())
+}
+
+// Minimum fee rate for the child to be relayed at all
+const MIN_RELAY_FEERATE: f64 = 1.0;
+
+fn fetch_cpfp_feerate(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    target_feerate: f64,
+    child_vsize: u64,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    if tx["status"]["confirmed"] == true {
+        return Err(format!(
+            "Transaction {txid} is already confirmed, there is nothing to bump"
+        ));
+    }
+    let fee = tx["fee"].as_u64().ok_or("Transaction has no fee")?;
+    let vsize = tx["weight"]
+        .as_u64()
+        .filter(|weight| *weight > 0)
+        .ok_or("Transaction has no weight")?
+        .div_ceil(4);
+    let package_fee = (target_feerate * (vsize + child_vsize) as f64).ceil() as u64;
+    let child_fee = package_fee
+        .saturating_sub(fee)
+        .max((MIN_RELAY_FEERATE * child_vsize as f64).ceil() as u64);
+    let round = |feerate: f64| (feerate * 100.0).round() / 100.0;
+    let result = json!({
+        "txid": txid,
+        "parent": {
+            "fee": fee,
+            "vsize": vsize,
+            "feerate_sat_vb": round(fee as f64 / vsize as f64),
+        },
+        "child_vsize": child_vsize,
+        "child_fee": child_fee,
+        "child_feerate_sat_vb": round(child_fee as f64 / child_vsize as f64),
+        "package_feerate_sat_vb": round((fee + child_fee) as f64 / (vsize + child_vsize) as f64),
+        "target_feerate_sat_vb": target_feerate,
+    });
+    Ok(result.to_string())
+}
+
+// Count, total fee, and total vsize of the related transactions listed by a
+// /v1/cpfp response
+fn cpfp_package(txs: &Value) -> (usize, u64, u64) {
+    let txs = txs.as_array().map(Vec::as_slice).unwrap_or_default();
+    let fee = txs.iter().filter_map(|tx| tx["fee"].as_u64()).sum();
+    let vsize = txs
+        .iter()
+        .filter_map(|tx| tx["weight"].as_u64())
+        .map(|weight| weight.div_ceil(4))
+        .sum();
+    (txs.len(), fee, vsize)
+}
+
+fn fetch_tx_mempool_info(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    if tx["status"]["confirmed"] == true {
+        return Err(format!(
+            "Transaction {txid} is already confirmed, it is no longer in the mempool"
+        ));
+    }
+    let vsize = tx["weight"].as_u64().map(|weight| weight.div_ceil(4));
+    let fee = tx["fee"].as_u64();
+    let feerate = fee
+        .zip(vsize.filter(|vsize| *vsize > 0))
+        .map(|(fee, vsize)| (fee as f64 / vsize as f64 * 100.0).round() / 100.0);
+    let cpfp_path = format!("/v1/cpfp/{txid}");
+    // Plain Esplora has no such endpoint, its absence is not an error
+    let mempool = match fetch_json(client, &format!("{base_url}{cpfp_path}")) {
+        Ok(cpfp) if cpfp.is_object() => {
+            let (ancestor_count, ancestor_fee, ancestor_vsize) = cpfp_package(&cpfp["ancestors"]);
+            let (descendant_count, descendant_fee, descendant_vsize) =
+                cpfp_package(&cpfp["descendants"]);
+            json!({
+                "source": cpfp_path,
+                "available": true,
+                "ancestor_count": ancestor_count,
+                "ancestor_fee": ancestor_fee,
+                "ancestor_vsize": ancestor_vsize,
+                "descendant_count": descendant_count,
+                "descendant_fee": descendant_fee,
+                "descendant_vsize": descendant_vsize,
+                "effective_feerate_sat_vb": cpfp["effectiveFeePerVsize"],
+            })
+        }
+        Ok(_) => json!({
+            "source": cpfp_path,
+            "available": false,
+            "reason": "Unexpected response from the backend",
+        }),
+        Err(e) => json!({ "source": cpfp_path, "available": false, "reason": e }),
+    };
+    let result = json!({
+        "txid": txid,
+        "tx": {
+            "source": format!("/tx/{txid}"),
+            "vsize": vsize,
+            "fee": fee,
+            "feerate_sat_vb": feerate,
+        },
+        "mempool": mempool,
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_tx_dust(client: &dyn EsploraClient, base_url: &str, txid: &str) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let mut dust = Vec::new();
+    for (vout, output) in outputs.iter().enumerate() {
+        let script = output["scriptpubkey"]
+            .as_str()
+            .ok_or("Output has no scriptpubkey")?;
+        let script = bitcoin::ScriptBuf::from_bytes(hex_decode(script)?);
+        let threshold = script.minimal_non_dust().to_sat();
+        let value = output["value"].as_u64().unwrap_or(0);
+        if value < threshold {
+            dust.push(json!({
+                "vout": vout,
+                "value": value,
+                "scriptpubkey_type": output["scriptpubkey_type"],
+                "dust_threshold_sat": threshold,
+            }));
+        }
+    }
+    let result = json!({
+        "txid": txid,
+        "output_count": outputs.len(),
+        "dust_count": dust.len(),
+        "dust_outputs": dust,
+    });
+    Ok(result.to_string())
+}
+
+fn decode_tx(hex: &str) -> Result<bitcoin::Transaction, String> {
+    let bytes = hex_decode(hex.trim()).map_err(|e| format!("Invalid transaction hex: {e}"))?;
+    bitcoin::consensus::deserialize(&bytes).map_err(|e| format!("Invalid transaction: {e}"))
+}
+
+fn tx_size(hex: &str) -> Result<String, String> {
+    let tx = decode_tx(hex)?;
+    let result = json!({
+        "txid": tx.compute_txid().to_string(),
+        "segwit": tx.input.iter().any(|input| !input.witness.is_empty()),
+        "size": tx.total_size(),
+        "base_size": tx.base_size(),
+        "weight": tx.weight().to_wu(),
+        "vsize": tx.vsize(),
+    });
+    Ok(result.to_string())
+}
+
+// Largest weight Bitcoin Core relays (MAX_STANDARD_TX_WEIGHT)
+const MAX_STANDARD_TX_WEIGHT: u64 = 400_000;
+
+fn validation_check(name: &str, passed: bool, detail: String) -> Value {
+    json!({ "check": name, "passed": passed, "detail": detail })
+}
+
+// Checks that can be made on the transaction alone
+fn structural_checks(tx: &bitcoin::Transaction) -> Vec<Value> {
+    let mut outpoints = std::collections::HashSet::new();
+    let duplicate = tx
+        .input
+        .iter()
+        .find(|input| !outpoints.insert(input.previous_output));
+    let total_out = tx
+        .output
+        .iter()
+        .try_fold(bitcoin::Amount::ZERO, |total, output| {
+            total.checked_add(output.value)
+        })
+        .filter(|total| *total <= bitcoin::Amount::MAX_MONEY);
+    let weight = tx.weight().to_wu();
+    vec![
+        validation_check(
+            "has_inputs",
+            !tx.input.is_empty(),
+            plural(tx.input.len(), "input"),
+        ),
+        validation_check(
+            "has_outputs",
+            !tx.output.is_empty(),
+            plural(tx.output.len(), "output"),
+        ),
+        validation_check(
+            "not_coinbase",
+            !tx.is_coinbase(),
+            "Coinbase transactions can only be created by miners".to_string(),
+        ),
+        validation_check(
+            "no_duplicate_inputs",
+            duplicate.is_none(),
+            match duplicate {
+                Some(input) => format!("{} is spent more than once", input.previous_output),
+                None => "Every input spends a different output".to_string(),
+            },
+        ),
+        validation_check(
+            "output_values",
+            total_out.is_some(),
+            match total_out {
+                Some(total) => format!("Outputs total {} sat", total.to_sat()),
+                None => "Outputs total more than 21 million BTC".to_string(),
+            },
+        ),
+        validation_check(
+            "standard_weight",
+            weight <= MAX_STANDARD_TX_WEIGHT,
+            format!("Weight {weight} WU, the standard maximum is {MAX_STANDARD_TX_WEIGHT} WU"),
+        ),
+    ]
+}
+
+// Whether each spent output exists and is unspent, and the fee if they all do
+fn prevout_checks(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    tx: &bitcoin::Transaction,
+) -> Result<Vec<Value>, String> {
+    let mut checks = Vec::new();
+    let mut prev_txs: HashMap<bitcoin::Txid, Option<Value>> = HashMap::new();
+    let mut total_in = Some(0u64);
+    for (index, input) in tx.input.iter().enumerate() {
+        let outpoint = input.previous_output;
+        let prev_tx = match prev_txs.get(&outpoint.txid) {
+            Some(prev_tx) => prev_tx.clone(),
+            None => {
+                let prev_tx = match fetch_json(client, &format!("{base_url}/tx/{}", outpoint.txid))
+                {
+                    Ok(prev_tx) => Some(prev_tx),
+                    Err(e) if is_not_found(&e) => None,
+                    Err(e) => return Err(e),
+                };
+                prev_txs.insert(outpoint.txid, prev_tx.clone());
+                prev_tx
+            }
+        };
+        let value = prev_tx
+            .as_ref()
+            .and_then(|prev_tx| prev_tx["vout"][outpoint.vout as usize]["value"].as_u64());
+        let name = format!("input_{index}_unspent");
+        let Some(value) = value else {
+            checks.push(validation_check(
+                &name,
+                false,
+                format!("Output {outpoint} does not exist"),
+            ));
+            total_in = None;
+            continue;
+        };
+        total_in = total_in.map(|total| total + value);
+        let outspend = fetch_json(
+            client,
+            &format!("{base_url}/tx/{}/outspend/{}", outpoint.txid, outpoint.vout),
+        )?;
+        checks.push(if outspend["spent"] == true {
+            validation_check(
+                &name,
+                false,
+                format!(
+                    "Output {outpoint} is already spent by {}",
+                    outspend["txid"].as_str().unwrap_or("another transaction")
+                ),
+            )
+        } else {
+            validation_check(&name, true, format!("Output {outpoint} is unspent"))
+        });
+    }
+    if let Some(total_in) = total_in {
+        let total_out = tx.output.iter().try_fold(0u64, |total, output| {
+            total.checked_add(output.value.to_sat())
+        });
+        checks.push(match total_out {
+            Some(total_out) if total_in >= total_out => validation_check(
+                "inputs_cover_outputs",
+                true,
+                format!("Fee {} sat", total_in - total_out),
+            ),
+            Some(total_out) => validation_check(
+                "inputs_cover_outputs",
+                false,
+                format!("Outputs total {total_out} sat but inputs only {total_in} sat"),
+            ),
+            None => validation_check(
+                "inputs_cover_outputs",
+                false,
+                format!("Outputs total more than the {total_in} sat of the inputs"),
+            ),
+        });
+    }
+    Ok(checks)
+}
+
+fn validate_tx(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hex: &str,
+    check_prevouts: bool,
+) -> Result<String, String> {
+    let tx = match decode_tx(hex) {
+        Ok(tx) => tx,
+        Err(e) => {
+            let checks = [validation_check("decodes", false, e)];
+            return Ok(json!({ "valid": false, "checks": checks }).to_string());
+        }
+    };
+    let txid = tx.compute_txid().to_string();
+    let mut checks = vec![validation_check("decodes", true, format!("txid {txid}"))];
+    checks.extend(structural_checks(&tx));
+    if check_prevouts && !tx.is_coinbase() {
+        checks.extend(prevout_checks(client, base_url, &tx)?);
+    }
+    let result = json!({
+        "txid": txid,
+        "valid": checks.iter().all(|check| check["passed"] == true),
+        "checks": checks,
+    });
+    Ok(result.to_string())
+}
+
+// Reject reasons of Bitcoin Core meaning the node already has the transaction
+const ALREADY_KNOWN_REASONS: [&str; 4] = [
+    "txn-already-in-mempool",
+    "txn-already-known",
+    "transaction already in block chain",
+    "transaction outputs already in utxo set",
+];
+
+// Categories of reject reasons, matched in order against the lowercased
+// reason, for an agent to decide what to fix
+const REJECT_CATEGORIES: [(&str, &str); 15] = [
+    ("missingorspent", "inputs_missing_or_spent"),
+    ("missing-inputs", "inputs_missing_or_spent"),
+    ("txn-mempool-conflict", "mempool_conflict"),
+    ("insufficient fee", "insufficient_fee"),
+    ("min relay fee not met", "insufficient_fee"),
+    ("mempool min fee not met", "insufficient_fee"),
+    ("script-verify-flag", "invalid_script"),
+    ("dust", "dust"),
+    ("non-final", "non_final"),
+    ("non-bip68-final", "non_final"),
+    ("too-long-mempool-chain", "mempool_chain_limit"),
+    ("tx-size", "non_standard"),
+    ("scriptpubkey", "non_standard"),
+    ("version", "non_standard"),
+    ("bad-txns", "invalid"),
+];
+
+fn reject_category(reason: &str) -> &'static str {
+    let lowercase = reason.to_lowercase();
+    REJECT_CATEGORIES
+        .iter()
+        .find(|(pattern, _)| lowercase.contains(pattern))
+        .map_or("other", |(_, category)| category)
+}
+
+// The reason a node refused a transaction, from the body of a failed POST
+// /tx, e.g. `sendrawtransaction RPC error: {"code":-26,"message":"..."}`
+fn reject_reason(err: &str) -> String {
+    let body = err
+        .split_once("status code ")
+        .and_then(|(_, rest)| rest.split_once(": "))
+        .map_or(err, |(_, body)| body);
+    body.find('{')
+        .and_then(|start| serde_json::from_str::<Value>(&body[start..]).ok())
+        .and_then(|rpc_error| rpc_error["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string())
+}
+
+// Submit a transaction, treating a node that already has it as a success so
+// that retrying a broadcast is harmless
+fn broadcast_tx(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    hex: &str,
+    txid: &str,
+) -> Result<String, ErrorData> {
+    let already_known = match client.post(&format!("{base_url}/tx"), hex) {
+        Ok(_) => false,
+        Err(e) if status_from_error(&e).is_some_and(|status| status < 500) => {
+            let reason = reject_reason(&e);
+            let lowercase = reason.to_lowercase();
+            if !ALREADY_KNOWN_REASONS
+                .iter()
+                .any(|known| lowercase.contains(known))
+            {
+                let data = json!({
+                    "kind": "tx_rejected",
+                    "category": reject_category(&reason),
+                    "reason": reason,
+                });
+                return Err(ErrorData::invalid_request(
+                    format!("Transaction {txid} rejected: {reason}"),
+                    Some(data),
+                ));
+            }
+            true
+        }
+        Err(e) => return Err(upstream_error(e)),
+    };
+    Ok(json!({ "txid": txid, "already_known": already_known }).to_string())
+}
+
+// Total of the outputs paying `address`, which may be split across several outputs
+fn verify_payment(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    address: &str,
+    min_amount_sat: u64,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let paying: Vec<(usize, u64)> = outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, output)| output["scriptpubkey_address"] == address)
+        .map(|(vout, output)| (vout, output["value"].as_u64().unwrap_or(0)))
+        .collect();
+    let amount_sat: u64 = paying.iter().map(|(_, value)| value).sum();
+    let mut result = json!({
+        "txid": txid,
+        "address": address,
+        "paid": !paying.is_empty() && amount_sat >= min_amount_sat,
+        "amount_sat": amount_sat,
+        "vout": paying.iter().map(|(vout, _)| vout).collect::<Vec<_>>(),
+        "confirmed": tx["status"]["confirmed"].as_bool().unwrap_or(false),
+    });
+    if paying.is_empty() {
+        result["reason"] = json!(format!(
+            "No output of transaction {txid} pays address {address}"
+        ));
+    } else if amount_sat < min_amount_sat {
+        result["reason"] = json!(format!(
+            "Address {address} received {amount_sat} sat, less than the expected {min_amount_sat} sat"
+        ));
+    }
+    Ok(result.to_string())
+}
+
+// Outpoints spent by a transaction, as txid:vout
+fn tx_outpoints(tx: &Value) -> Vec<String> {
+    tx["vin"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|input| {
+            format!(
+                "{}:{}",
+                input["txid"].as_str().unwrap_or_default(),
+                input["vout"]
+            )
+        })
+        .collect()
+}
+
+// Value paid to each output script, with the address when it has one
+fn tx_outputs_by_script(tx: &Value) -> BTreeMap<String, (u64, Value)> {
+    let mut outputs = BTreeMap::new();
+    for output in tx["vout"].as_array().into_iter().flatten() {
+        let script = output["scriptpubkey"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let entry = outputs
+            .entry(script)
+            .or_insert((0, output["scriptpubkey_address"].clone()));
+        entry.0 += output["value"].as_u64().unwrap_or(0);
+    }
+    outputs
+}
+
+fn diff_txs(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid_a: &str,
+    txid_b: &str,
+) -> Result<String, String> {
+    let span = tracing::Span::current();
+    let fetch = |txid: &str| fetch_json(client, &format!("{base_url}/tx/{txid}"));
+    let (tx_a, tx_b) = std::thread::scope(|scope| {
+        let a = scope.spawn(|| span.in_scope(|| fetch(txid_a)));
+        let b = scope.spawn(|| span.in_scope(|| fetch(txid_b)));
+        let join = |handle: std::thread::ScopedJoinHandle<'_, _>| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err("Lookup thread panicked".to_string()))
+        };
+        (join(a), join(b))
+    });
+    let (tx_a, tx_b) = (tx_a?, tx_b?);
+
+    let inputs_a = tx_outpoints(&tx_a);
+    let inputs_b = tx_outpoints(&tx_b);
+    let shared: Vec<&String> = inputs_a.iter().filter(|i| inputs_b.contains(i)).collect();
+    let only = |inputs: &[String], other: &[String]| -> Vec<String> {
+        inputs
+            .iter()
+            .filter(|i| !other.contains(i))
+            .cloned()
+            .collect()
+    };
+
+    let outputs_a = tx_outputs_by_script(&tx_a);
+    let outputs_b = tx_outputs_by_script(&tx_b);
+    let output = |script: &str, address: &Value, value: u64| json!({ "scriptpubkey": script, "scriptpubkey_address": address, "value": value });
+    let mut changed = Vec::new();
+    let mut only_in_a = Vec::new();
+    for (script, (value_a, address)) in &outputs_a {
+        match outputs_b.get(script) {
+            Some((value_b, _)) if value_b != value_a => changed.push(json!({
+                "scriptpubkey": script,
+                "scriptpubkey_address": address,
+                "value_a": value_a,
+                "value_b": value_b,
+                "value_diff": *value_b as i64 - *value_a as i64,
+            })),
+            Some(_) => {}
+            None => only_in_a.push(output(script, address, *value_a)),
+        }
+    }
+    let only_in_b: Vec<Value> = outputs_b
+        .iter()
+        .filter(|(script, _)| !outputs_a.contains_key(*script))
+        .map(|(script, (value, address))| output(script, address, *value))
+        .collect();
+
+    let fee = |tx: &Value| tx["fee"].as_u64();
+    let feerate = |tx: &Value| tx_feerate(tx).map(|rate| (rate * 100.0).round() / 100.0);
+    let mut result = json!({
+        "txid_a": txid_a,
+        "txid_b": txid_b,
+        "related": !shared.is_empty(),
+        "inputs": {
+            "shared": shared,
+            "only_in_a": only(&inputs_a, &inputs_b),
+            "only_in_b": only(&inputs_b, &inputs_a),
+        },
+        "outputs": {
+            "changed": changed,
+            "only_in_a": only_in_a,
+            "only_in_b": only_in_b,
+        },
+        "fee_a": fee(&tx_a),
+        "fee_b": fee(&tx_b),
+        "fee_diff_sat": fee(&tx_a).zip(fee(&tx_b)).map(|(a, b)| b as i64 - a as i64),
+        "feerate_a": feerate(&tx_a),
+        "feerate_b": feerate(&tx_b),
+        "feerate_diff": feerate(&tx_a)
+            .zip(feerate(&tx_b))
+            .map(|(a, b)| ((b - a) * 100.0).round() / 100.0),
+    });
+    if shared.is_empty() {
+        result["note"] = json!(format!(
+            "Transactions {txid_a} and {txid_b} share no input, so neither replaces the other"
+        ));
+    }
+    Ok(result.to_string())
+}
+
+// Guess how a witness spends its output from the output type and the stack
+// shape, returning the spend type and the role of each stack item
+fn classify_witness(prevout_type: &str, witness: &[&str]) -> (&'static str, Vec<&'static str>) {
+    let item_len = |hex: &str| hex.len() / 2;
+    let inputs = |count: usize| vec!["script_input"; count];
+    match prevout_type {
+        "v0_p2wpkh" if witness.len() == 2 => ("p2wpkh", vec!["signature", "pubkey"]),
+        "p2sh" if witness.len() == 2 && item_len(witness[1]) == 33 => {
+            ("p2sh-p2wpkh", vec!["signature", "pubkey"])
+        }
+        "v0_p2wsh" | "p2sh" => {
+            let kind = if prevout_type == "p2sh" {
+                "p2sh-p2wsh"
+            } else {
+                "p2wsh"
+            };
+            let mut roles = inputs(witness.len() - 1);
+            roles.push("witness_script");
+            (kind, roles)
+        }
+        "v1_p2tr" => {
+            // BIP341: with two or more items, a last one starting with 0x50 is the annex
+            let annex = witness.len() >= 2 && witness[witness.len() - 1].starts_with("50");
+            let stack = witness.len() - usize::from(annex);
+            let (kind, mut roles) = if stack == 1 {
+                ("taproot_keypath", vec!["signature"])
+            } else {
+                let mut roles = inputs(stack - 2);
+                roles.extend(["leaf_script", "control_block"]);
+                ("taproot_scriptpath", roles)
+            };
+            if annex {
+                roles.push("annex");
+            }
+            (kind, roles)
+        }
+        _ => ("unknown", vec!["unknown"; witness.len()]),
+    }
+}
+
+// A BIP341 script-path spend: the leaf script, the control block proving
+// it is committed to by the output key, and the script inputs before them
+fn taproot_script_path(witness: &Witness, output_key: Option<XOnlyPublicKey>) -> Value {
+    let (Some(leaf), Some(control_block)) = (
+        witness.taproot_leaf_script(),
+        witness.taproot_control_block(),
+    ) else {
+        return json!({ "error": "Invalid control block size" });
+    };
+    let annex = usize::from(witness.taproot_annex().is_some());
+    let script_inputs: Vec<String> = witness
+        .iter()
+        .take(witness.len() - 2 - annex)
+        .map(hex_encode)
+        .collect();
+    let mut detail = json!({
+        "leaf_script": hex_encode(leaf.script.as_bytes()),
+        "leaf_script_asm": leaf.script.to_asm_string(),
+        "leaf_version": format!("0x{:02x}", leaf.version.to_consensus()),
+        "tapscript": leaf.version == LeafVersion::TapScript,
+        "tapleaf_hash": TapLeafHash::from_script(leaf.script, leaf.version).to_string(),
+        "script_inputs": script_inputs,
+        "control_block": hex_encode(control_block),
+    });
+    match ControlBlock::decode(control_block) {
+        Ok(decoded) => {
+            let merkle_path: Vec<String> = decoded
+                .merkle_branch
+                .as_slice()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            detail["internal_key"] = json!(decoded.internal_key.to_string());
+            detail["output_key_parity"] = json!(match decoded.output_key_parity {
+                Parity::Even => "even",
+                Parity::Odd => "odd",
+            });
+            detail["merkle_depth"] = json!(merkle_path.len());
+            detail["merkle_path"] = json!(merkle_path);
+            detail["commitment_valid"] = json!(output_key.map(|output_key| {
+                decoded.verify_taproot_commitment(
+                    &Secp256k1::verification_only(),
+                    output_key,
+                    leaf.script,
+                )
+            }));
+        }
+        Err(e) => detail["control_block_error"] = json!(e.to_string()),
+    }
+    detail
+}
+
+fn fetch_tx_taproot_detail(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    vin: u32,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let input = tx["vin"]
+        .get(vin as usize)
+        .ok_or_else(|| format!("Transaction {txid} has no input {vin}"))?;
+    let prevout_type = input["prevout"]["scriptpubkey_type"].as_str();
+    if prevout_type != Some("v1_p2tr") {
+        let spent = if input["is_coinbase"] == true {
+            "it is a coinbase input".to_string()
+        } else {
+            format!("it spends a {} output", prevout_type.unwrap_or("unknown"))
+        };
+        let result = json!({
+            "txid": txid,
+            "vin": vin,
+            "taproot": false,
+            "prevout_type": prevout_type,
+            "note": format!("Input {vin} is not a taproot input, {spent}"),
+        });
+        return Ok(result.to_string());
+    }
+    let items = input["witness"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| hex_decode(item.as_str().unwrap_or_default()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid witness item: {e}"))?;
+    if items.is_empty() {
+        return Err(format!("Taproot input {vin} has no witness"));
+    }
+    let witness = Witness::from_slice(&items);
+    // The x-only key of OP_1 <32 bytes>
+    let output_key = input["prevout"]["scriptpubkey"]
+        .as_str()
+        .and_then(|hex| hex_decode(hex).ok())
+        .filter(|script| script.len() == 34)
+        .and_then(|script| XOnlyPublicKey::from_slice(&script[2..]).ok());
+    let annex = witness.taproot_annex();
+    let mut result = json!({
+        "txid": txid,
+        "vin": vin,
+        "taproot": true,
+        "has_annex": annex.is_some(),
+        "annex": annex.map(hex_encode),
+    });
+    if witness.len() - usize::from(annex.is_some()) == 1 {
+        let signature = &witness[0];
+        // A 64-byte signature implies SIGHASH_DEFAULT, a 65th byte gives the type
+        let sighash = match signature.len() {
+            64 => Some("SIGHASH_DEFAULT".to_string()),
+            65 => TapSighashType::from_consensus_u8(signature[64])
+                .ok()
+                .map(|sighash| sighash.to_string()),
+            _ => None,
+        };
+        result["spend_type"] = json!("key_path");
+        result["signature"] = json!(hex_encode(signature));
+        result["sighash_type"] = json!(sighash);
+    } else {
+        result["spend_type"] = json!("script_path");
+        result["script_path"] = taproot_script_path(&witness, output_key);
+    }
+    Ok(result.to_string())
+}
+
+fn fetch_tx_witness(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    vin: u32,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let input = tx["vin"]
+        .get(vin as usize)
+        .ok_or_else(|| format!("Transaction {txid} has no input {vin}"))?;
+    let witness: Vec<&str> = input["witness"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    let prevout_type = input["prevout"]["scriptpubkey_type"].as_str();
+    let mut result = json!({
+        "txid": txid,
+        "vin": vin,
+        "prevout_type": prevout_type,
+        "witness": witness,
+    });
+    if witness.is_empty() {
+        result["classification"] = json!("none");
+        result["note"] = json!(if input["is_coinbase"] == true {
+            "Coinbase inputs have no witness data"
+        } else {
+            "This input has no witness data, it spends a non-segwit output"
+        });
+    } else {
+        let (classification, roles) = classify_witness(prevout_type.unwrap_or_default(), &witness);
+        result["classification"] = json!(classification);
+        result["roles"] = json!(roles);
+    }
+    Ok(result.to_string())
+}
+
+fn fetch_tx_input_addresses(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let inputs = tx["vin"].as_array().ok_or("Transaction has no vin array")?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let inputs: Vec<Value> = inputs
+        .iter()
+        .map(|input| {
+            let mut entry = json!({
+                "address": input["prevout"]["scriptpubkey_address"],
+                "value": input["prevout"]["value"],
+            });
+            if input["is_coinbase"] == true {
+                entry["is_coinbase"] = json!(true);
+            }
+            entry
+        })
+        .collect();
+    let outputs: Vec<Value> = outputs
+        .iter()
+        .map(|output| {
+            json!({
+                "address": output["scriptpubkey_address"],
+                "value": output["value"],
+                "type": output["scriptpubkey_type"],
+            })
+        })
+        .collect();
+    let result = json!({
+        "txid": txid,
+        "inputs": inputs,
+        "outputs": outputs,
+        "fee": tx["fee"],
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_input_prevout(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    vin: u32,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let input = tx["vin"]
+        .get(vin as usize)
+        .ok_or_else(|| format!("Transaction {txid} has no input {vin}"))?;
+    if input["is_coinbase"] == true {
+        let result = json!({
+            "txid": txid,
+            "vin": vin,
+            "is_coinbase": true,
+            "prevout": null,
+            "note": "No prevout (coinbase): this input creates new coins instead of spending an output",
+        });
+        return Ok(result.to_string());
+    }
+    let prevout = &input["prevout"];
+    if !prevout.is_object() {
+        return Err(format!(
+            "Input {vin} of transaction {txid} has no prevout in the Esplora response"
+        ));
+    }
+    let result = json!({
+        "txid": txid,
+        "vin": vin,
+        "is_coinbase": false,
+        "prevout": {
+            "txid": input["txid"],
+            "vout": input["vout"],
+            "value_sat": prevout["value"],
+            "address": prevout["scriptpubkey_address"],
+            "script_type": prevout["scriptpubkey_type"],
+            "scriptpubkey": prevout["scriptpubkey"],
+        },
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_tx_block(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+) -> Result<String, String> {
+    let status = fetch_json(client, &format!("{base_url}/tx/{txid}/status"))?;
+    if status["confirmed"] != true {
+        return Ok(json!({ "confirmed": false }).to_string());
+    }
+    let hash = status["block_hash"]
+        .as_str()
+        .ok_or("Transaction status has no block_hash")?;
+    let block = fetch_json(client, &format!("{base_url}/block/{hash}"))?;
+    let result = json!({
+        "confirmed": true,
+        "block_height": block["height"],
+        "block_hash": hash,
+        "block_time": block["timestamp"],
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_output_spender(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    vout: u32,
+) -> Result<String, String> {
+    let outspend = fetch_json(client, &format!("{base_url}/tx/{txid}/outspend/{vout}"))?;
+    if outspend["spent"] != true {
+        return Ok(json!({ "spent": false }).to_string());
+    }
+    let spender = outspend["txid"]
+        .as_str()
+        .ok_or("Outspend response has no spending txid")?;
+    let tx = fetch_json(client, &format!("{base_url}/tx/{spender}"))?;
+    let result = json!({
+        "spent": true,
+        "vin": outspend["vin"],
+        "tx": tx,
+    });
+    Ok(result.to_string())
+}
+
+// The index of the output picked out of a transaction's outputs
+fn pick_output(outputs: &[Value], follow: FollowOutput) -> Option<u32> {
+    match follow {
+        FollowOutput::Index(vout) => Some(vout),
+        FollowOutput::Pick(OutputPick::Largest) => outputs
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, output)| output["value"].as_u64().unwrap_or(0))
+            .map(|(vout, _)| vout as u32),
+    }
+}
+
+// Follow an output through one spend per entry of `follow` plus one
+fn trace_output_forward(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    vout: u32,
+    follow: &[FollowOutput],
+) -> Result<String, String> {
+    let mut tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let mut txid = txid.to_string();
+    let mut vout = vout;
+    let mut chain = Vec::new();
+    let mut picks = follow.iter();
+    let ended = loop {
+        let outputs = tx["vout"]
+            .as_array()
+            .ok_or("Transaction has no vout array")?;
+        let output = outputs.get(vout as usize).ok_or_else(|| {
+            format!(
+                "Transaction {txid} has no output {vout}, it has {}",
+                plural(outputs.len(), "output")
+            )
+        })?;
+        let outspend = fetch_json(client, &format!("{base_url}/tx/{txid}/outspend/{vout}"))?;
+        let spender = outspend["txid"]
+            .as_str()
+            .filter(|_| outspend["spent"] == true);
+        chain.push(json!({
+            "txid": txid,
+            "vout": vout,
+            "value": output["value"],
+            "address": output["scriptpubkey_address"],
+            "spent_by": spender,
+            "spent_by_vin": spender.map(|_| outspend["vin"].clone()),
+        }));
+        let Some(spender) = spender else {
+            break "unspent";
+        };
+        let Some(&pick) = picks.next() else {
+            break "hops_reached";
+        };
+        let spender = spender.to_string();
+        tx = fetch_json(client, &format!("{base_url}/tx/{spender}"))?;
+        let outputs = tx["vout"]
+            .as_array()
+            .ok_or("Transaction has no vout array")?;
+        vout = pick_output(outputs, pick)
+            .ok_or_else(|| format!("Transaction {spender} has no outputs to follow"))?;
+        txid = spender;
+    };
+    let result = json!({
+        "hops": chain.len(),
+        "ended": ended,
+        "chain": chain,
+    });
+    Ok(result.to_string())
+}
+
+fn fetch_utxo_status(
+    client: &dyn EsploraClient,
+    base_url: &str,
+    txid: &str,
+    vout: u32,
+) -> Result<String, String> {
+    let tx = fetch_json(client, &format!("{base_url}/tx/{txid}"))?;
+    let outputs = tx["vout"]
+        .as_array()
+        .ok_or("Transaction has no vout array")?;
+    let output = outputs.get(vout as usize).ok_or_else(|| {
+        format!(
+            "Transaction {txid} has no output {vout}, it has {}",
+            plural(outputs.len(), "output")
+        )
+    })?;
+    let outspend = fetch_json(client, &format!("{base_url}/tx/{txid}/outspend/{vout}"))?;
+    let spent = outspend["spent"] == true;
+    let result = json!({
+        "txid": txid,
+        "vout": vout,
+        "value": output["value"],
+        "address": output["scriptpubkey_address"],
+        "scriptpubkey_type": output["scriptpubkey_type"],
+        "confirmed": tx["status"]["confirmed"],
+        "spent": spent,
+        "spending_txid": if spent { outspend["txid"].clone() } else { Value::Null },
+        "spending_vin": if spent { outspend["vin"].clone() } else { Value::Null },
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esplora::testing::MockClient;
+
+    #[test]
+    fn test_fetch_utxo_status() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/ab",
+                r#"{"status":{"confirmed":true},"vout":[{"value":1000,"scriptpubkey_address":"bc1qaa","scriptpubkey_type":"v0_p2wpkh"},{"value":5}]}"#,
+            )
+            .with(
+                "http://esplora/tx/ab/outspend/0",
+                r#"{"spent":true,"txid":"cd","vin":1}"#,
+            )
+            .with("http://esplora/tx/ab/outspend/1", r#"{"spent":false}"#);
+        let result = fetch_utxo_status(&client, "http://esplora", "ab", 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["value"], 1000);
+        assert_eq!(result["address"], "bc1qaa");
+        assert_eq!(result["spent"], true);
+        assert_eq!(result["spending_txid"], "cd");
+        assert_eq!(result["spending_vin"], 1);
+        let result = fetch_utxo_status(&client, "http://esplora", "ab", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["spent"], false);
+        assert_eq!(result["spending_txid"], Value::Null);
+        let err = fetch_utxo_status(&client, "http://esplora", "ab", 2).unwrap_err();
+        assert_eq!(err, "Transaction ab has no output 2, it has 2 outputs");
+    }
+
+    #[test]
+    fn test_op_return_data() {
+        // OP_RETURN OP_PUSHBYTES_5 "hello"
+        assert_eq!(
+            op_return_data(&hex_decode("6a0568656c6c6f").unwrap()),
+            b"hello"
+        );
+        // OP_RETURN OP_PUSHDATA1 <3> "abc"
+        assert_eq!(op_return_data(&hex_decode("6a4c03616263").unwrap()), b"abc");
+        // bare OP_RETURN
+        assert!(op_return_data(&[0x6a]).is_empty());
+    }
+
+    #[test]
+    fn test_fetch_tx_opreturn() {
+        let client = MockClient::default().with(
+            "http://esplora/tx/abcd",
+            r#"{"vout":[
+                {"scriptpubkey":"0014aa","scriptpubkey_type":"v0_p2wpkh","value":1},
+                {"scriptpubkey":"6a0568656c6c6f","scriptpubkey_type":"op_return","value":0}
+            ]}"#,
+        );
+        let result = fetch_tx_opreturn(&client, "http://esplora", "abcd").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            json!([{ "vout": 1, "hex": "68656c6c6f", "utf8": "hello" }])
+        );
+    }
+
+    #[test]
+    fn test_fetch_tx_raw() {
+        let client = MockClient::default().with("http://esplora/tx/abcd/raw", "\u{1}\u{2}a");
+        let result = fetch_tx_raw(&client, "http://esplora", "abcd").unwrap();
+        assert_eq!(result, "010261");
+    }
+
+    #[test]
+    fn test_fetch_tx_fee_context() {
+        let page = |rates: &[u64]| {
+            let txs: Vec<Value> = rates
+                .iter()
+                .map(|rate| json!({ "fee": rate * 100, "weight": 400 }))
+                .collect();
+            Value::Array(txs).to_string()
+        };
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/ab",
+                r#"{"fee":1500,"weight":400,"status":{"confirmed":true,"block_hash":"00ff","block_height":7}}"#,
+            )
+            .with("http://esplora/block/00ff", r#"{"tx_count":30}"#)
+            // The coinbase pays no fee and is left out
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                &page(&[0, 20, 10, 5]),
+            )
+            .with("http://esplora/block/00ff/txs/25", &page(&[1, 2]));
+        let result = fetch_tx_fee_context(&client, "http://esplora", "ab").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["feerate_sat_vb"], 15.0);
+        assert_eq!(result["sample_size"], 5);
+        assert_eq!(result["block_median_feerate_sat_vb"], 5.0);
+        assert_eq!(result["percentile"], 80);
+        assert_eq!(result["above_median"], true);
+    }
+
+    #[test]
+    fn test_fetch_tx_fee_rank() {
+        let page = |rates: &[(&str, u64)]| {
+            let txs: Vec<Value> = rates
+                .iter()
+                .map(|(txid, rate)| json!({ "txid": txid, "fee": rate * 100, "weight": 400 }))
+                .collect();
+            Value::Array(txs).to_string()
+        };
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/ab",
+                r#"{"fee":1000,"weight":400,"status":{"confirmed":true,"block_hash":"00ff","block_height":7}}"#,
+            )
+            .with("http://esplora/block/00ff", r#"{"tx_count":5}"#)
+            .with(
+                "http://esplora/block/00ff/txs/0",
+                &page(&[("cb", 0), ("c1", 20), ("ab", 10), ("c2", 10), ("c3", 5)]),
+            );
+        let result = fetch_tx_fee_rank(&client, "http://esplora", "ab").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["sample_size"], 3);
+        assert_eq!(result["complete"], true);
+        assert_eq!(result["rank"], 2);
+        assert_eq!(result["paying_less"], 1);
+        assert_eq!(result["paying_same"], 1);
+        assert_eq!(result["percentile"], 33);
+        assert_eq!(result["max_feerate_sat_vb"], 20.0);
+        assert!(result.get("note").is_none());
+    }
+
+    #[test]
+    fn test_fetch_tx_is_coinbase() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/cb",
+                r#"{"vin":[{"is_coinbase":true}],"status":{"confirmed":true,"block_height":7}}"#,
+            )
+            .with(
+                "http://esplora/tx/ab",
+                r#"{"vin":[{"is_coinbase":false,"txid":"ff","vout":0}],"status":{"confirmed":true,"block_height":7}}"#,
+            );
+        let result = fetch_tx_is_coinbase(&client, "http://esplora", "cb").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["is_coinbase"], true);
+        assert_eq!(result["block_height"], 7);
+        let result = fetch_tx_is_coinbase(&client, "http://esplora", "ab").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["is_coinbase"], false);
+        assert!(result.get("block_height").is_none());
+        // Without the flag, the null prevout is enough
+        let null_prevout = json!({ "vin": [{ "txid": "0".repeat(64), "vout": 0xffff_ffffu64 }] });
+        assert!(is_coinbase_tx(&null_prevout));
+    }
+
+    #[test]
+    fn test_fetch_input_prevout() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/ab",
+                r#"{"vin":[{"txid":"ff","vout":2,"is_coinbase":false,"prevout":{"value":1000,"scriptpubkey":"0014aa","scriptpubkey_type":"v0_p2wpkh","scriptpubkey_address":"bc1qaa"}}]}"#,
+            )
+            .with("http://esplora/tx/cb", r#"{"vin":[{"is_coinbase":true,"prevout":null}]}"#);
+        let result = fetch_input_prevout(&client, "http://esplora", "ab", 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["prevout"]["txid"], "ff");
+        assert_eq!(result["prevout"]["vout"], 2);
+        assert_eq!(result["prevout"]["value_sat"], 1000);
+        assert_eq!(result["prevout"]["address"], "bc1qaa");
+        assert_eq!(result["prevout"]["script_type"], "v0_p2wpkh");
+        assert!(fetch_input_prevout(&client, "http://esplora", "ab", 1).is_err());
+        let result = fetch_input_prevout(&client, "http://esplora", "cb", 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["is_coinbase"], true);
+        assert!(result["prevout"].is_null());
+    }
+
+    #[test]
+    fn test_tx_to_psbt() {
+        let genesis = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+        let client = MockClient::default()
+            .with("http://esplora/tx/ab/hex", genesis)
+            .with(
+                "http://esplora/tx/ab",
+                r#"{"vin":[{"prevout":{"value":1000,"scriptpubkey":"0014751e76e8199196d454941c45d1b3a323f1433bd6","scriptpubkey_type":"v0_p2wpkh"}}]}"#,
+            );
+        let result = tx_to_psbt(&client, "http://esplora", "ab").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["inputs_with_witness_utxo"], 1);
+        let psbt: bitcoin::Psbt = result["psbt"].as_str().unwrap().parse().unwrap();
+        assert!(psbt.unsigned_tx.input[0].script_sig.is_empty());
+        assert_eq!(psbt.unsigned_tx.output.len(), 1);
+        assert_eq!(
+            psbt.inputs[0].witness_utxo.as_ref().unwrap().value.to_sat(),
+            1000
+        );
+    }
+
+    #[test]
+    fn test_fetch_tx_input_addresses() {
+        let tx = json!({
+            "vin": [{ "txid": "pp", "vout": 0, "prevout": { "scriptpubkey_address": "bc1qa", "value": 5000 } }],
+            "vout": [
+                { "scriptpubkey_address": "bc1qb", "value": 4000, "scriptpubkey_type": "v0_p2wpkh" },
+                { "value": 0, "scriptpubkey_type": "op_return" },
+            ],
+            "fee": 1000,
+        });
+        let coinbase = json!({
+            "vin": [{ "is_coinbase": true, "prevout": null }],
+            "vout": [{ "scriptpubkey_address": "bc1qminer", "value": 312_500_000 }],
+            "fee": 0,
+        });
+        let client = MockClient::default()
+            .with("http://esplora/tx/aa", &tx.to_string())
+            .with("http://esplora/tx/cb", &coinbase.to_string());
+        let flow = |txid| {
+            let result = fetch_tx_input_addresses(&client, "http://esplora", txid).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = flow("aa");
+        assert_eq!(
+            result["inputs"],
+            json!([{ "address": "bc1qa", "value": 5000 }])
+        );
+        assert_eq!(result["outputs"][1]["address"], Value::Null);
+        assert_eq!(result["outputs"][1]["type"], "op_return");
+        assert_eq!(result["fee"], 1000);
+        let result = flow("cb");
+        assert_eq!(
+            result["inputs"],
+            json!([{ "address": null, "value": null, "is_coinbase": true }])
+        );
+    }
+
+    #[test]
+    fn test_privacy_heuristics() {
+        let input = |address: &str, script_type: &str| json!({ "prevout": { "scriptpubkey_address": address, "scriptpubkey_type": script_type } });
+        let output = |address: &str, script_type: &str, value: u64| json!({ "scriptpubkey_address": address, "scriptpubkey_type": script_type, "value": value });
+        // A round payment to a p2tr address, p2wpkh change back to the input address
+        let payment = json!({
+            "vin": [input("bc1qa", "v0_p2wpkh")],
+            "vout": [output("bc1pb", "v1_p2tr", 1_000_000), output("bc1qa", "v0_p2wpkh", 123_456)],
+        });
+        assert!(address_reuse_leak(&payment).unwrap().contains("bc1qa"));
+        assert!(
+            round_amount_leak(&payment)
+                .unwrap()
+                .starts_with("Output 0 ")
+        );
+        assert!(
+            script_type_change_leak(&payment)
+                .unwrap()
+                .starts_with("Output 1 ")
+        );
+        assert!(no_equal_outputs_leak(&payment).is_some());
+
+        let coinjoin = json!({
+            "vin": [input("bc1qa", "v0_p2wpkh"), input("bc1qb", "v0_p2wpkh")],
+            "vout": [output("bc1qc", "v0_p2wpkh", 500_000), output("bc1qd", "v0_p2wpkh", 500_000)],
+        });
+        for (_, _, check) in PRIVACY_HEURISTICS {
+            assert_eq!(check(&coinjoin), None);
+        }
+
+        let client = MockClient::default().with("http://esplora/tx/aa", &payment.to_string());
+        let result = fetch_tx_privacy_score(&client, "http://esplora", "aa").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["score"], 0);
+        assert_eq!(result["heuristics"][0]["flagged"], true);
+    }
+
+    #[test]
+    fn test_fetch_tx_self_transfer() {
+        let input = |address: &str| json!({ "prevout": { "scriptpubkey_address": address } });
+        let output =
+            |address: Option<&str>| json!({ "scriptpubkey_address": address, "value": 1000 });
+        let payment = json!({
+            "vin": [input("bc1qa"), input("bc1qb")],
+            "vout": [output(Some("bc1qpayee")), output(Some("bc1qb")), output(None)],
+        });
+        let consolidation = json!({
+            "vin": [input("bc1qa"), input("bc1qa")],
+            "vout": [output(Some("bc1qa"))],
+        });
+        let client = MockClient::default()
+            .with("http://esplora/tx/aa", &payment.to_string())
+            .with("http://esplora/tx/bb", &consolidation.to_string());
+        let check = |txid| {
+            let result = fetch_tx_self_transfer(&client, "http://esplora", txid).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = check("aa");
+        assert_eq!(result["self_transfer"], true);
+        assert_eq!(result["matched_addresses"], json!(["bc1qb"]));
+        assert_eq!(result["matched_outputs"], json!([1]));
+        assert_eq!(result["all_outputs_to_inputs"], false);
+        let result = check("bb");
+        assert_eq!(result["all_outputs_to_inputs"], true);
+        assert_eq!(result["input_address_count"], 1);
+    }
+
+    #[test]
+    fn test_fetch_tx_consolidation() {
+        let input = json!({ "prevout": { "value": 10_000 } });
+        let output = |kind: &str, value| json!({ "scriptpubkey_type": kind, "value": value });
+        let tx = json!({
+            "vin": vec![input; 12],
+            "vout": [output("v0_p2wpkh", 119_000), output("op_return", 0)],
+            "fee": 1000,
+        });
+        let client = MockClient::default().with("http://esplora/tx/aa", &tx.to_string());
+        let check = |min_inputs, max_outputs| {
+            let result =
+                fetch_tx_consolidation(&client, "http://esplora", "aa", min_inputs, max_outputs)
+                    .unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = check(10, 2);
+        assert_eq!(result["consolidation"], true);
+        assert_eq!(result["input_count"], 12);
+        // The OP_RETURN output doesn't count
+        assert_eq!(result["output_count"], 1);
+        assert_eq!(result["consolidated_value"], 120_000);
+        assert_eq!(result["output_value"], 119_000);
+        assert_eq!(result["fee"], 1000);
+        assert_eq!(check(20, 2)["consolidation"], false);
+        assert_eq!(check(10, 0)["consolidation"], false);
+    }
+
+    #[test]
+    fn test_trace_output_forward() {
+        let tx = |values: &[u64]| {
+            let outputs: Vec<Value> = values
+                .iter()
+                .map(|value| json!({ "value": value, "scriptpubkey_address": "bc1qa" }))
+                .collect();
+            json!({ "vout": outputs }).to_string()
+        };
+        let client = MockClient::default()
+            .with("http://esplora/tx/aa", &tx(&[5000]))
+            .with(
+                "http://esplora/tx/aa/outspend/0",
+                r#"{"spent":true,"txid":"bb","vin":0}"#,
+            )
+            .with("http://esplora/tx/bb", &tx(&[1000, 3500]))
+            .with(
+                "http://esplora/tx/bb/outspend/1",
+                r#"{"spent":true,"txid":"cc","vin":2}"#,
+            )
+            .with("http://esplora/tx/bb/outspend/0", r#"{"spent":false}"#)
+            .with("http://esplora/tx/cc", &tx(&[3000]));
+        let trace = |follow: &[FollowOutput]| {
+            let result = trace_output_forward(&client, "http://esplora", "aa", 0, follow).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = trace(&[FollowOutput::Pick(OutputPick::Largest)]);
+        assert_eq!(result["ended"], "hops_reached");
+        assert_eq!(result["hops"], 2);
+        assert_eq!(result["chain"][0]["spent_by"], "bb");
+        assert_eq!(result["chain"][1]["vout"], 1);
+        assert_eq!(result["chain"][1]["value"], 3500);
+        assert_eq!(result["chain"][1]["spent_by"], "cc");
+        assert_eq!(result["chain"][1]["spent_by_vin"], 2);
+
+        let result = trace(&[FollowOutput::Index(0), FollowOutput::Index(0)]);
+        assert_eq!(result["ended"], "unspent");
+        assert_eq!(result["chain"][1]["spent_by"], Value::Null);
+
+        // An index the spending transaction doesn't have
+        assert!(
+            trace_output_forward(
+                &client,
+                "http://esplora",
+                "aa",
+                0,
+                &[FollowOutput::Index(5)]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fetch_coinbase_maturity() {
+        let coinbase = json!({
+            "vin": [{ "is_coinbase": true }],
+            "status": { "confirmed": true, "block_height": 839_950 },
+        });
+        let client = MockClient::default()
+            .with("http://esplora/tx/aa", &coinbase.to_string())
+            .with(
+                "http://esplora/tx/bb",
+                r#"{"vin":[{"txid":"cc","vout":0}]}"#,
+            )
+            .with("http://esplora/blocks/tip/height", "839999");
+        let result = fetch_coinbase_maturity(&client, "http://esplora", "aa").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["mature"], false);
+        assert_eq!(result["confirmations"], 50);
+        assert_eq!(result["confirmations_remaining"], 50);
+        assert_eq!(result["spendable_at_height"], 840_050);
+
+        // 100 confirmations: a spend may go in the next block
+        let client = client.with("http://esplora/blocks/tip/height", "840049");
+        let result = fetch_coinbase_maturity(&client, "http://esplora", "aa").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["mature"], true);
+        assert_eq!(result["confirmations_remaining"], 0);
+
+        let err = fetch_coinbase_maturity(&client, "http://esplora", "bb").unwrap_err();
+        assert!(err.contains("not a coinbase"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_fetch_rbf_chain() {
+        let input = |txid: &str, vout: u32| json!({ "txid": txid, "vout": vout, "sequence": 0xffff_fffd_u32 });
+        let tx = json!({
+            "vin": [input("p1", 0), input("p2", 1), input("p3", 0)],
+            "fee": 1000,
+            "weight": 800,
+            "status": { "confirmed": false },
+        });
+        let replacement = json!({
+            "vin": [input("p1", 0), input("p2", 1)],
+            "fee": 3000,
+            "weight": 600,
+            "status": { "confirmed": false },
+        });
+        let client = MockClient::default()
+            .with("http://esplora/tx/aa", &tx.to_string())
+            .with("http://esplora/tx/bb", &replacement.to_string())
+            .with(
+                "http://esplora/tx/p1/outspend/0",
+                r#"{"spent":true,"txid":"bb","vin":0}"#,
+            )
+            .with(
+                "http://esplora/tx/p2/outspend/1",
+                r#"{"spent":true,"txid":"bb","vin":1}"#,
+            )
+            .with("http://esplora/tx/p3/outspend/0", r#"{"spent":false}"#);
+
+        let result = fetch_rbf_chain(&client, "http://esplora", "aa", 25).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["feerate_sat_vb"], 5.0);
+        assert_eq!(result["replaced"], true);
+        assert_eq!(result["unspent_inputs"], json!([2]));
+        assert_eq!(result["truncated"], false);
+        let candidates = result["candidates"].as_array().unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0]["txid"], "bb");
+        assert_eq!(candidates[0]["feerate_sat_vb"], 20.0);
+        assert_eq!(candidates[0]["shared_inputs"][1]["outpoint"], "p2:1");
+
+        let result = fetch_rbf_chain(&client, "http://esplora", "aa", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["inputs_examined"], 1);
+        assert_eq!(result["truncated"], true);
+    }
+
+    #[test]
+    fn test_fetch_tx_finality() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/aa",
+                r#"{"vin":[{"sequence":4294967293}],"status":{"confirmed":true,"block_height":840000}}"#,
+            )
+            .with(
+                "http://esplora/tx/bb",
+                r#"{"vin":[{"sequence":4294967293}],"status":{"confirmed":false}}"#,
+            )
+            .with("http://esplora/blocks/tip/height", "840005");
+        let finality = |txid, min_confirmations| {
+            let result =
+                fetch_tx_finality(&client, "http://esplora", txid, min_confirmations).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = finality("aa", 6);
+        // Signaling RBF doesn't matter once confirmed
+        assert_eq!(result["final"], true);
+        assert_eq!(result["confirmations"], 6);
+        assert_eq!(result["replaceable"], false);
+        let result = finality("aa", 10);
+        assert_eq!(result["final"], false);
+        assert_eq!(result["confirmations_remaining"], 4);
+        let result = finality("bb", 1);
+        assert_eq!(result["final"], false);
+        assert_eq!(result["confirmed"], false);
+        assert_eq!(result["replaceable"], true);
+    }
+
+    #[test]
+    fn test_fetch_rbf_bump_feerate() {
+        let tx = |sequence: u64, confirmed: bool| {
+            json!({
+                "vin": [{ "sequence": sequence }],
+                "fee": 1000,
+                "weight": 800,
+                "status": { "confirmed": confirmed },
+            })
+            .to_string()
+        };
+        let client = MockClient::default()
+            .with("http://esplora/tx/aa", &tx(0xffff_fffd, false))
+            .with("http://esplora/tx/bb", &tx(0xffff_ffff, false))
+            .with("http://esplora/tx/cc", &tx(0xffff_fffd, true))
+            .with("http://esplora/fee-estimates", r#"{"1":20.0,"6":8.0}"#);
+        let bump = |txid, target| {
+            let result = fetch_rbf_bump_feerate(&client, "http://esplora", txid, target).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+
+        // 1000 sat for 200 vB: 5 sat/vB, bumped to the 20 sat/vB estimate
+        let result = bump("aa", 1);
+        assert_eq!(result["feerate_sat_vb"], 5.0);
+        assert_eq!(result["suggested_feerate_sat_vb"], 20.0);
+        assert_eq!(result["extra_fee"], 3000);
+        // The incremental relay fee wins over an estimate barely above the original
+        let client = client.with("http://esplora/fee-estimates", r#"{"6":5.5}"#);
+        let result = fetch_rbf_bump_feerate(&client, "http://esplora", "aa", 6).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["suggested_feerate_sat_vb"], 6.0);
+        assert_eq!(result["extra_fee"], 200);
+
+        let err = fetch_rbf_bump_feerate(&client, "http://esplora", "bb", 6).unwrap_err();
+        assert!(err.contains("doesn't signal"), "unexpected error: {err}");
+        let err = fetch_rbf_bump_feerate(&client, "http://esplora", "cc", 6).unwrap_err();
+        assert!(err.contains("already confirmed"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_fetch_cpfp_feerate() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/aa",
+                r#"{"fee":200,"weight":800,"status":{"confirmed":false}}"#,
+            )
+            .with(
+                "http://esplora/tx/cc",
+                r#"{"fee":200,"weight":800,"status":{"confirmed":true}}"#,
+            );
+        let cpfp = |target| {
+            let result = fetch_cpfp_feerate(&client, "http://esplora", "aa", target, 100).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+
+        // 300 vB at 10 sat/vB is 3000 sat, the parent already pays 200
+        let result = cpfp(10.0);
+        assert_eq!(result["parent"]["feerate_sat_vb"], 1.0);
+        assert_eq!(result["child_fee"], 2800);
+        assert_eq!(result["child_feerate_sat_vb"], 28.0);
+        assert_eq!(result["package_feerate_sat_vb"], 10.0);
+        // A parent already above the target still needs a relayable child
+        let result = cpfp(0.5);
+        assert_eq!(result["child_fee"], 100);
+
+        let err = fetch_cpfp_feerate(&client, "http://esplora", "cc", 10.0, 100).unwrap_err();
+        assert!(err.contains("already confirmed"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_fetch_tx_mempool_info() {
+        let tx = r#"{"fee":1000,"weight":800,"status":{"confirmed":false}}"#;
+        let client = MockClient::default()
+            .with("http://esplora/tx/aa", tx)
+            .with("http://esplora/tx/bb", tx)
+            .with(
+                "http://esplora/v1/cpfp/aa",
+                r#"{"ancestors":[{"txid":"00","fee":200,"weight":400}],"descendants":[],
+                    "effectiveFeePerVsize":4.0}"#,
+            );
+        let info = |txid| {
+            let result = fetch_tx_mempool_info(&client, "http://esplora", txid).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+
+        let result = info("aa");
+        assert_eq!(result["tx"]["vsize"], 200);
+        assert_eq!(result["tx"]["feerate_sat_vb"], 5.0);
+        let mempool = &result["mempool"];
+        assert_eq!(mempool["available"], true);
+        assert_eq!(mempool["ancestor_count"], 1);
+        assert_eq!(mempool["ancestor_vsize"], 100);
+        assert_eq!(mempool["descendant_count"], 0);
+        assert_eq!(mempool["effective_feerate_sat_vb"], 4.0);
+
+        // Without the endpoint only the transaction's own fields are reported
+        let result = info("bb");
+        assert_eq!(result["tx"]["fee"], 1000);
+        assert_eq!(result["mempool"]["available"], false);
+        assert_eq!(result["mempool"]["source"], "/v1/cpfp/bb");
+    }
+
+    #[test]
+    fn test_fetch_tx_dust() {
+        let p2pkh = format!("76a914{}88ac", "00".repeat(20));
+        let p2wpkh = format!("0014{}", "00".repeat(20));
+        let p2tr = format!("5120{}", "00".repeat(32));
+        let tx = json!({ "vout": [
+            { "scriptpubkey": p2pkh, "scriptpubkey_type": "p2pkh", "value": 545 },
+            { "scriptpubkey": p2pkh, "scriptpubkey_type": "p2pkh", "value": 546 },
+            { "scriptpubkey": p2wpkh, "scriptpubkey_type": "v0_p2wpkh", "value": 293 },
+            { "scriptpubkey": p2wpkh, "scriptpubkey_type": "v0_p2wpkh", "value": 294 },
+            { "scriptpubkey": p2tr, "scriptpubkey_type": "v1_p2tr", "value": 329 },
+            { "scriptpubkey": "6a0100", "scriptpubkey_type": "op_return", "value": 0 },
+        ]});
+        let client = MockClient::default().with("http://esplora/tx/abcd", &tx.to_string());
+        let result = fetch_tx_dust(&client, "http://esplora", "abcd").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["dust_count"], 3);
+        let dust = &result["dust_outputs"];
+        assert_eq!(dust[0]["vout"], 0);
+        assert_eq!(dust[0]["dust_threshold_sat"], 546);
+        assert_eq!(dust[1]["vout"], 2);
+        assert_eq!(dust[1]["dust_threshold_sat"], 294);
+        assert_eq!(dust[2]["vout"], 4);
+        assert_eq!(dust[2]["dust_threshold_sat"], 330);
+    }
+
+    #[test]
+    fn test_fetch_tx_rbf_status() {
+        let client = MockClient::default().with(
+            "http://esplora/tx/abcd",
+            r#"{"vin":[{"sequence":4294967295},{"sequence":4294967293}],"status":{"confirmed":false}}"#,
+        );
+        let result = fetch_tx_rbf_status(&client, "http://esplora", "abcd").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["rbf"], true);
+        assert_eq!(result["signaling_inputs"], json!([1]));
+        assert_eq!(result["confirmed"], false);
+    }
+
+    #[test]
+    fn test_tx_size() {
+        // Genesis coinbase, a legacy transaction
+        let genesis = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+        let result: Value = serde_json::from_str(&tx_size(genesis).unwrap()).unwrap();
+        assert_eq!(
+            result["txid"],
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+        );
+        assert_eq!(result["segwit"], false);
+        assert_eq!(result["size"], 204);
+        assert_eq!(result["weight"], 816);
+        assert_eq!(result["vsize"], 204);
+
+        // Witness bytes weigh a quarter of the others
+        let mut tx: bitcoin::Transaction =
+            bitcoin::consensus::deserialize(&hex_decode(genesis).unwrap()).unwrap();
+        tx.input[0].witness.push([0u8; 64]);
+        let segwit = hex_encode(&bitcoin::consensus::serialize(&tx));
+        let result: Value = serde_json::from_str(&tx_size(&segwit).unwrap()).unwrap();
+        assert_eq!(result["segwit"], true);
+        assert_eq!(result["base_size"], 204);
+        // Marker, flag, item count, item length, and the 64-byte item
+        assert_eq!(result["size"], 204 + 2 + 1 + 1 + 64);
+        assert_eq!(result["weight"], 204 * 4 + 68);
+        assert_eq!(result["vsize"], 221);
+
+        assert!(
+            tx_size("abc")
+                .unwrap_err()
+                .starts_with("Invalid transaction hex")
+        );
+        assert!(
+            tx_size("0100")
+                .unwrap_err()
+                .starts_with("Invalid transaction:")
+        );
+    }
+
+    #[test]
+    fn test_verify_payment() {
+        let client = MockClient::default().with(
+            "http://esplora/tx/abcd",
+            r#"{"vout":[
+                {"scriptpubkey_address":"bc1qmerchant","value":30000},
+                {"scriptpubkey_address":"bc1qchange","value":5000},
+                {"scriptpubkey_address":"bc1qmerchant","value":20000}
+            ],"status":{"confirmed":true}}"#,
+        );
+        let verify = |address, min_amount_sat| -> Value {
+            let result =
+                verify_payment(&client, "http://esplora", "abcd", address, min_amount_sat).unwrap();
+            serde_json::from_str(&result).unwrap()
+        };
+        let result = verify("bc1qmerchant", 50000);
+        assert_eq!(result["paid"], true);
+        assert_eq!(result["amount_sat"], 50000);
+        assert_eq!(result["vout"], json!([0, 2]));
+        assert!(result["reason"].is_null());
+
+        let result = verify("bc1qmerchant", 50001);
+        assert_eq!(result["paid"], false);
+        assert!(result["reason"].as_str().unwrap().contains("less than"));
+
+        let result = verify("bc1qother", 0);
+        assert_eq!(result["paid"], false);
+        assert_eq!(result["vout"], json!([]));
+        assert!(result["reason"].as_str().unwrap().starts_with("No output"));
+    }
+
+    #[test]
+    fn test_fetch_tx_block() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/abcd/status",
+                r#"{"confirmed":true,"block_height":840000,"block_hash":"00ff","block_time":1713571767}"#,
+            )
+            .with("http://esplora/tx/ef01/status", r#"{"confirmed":false}"#)
+            .with(
+                "http://esplora/block/00ff",
+                r#"{"id":"00ff","height":840000,"timestamp":1713571767}"#,
+            );
+        let result = fetch_tx_block(&client, "http://esplora", "abcd").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            json!({ "confirmed": true, "block_height": 840000, "block_hash": "00ff", "block_time": 1713571767 })
+        );
+        let result = fetch_tx_block(&client, "http://esplora", "ef01").unwrap();
+        assert_eq!(result, r#"{"confirmed":false}"#);
+    }
+
+    #[test]
+    fn test_fetch_output_spender() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/abcd/outspend/0",
+                r#"{"spent":true,"txid":"ef01","vin":3,"status":{"confirmed":true}}"#,
+            )
+            .with("http://esplora/tx/abcd/outspend/1", r#"{"spent":false}"#)
+            .with("http://esplora/tx/ef01", r#"{"txid":"ef01"}"#);
+        let result = fetch_output_spender(&client, "http://esplora", "abcd", 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            result,
+            json!({ "spent": true, "vin": 3, "tx": { "txid": "ef01" } })
+        );
+
+        let result = fetch_output_spender(&client, "http://esplora", "abcd", 1).unwrap();
+        assert_eq!(result, r#"{"spent":false}"#);
+        assert_eq!(client.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_summarize_tx() {
+        let tx = json!({
+            "weight": 561,
+            "fee": 2100,
+            "vin": [{ "is_coinbase": false }, { "is_coinbase": false }],
+            "vout": [{ "value": 30_000_000 }, { "value": 20_000_000 }],
+            "status": { "confirmed": true, "block_height": 800123 },
+        });
+        assert_eq!(
+            summarize_tx(&tx, "BTC", Locale::Plain),
+            "This transaction moved 0.5 BTC across 2 inputs and 2 outputs, paid a 0.000021 BTC fee (~15 sat/vB), and confirmed in block 800123."
+        );
+        assert!(
+            summarize_tx(&tx, "BTC", Locale::DeDe).starts_with("This transaction moved 0,5 BTC")
+        );
+
+        let tx = json!({
+            "fee": 30,
+            "vin": [{ "is_coinbase": false }],
+            "vout": [{ "value": null }, { "value": 30, "scriptpubkey_type": "fee" }],
+            "status": { "confirmed": false },
+        });
+        assert_eq!(
+            summarize_tx(&tx, "L-BTC", Locale::Plain),
+            "This transaction moved at least 0 L-BTC (some amounts are confidential) across 1 input and 1 output, paid a 0.0000003 L-BTC fee, and is still unconfirmed."
+        );
+    }
+
+    #[test]
+    fn test_find_transaction_falls_back_to_liquid() {
+        let client = MockClient::default().with("http://liquid/tx/abcd", r#"{"txid":"abcd"}"#);
+        let config = Config::default();
+        let result =
+            find_transaction(&client, &config, "abcd", "http://bitcoin", "http://liquid").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["chain"], "liquid");
+        assert_eq!(result["tx"]["txid"], "abcd");
+    }
+
+    #[test]
+    fn test_find_transaction_not_found() {
+        let client = MockClient::default();
+        let config = Config::default();
+        let err = find_transaction(&client, &config, "abcd", "http://bitcoin", "http://liquid")
+            .unwrap_err();
+        assert_eq!(err, "Transaction abcd not found on any chain");
+    }
+
+    #[test]
+    fn test_find_transaction_respects_enabled_networks() {
+        let client = MockClient::default().with("http://liquid/tx/abcd", r#"{"txid":"abcd"}"#);
+        let config = Config {
+            enabled_chains: vec![Chain::Bitcoin],
+            ..Config::default()
+        };
+        let err = find_transaction(&client, &config, "abcd", "http://bitcoin", "http://liquid")
+            .unwrap_err();
+        assert_eq!(err, "Transaction abcd not found on any chain");
+        assert_eq!(client.requests(), vec!["http://bitcoin/tx/abcd"]);
+    }
+
+    #[test]
+    fn test_fetch_coinjoin_heuristic() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/cj",
+                r#"{"vin":[{},{},{},{}],"vout":[{"value":100000},{"value":100000},{"value":100000},{"value":37000},{"value":0}]}"#,
+            )
+            .with(
+                "http://esplora/tx/pay",
+                r#"{"vin":[{}],"vout":[{"value":5000},{"value":5000},{"value":5000},{"value":120000}]}"#,
+            );
+        let result = fetch_coinjoin_heuristic(&client, "http://esplora", "cj").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["likely_coinjoin"], true);
+        assert_eq!(result["equal_output_value"], 100000);
+        assert_eq!(result["equal_output_count"], 3);
+
+        // A batched payment funded by a single input
+        let result = fetch_coinjoin_heuristic(&client, "http://esplora", "pay").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["likely_coinjoin"], false);
+        assert_eq!(result["equal_output_count"], 3);
+    }
+
+    // Client refusing every submission with the given error
+    struct RejectingClient(&'static str);
+
+    impl EsploraClient for RejectingClient {
+        fn get(&self, url: &str) -> Result<String, String> {
+            Err(format!("HTTP request failed: {url}: status code 404"))
+        }
+
+        fn post(&self, _url: &str, _body: &str) -> Result<String, String> {
+            Err(self.0.to_string())
+        }
+    }
+
+    #[test]
+    fn test_broadcast_tx() {
+        let client = MockClient::default().with("http://esplora/tx", "ab");
+        let result = broadcast_tx(&client, "http://esplora", "0100", "ab").unwrap();
+        assert_eq!(result, r#"{"already_known":false,"txid":"ab"}"#);
+
+        let client = RejectingClient(
+            r#"HTTP request failed: http://esplora/tx: status code 400: sendrawtransaction RPC error: {"code":-27,"message":"Transaction already in block chain"}"#,
+        );
+        let result = broadcast_tx(&client, "http://esplora", "0100", "ab").unwrap();
+        assert_eq!(result, r#"{"already_known":true,"txid":"ab"}"#);
+
+        let client = RejectingClient(
+            r#"HTTP request failed: http://esplora/tx: status code 400: sendrawtransaction RPC error: {"code":-26,"message":"min relay fee not met, 0 < 110"}"#,
+        );
+        let err = broadcast_tx(&client, "http://esplora", "0100", "ab").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Transaction ab rejected: min relay fee not met, 0 < 110"
+        );
+        let data = err.data.unwrap();
+        assert_eq!(data["kind"], "tx_rejected");
+        assert_eq!(data["category"], "insufficient_fee");
+
+        // Backend failures are reported as such, not as rejections
+        let client = RejectingClient("HTTP request failed: Connection refused");
+        let err = broadcast_tx(&client, "http://esplora", "0100", "ab").unwrap_err();
+        assert_eq!(err.message, "HTTP request failed: Connection refused");
+        assert_eq!(err.data, None);
+    }
+
+    #[test]
+    fn test_classify_witness() {
+        let sig = "30".repeat(71);
+        let pubkey = "02".repeat(33);
+        assert_eq!(
+            classify_witness("v0_p2wpkh", &[&sig, &pubkey]),
+            ("p2wpkh", vec!["signature", "pubkey"])
+        );
+        assert_eq!(classify_witness("p2sh", &[&sig, &pubkey]).0, "p2sh-p2wpkh");
+        assert_eq!(
+            classify_witness("v0_p2wsh", &["", &sig, "5221"]),
+            (
+                "p2wsh",
+                vec!["script_input", "script_input", "witness_script"]
+            )
+        );
+        assert_eq!(
+            classify_witness("v1_p2tr", &[&"ab".repeat(64)]),
+            ("taproot_keypath", vec!["signature"])
+        );
+        assert_eq!(
+            classify_witness("v1_p2tr", &[&sig, "20ac", "c0aa", "50ff"]),
+            (
+                "taproot_scriptpath",
+                vec!["script_input", "leaf_script", "control_block", "annex"]
+            )
+        );
+    }
+
+    #[test]
+    fn test_fetch_tx_witness() {
+        let client = MockClient::default().with(
+            "http://esplora/tx/ab",
+            r#"{"vin":[{"is_coinbase":false,"witness":["aa"],"prevout":{"scriptpubkey_type":"v1_p2tr"}},
+                       {"is_coinbase":false,"prevout":{"scriptpubkey_type":"p2pkh"}}]}"#,
+        );
+        let result = fetch_tx_witness(&client, "http://esplora", "ab", 0).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["classification"], "taproot_keypath");
+        assert_eq!(result["witness"], json!(["aa"]));
+
+        let result = fetch_tx_witness(&client, "http://esplora", "ab", 1).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["witness"], json!([]));
+        assert_eq!(
+            result["note"],
+            "This input has no witness data, it spends a non-segwit output"
+        );
+
+        let err = fetch_tx_witness(&client, "http://esplora", "ab", 2).unwrap_err();
+        assert_eq!(err, "Transaction ab has no input 2");
+    }
+
+    #[test]
+    fn test_fetch_tx_taproot_detail() {
+        use bitcoin::ScriptBuf;
+        use bitcoin::taproot::TaprootBuilder;
+
+        let secp = Secp256k1::new();
+        // The x coordinate of the generator point
+        let internal_key = XOnlyPublicKey::from_slice(
+            &hex_decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap(),
+        )
+        .unwrap();
+        let leaf = ScriptBuf::from_bytes(vec![0x51]);
+        let other = ScriptBuf::from_bytes(vec![0x52]);
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(1, leaf.clone())
+            .unwrap()
+            .add_leaf(1, other)
+            .unwrap()
+            .finalize(&secp, internal_key)
+            .unwrap();
+        let control_block = spend_info
+            .control_block(&(leaf.clone(), LeafVersion::TapScript))
+            .unwrap();
+        let output_key = spend_info.output_key().to_x_only_public_key();
+        let prevout = json!({
+            "scriptpubkey_type": "v1_p2tr",
+            "scriptpubkey": format!("5120{}", hex_encode(&output_key.serialize())),
+        });
+        let witness = |items: Vec<String>| json!({ "witness": items, "prevout": prevout });
+        let script_path = vec![
+            "01".to_string(),
+            hex_encode(leaf.as_bytes()),
+            hex_encode(&control_block.serialize()),
+            "50aa".to_string(),
+        ];
+        let tx = json!({
+            "vin": [
+                witness(vec!["11".repeat(64)]),
+                witness(script_path),
+                { "prevout": { "scriptpubkey_type": "v0_p2wpkh" } },
+            ]
+        });
+        let client = MockClient::default().with("http://esplora/tx/ab", &tx.to_string());
+        let detail = |vin| {
+            let result = fetch_tx_taproot_detail(&client, "http://esplora", "ab", vin).unwrap();
+            serde_json::from_str::<Value>(&result).unwrap()
+        };
+        let result = detail(0);
+        assert_eq!(result["spend_type"], "key_path");
+        assert_eq!(result["sighash_type"], "SIGHASH_DEFAULT");
+        assert_eq!(result["has_annex"], false);
+
+        let result = detail(1);
+        assert_eq!(result["spend_type"], "script_path");
+        assert_eq!(result["annex"], "50aa");
+        let script_path = &result["script_path"];
+        assert_eq!(script_path["leaf_script_asm"], "OP_PUSHNUM_1");
+        assert_eq!(script_path["tapscript"], true);
+        assert_eq!(script_path["script_inputs"], json!(["01"]));
+        assert_eq!(script_path["internal_key"], internal_key.to_string());
+        assert_eq!(script_path["merkle_depth"], 1);
+        assert_eq!(script_path["commitment_valid"], true);
+
+        let result = detail(2);
+        assert_eq!(result["taproot"], false);
+        assert_eq!(
+            result["note"],
+            "Input 2 is not a taproot input, it spends a v0_p2wpkh output"
+        );
+    }
+
+    #[test]
+    fn test_tx_fingerprint() {
+        let tx = |value: u64, address: &str| {
+            json!({
+                "version": 2,
+                "locktime": 840_000,
+                "vin": [{
+                    "sequence": 0xffff_fffd_u64,
+                    "witness": ["aa"],
+                    "prevout": { "scriptpubkey_type": "v0_p2wpkh", "scriptpubkey_address": address },
+                }],
+                "vout": [
+                    { "scriptpubkey_type": "v1_p2tr", "value": 200_000 },
+                    { "scriptpubkey_type": "v0_p2wpkh", "value": value },
+                ],
+            })
+        };
+        let (fingerprint, components) = tx_fingerprint(&tx(1_234_567, "bc1qa")).unwrap();
+        assert_eq!(
+            fingerprint,
+            "v2-lth-rbf-sw-i1[v0_p2wpkh]-o2[v1_p2tr:e5r,v0_p2wpkh:e6]"
+        );
+        assert_eq!(components["output_count"], 2);
+        // Other addresses and amounts of the same magnitude don't matter
+        assert_eq!(
+            tx_fingerprint(&tx(9_000_001, "bc1qb")).unwrap().0,
+            fingerprint
+        );
+        assert_ne!(tx_fingerprint(&tx(99_999, "bc1qa")).unwrap().0, fingerprint);
+        assert_eq!(value_bucket(0), "e0");
+    }
+
+    #[test]
+    fn test_diff_txs() {
+        let client = MockClient::default()
+            .with(
+                "http://esplora/tx/aa",
+                r#"{"fee":1000,"weight":800,
+                    "vin":[{"txid":"01","vout":0},{"txid":"01","vout":1}],
+                    "vout":[{"scriptpubkey":"0014aa","scriptpubkey_address":"bc1qa","value":5000},
+                            {"scriptpubkey":"0014cc","scriptpubkey_address":"bc1qc","value":3000}]}"#,
+            )
+            .with(
+                "http://esplora/tx/bb",
+                r#"{"fee":3000,"weight":800,
+                    "vin":[{"txid":"01","vout":0}],
+                    "vout":[{"scriptpubkey":"0014aa","scriptpubkey_address":"bc1qa","value":5000},
+                            {"scriptpubkey":"0014cc","scriptpubkey_address":"bc1qc","value":1000}]}"#,
+            )
+            .with("http://esplora/tx/cc", r#"{"vin":[{"txid":"02","vout":0}],"vout":[]}"#);
+        let result = diff_txs(&client, "http://esplora", "aa", "bb").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["related"], true);
+        assert_eq!(result["inputs"]["shared"], json!(["01:0"]));
+        assert_eq!(result["inputs"]["only_in_a"], json!(["01:1"]));
+        let changed = &result["outputs"]["changed"];
+        assert_eq!(changed.as_array().unwrap().len(), 1);
+        assert_eq!(changed[0]["scriptpubkey_address"], "bc1qc");
+        assert_eq!(changed[0]["value_diff"], -2000);
+        assert_eq!(result["fee_diff_sat"], 2000);
+        assert_eq!(result["feerate_diff"], 10.0);
+
+        let result = diff_txs(&client, "http://esplora", "aa", "cc").unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["related"], false);
+        assert_eq!(result["fee_diff_sat"], Value::Null);
+        assert!(result["note"].as_str().unwrap().contains("share no input"));
+    }
+
+    #[test]
+    fn test_reject_category() {
+        assert_eq!(
+            reject_category("bad-txns-inputs-missingorspent"),
+            "inputs_missing_or_spent"
+        );
+        assert_eq!(
+            reject_category("mandatory-script-verify-flag-failed (Signature must be zero)"),
+            "invalid_script"
+        );
+        assert_eq!(reject_category("bad-txns-vout-negative"), "invalid");
+        assert_eq!(reject_category("something new"), "other");
+    }
+
+    #[test]
+    fn test_validate_tx() {
+        use bitcoin::{Amount, OutPoint, ScriptBuf, Transaction, TxIn, TxOut, Txid};
+        let prev: Txid = "aa".repeat(32).parse().unwrap();
+        let tx = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: (0..2)
+                .map(|vout| TxIn {
+                    previous_output: OutPoint::new(prev, vout),
+                    ..TxIn::default()
+                })
+                .collect(),
+            output: vec![TxOut {
+                value: Amount::from_sat(900),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+        let hex = bitcoin::consensus::encode::serialize_hex(&tx);
+
+        let result: Value = serde_json::from_str(
+            &validate_tx(&MockClient::default(), "http://esplora", &hex, false).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(result["valid"], true);
+        assert_eq!(result["checks"].as_array().unwrap().len(), 7);
+
+        let prev_url = format!("http://esplora/tx/{prev}");
+        let client = MockClient::default()
+            .with(&prev_url, r#"{"vout":[{"value":500},{"value":600}]}"#)
+            .with(&format!("{prev_url}/outspend/0"), r#"{"spent":false}"#)
+            .with(
+                &format!("{prev_url}/outspend/1"),
+                r#"{"spent":true,"txid":"bb"}"#,
+            );
+        let result: Value =
+            serde_json::from_str(&validate_tx(&client, "http://esplora", &hex, true).unwrap())
+                .unwrap();
+        assert_eq!(result["valid"], false);
+        let checks = result["checks"].as_array().unwrap();
+        assert_eq!(checks[7]["passed"], true);
+        assert_eq!(checks[8]["passed"], false);
+        assert_eq!(
+            checks[8]["detail"],
+            format!("Output {prev}:1 is already spent by bb")
+        );
+        assert_eq!(checks[9]["detail"], "Fee 200 sat");
+        // The previous transaction is fetched once
+        assert_eq!(client.requests().len(), 3);
+
+        let result = validate_tx(&client, "http://esplora", "zz", true).unwrap();
+        assert_eq!(
+            result,
+            r#"{"checks":[{"check":"decodes","detail":"Invalid transaction hex: Invalid hex at position 0","passed":false}],"valid":false}"#
+        );
+    }
+
+    #[test]
+    fn test_validate_tx_output_overflow() {
+        use bitcoin::{Amount, OutPoint, ScriptBuf, Transaction, TxIn, TxOut, Txid};
+        let prev: Txid = "aa".repeat(32).parse().unwrap();
+        let output = TxOut {
+            value: Amount::from_sat(u64::MAX),
+            script_pubkey: ScriptBuf::new(),
+        };
+        let tx = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(prev, 0),
+                ..TxIn::default()
+            }],
+            output: vec![output.clone(), output],
+        };
+        let hex = bitcoin::consensus::encode::serialize_hex(&tx);
+        let prev_url = format!("http://esplora/tx/{prev}");
+        let client = MockClient::default()
+            .with(&prev_url, r#"{"vout":[{"value":500}]}"#)
+            .with(&format!("{prev_url}/outspend/0"), r#"{"spent":false}"#);
+        let result: Value =
+            serde_json::from_str(&validate_tx(&client, "http://esplora", &hex, true).unwrap())
+                .unwrap();
+        assert_eq!(result["valid"], false);
+        let checks = result["checks"].as_array().unwrap();
+        let check = checks.last().unwrap();
+        assert_eq!(check["check"], "inputs_cover_outputs");
+        assert_eq!(check["passed"], false);
+        assert_eq!(
+            check["detail"],
+            "Outputs total more than the 500 sat of the inputs"
+        );
+    }
+}
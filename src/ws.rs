@@ -0,0 +1,69 @@
+// WebSocket transport: each connection is an independent MCP session carrying
+// one JSON-RPC message per text frame. As on stdio, a frame that can't be
+// decoded is answered with a JSON-RPC error and the session goes on.
+use crate::MyServer;
+use crate::stdio::decode_line;
+use futures::channel::mpsc;
+use futures::future::ready;
+use futures::{SinkExt, StreamExt};
+use rmcp::ServiceExt;
+use rmcp::model::ServerJsonRpcMessage;
+use rmcp::serde_json;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::{Error, Message};
+
+pub async fn serve(server: MyServer, bind_addr: &str) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(bind_addr).await?;
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(server, stream).await {
+                tracing::warn!(%peer, "WebSocket session failed: {e}");
+            }
+        });
+    }
+}
+
+async fn serve_connection(server: MyServer, stream: TcpStream) -> anyhow::Result<()> {
+    let (mut socket, stream) = tokio_tungstenite::accept_async(stream).await?.split();
+    // A single writer, so that responses and decode errors never interleave
+    let (frames, mut outgoing) = mpsc::unbounded::<String>();
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = outgoing.next().await {
+            socket.send(Message::text(frame)).await?;
+        }
+        Ok::<_, Error>(())
+    });
+    let sink =
+        frames
+            .clone()
+            .sink_map_err(std::io::Error::other)
+            .with(|message: ServerJsonRpcMessage| {
+                ready(serde_json::to_string(&message).map_err(std::io::Error::other))
+            });
+    // A close frame or a broken connection ends the stream, which ends the
+    // session and this task with it
+    let stream = stream
+        .take_while(|message| ready(matches!(message, Ok(m) if !m.is_close())))
+        .filter_map(move |message| {
+            let decoded = match message {
+                Ok(Message::Text(text)) => decode_line(text.as_bytes()),
+                Ok(Message::Binary(bytes)) => decode_line(&bytes),
+                _ => Ok(None),
+            };
+            ready(match decoded {
+                Ok(message) => message,
+                Err(reply) => {
+                    let _ = frames.unbounded_send(reply.to_string());
+                    None
+                }
+            })
+        });
+    let running = server.serve((sink, Box::pin(stream))).await?;
+    running.waiting().await?;
+    // Every sender is gone with the session: the writer ends once it has
+    // sent what is left
+    writer.await??;
+    Ok(())
+}
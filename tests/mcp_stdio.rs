@@ -0,0 +1,651 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use rmcp::serde_json::{self, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+// Minimal HTTP server answering every request whose path matches one of the
+// given routes with the associated body, and 404 otherwise. A body of
+// "redirect:<location>" answers with a redirect instead, and one of
+// "gzip:<body>" with the gzip-compressed body when the client accepts it. A
+// body of "reject:<reason>" answers 400 Bad Request with the reason as body,
+// and one of "ratelimit:<secs>" 429 Too Many Requests with that Retry-After.
+fn spawn_mock_esplora(routes: Vec<(&'static str, &'static str)>) -> String {
+    spawn_mock_esplora_on("127.0.0.1:0", routes)
+}
+
+fn spawn_mock_esplora_on(bind_addr: &str, routes: Vec<(&'static str, &'static str)>) -> String {
+    let listener = TcpListener::bind(bind_addr).expect("Failed to bind mock server");
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let target = request.split_whitespace().nth(1).unwrap_or("/");
+            // Requests sent through an HTTP proxy carry the absolute URL
+            let path = match target.split_once("://") {
+                Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+                None => target,
+            };
+            let accepts_gzip = request.lines().any(|line| {
+                let line = line.to_ascii_lowercase();
+                line.starts_with("accept-encoding:") && line.contains("gzip")
+            });
+            let response = match routes.iter().find(|(route, _)| *route == path) {
+                Some((_, body)) if body.starts_with("redirect:") => format!(
+                    "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    &body["redirect:".len()..]
+                ),
+                Some((_, body)) if body.starts_with("reject:") => format!(
+                    "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len() - "reject:".len(),
+                    &body["reject:".len()..]
+                ),
+                Some((_, body)) if body.starts_with("ratelimit:") => format!(
+                    "HTTP/1.1 429 Too Many Requests\r\nRetry-After: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    &body["ratelimit:".len()..]
+                ),
+                Some((_, body)) if body.starts_with("gzip:") && accepts_gzip => {
+                    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(body["gzip:".len()..].as_bytes()).unwrap();
+                    let compressed = encoder.finish().unwrap();
+                    let mut response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        compressed.len()
+                    )
+                    .into_bytes();
+                    response.extend(compressed);
+                    let _ = stream.write_all(&response);
+                    continue;
+                }
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                ),
+                None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    format!("http://{addr}")
+}
+
+// HTTPS server with a freshly generated self-signed certificate for
+// localhost, answering every request with the given body
+fn spawn_self_signed_esplora(body: &'static str) -> String {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let key = rustls::pki_types::PrivateKeyDer::Pkcs8(cert.key_pair.serialize_der().into());
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_no_client_auth()
+    .with_single_cert(vec![cert.cert.der().clone()], key)
+    .unwrap();
+    let config = Arc::new(config);
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock server");
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut connection = rustls::ServerConnection::new(config.clone()).unwrap();
+            let mut tls = rustls::Stream::new(&mut connection, &mut stream);
+            let mut buf = [0u8; 4096];
+            // Clients verifying the certificate abort the handshake
+            if tls.read(&mut buf).is_err() {
+                continue;
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = tls.write_all(response.as_bytes());
+            connection.send_close_notify();
+            let _ = connection.complete_io(&mut stream);
+        }
+    });
+    format!("https://localhost:{port}")
+}
+
+const PROXY_VARS: [&str; 6] = [
+    "ALL_PROXY",
+    "all_proxy",
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+];
+
+struct McpClient {
+    child: Child,
+    stdin: ChildStdin,
+    reader: BufReader<ChildStdout>,
+}
+
+impl McpClient {
+    fn spawn(envs: &[(&str, &str)]) -> Self {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_bitcoin-data-mcp"));
+        // Don't route requests to the local mock servers through the developer's proxy
+        for var in PROXY_VARS {
+            command.env_remove(var);
+        }
+        let mut child = command
+            .envs(envs.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start MCP server");
+        let stdin = child.stdin.take().expect("Failed to open stdin");
+        let reader = BufReader::new(child.stdout.take().expect("Failed to open stdout"));
+        McpClient {
+            child,
+            stdin,
+            reader,
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        writeln!(self.stdin, "{message}").expect("Failed to write message");
+        self.stdin.flush().expect("Failed to flush");
+    }
+
+    fn recv(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader
+            .read_line(&mut line)
+            .expect("Failed to read response");
+        serde_json::from_str(&line).expect("Failed to parse response")
+    }
+
+    fn initialize(&mut self) -> Value {
+        self.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "test-client", "version": "1.0.0" }
+            }
+        }));
+        let response = self.recv();
+        self.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized"
+        }));
+        response
+    }
+}
+
+impl Drop for McpClient {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_initialize_then_tool_call_against_mock_backend() {
+    let base_url = spawn_mock_esplora(vec![
+        ("/blocks/tip/height", "840000"),
+        (
+            "/tx/4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+            r#"{"txid":"4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b","status":{"confirmed":true}}"#,
+        ),
+    ]);
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+
+    let init_response = client.initialize();
+    assert_eq!(init_response["id"], 1);
+    assert_eq!(
+        init_response["result"]["serverInfo"]["name"],
+        "Bitcoin Data MCP Server"
+    );
+    // The version requested by the client is supported, so it is kept
+    assert_eq!(init_response["result"]["protocolVersion"], "2024-11-05");
+
+    // Tool calls sent right after the initialized notification must be answered in order
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": "get_bitcoin_tip_height", "arguments": {} }
+    }));
+    let response = client.recv();
+    assert_eq!(response["id"], 2);
+    assert_eq!(response["result"]["content"][0]["text"], "840000");
+
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "tools/call",
+        // Pasted ids are normalized before the lookup
+        "params": { "name": "get_bitcoin_tx", "arguments": { "txid": " 0x4A5E1E4BAAB89F3A32518A88C31BC87F618F76673E2CC77AB2127B7AFDEDA33B\n" } }
+    }));
+    let response = client.recv();
+    assert_eq!(response["id"], 3);
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let tx: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(
+        tx["txid"],
+        "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+    );
+}
+
+#[test]
+fn test_garbled_line_is_answered_with_parse_error() {
+    let base_url = spawn_mock_esplora(vec![("/blocks/tip/height", "840000")]);
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+
+    // A partial message, e.g. cut off by a client crashing mid-write
+    writeln!(client.stdin, r#"{{"jsonrpc":"2.0","id":1,"method":"initia"#).unwrap();
+    client.stdin.flush().unwrap();
+    let response = client.recv();
+    assert_eq!(response["error"]["code"], -32700);
+    assert_eq!(response["id"], Value::Null);
+
+    // The session goes on
+    let init_response = client.initialize();
+    assert_eq!(init_response["id"], 1);
+    assert_eq!(
+        init_response["result"]["serverInfo"]["name"],
+        "Bitcoin Data MCP Server"
+    );
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": "get_bitcoin_tip_height", "arguments": {} }
+    }));
+    assert_eq!(client.recv()["result"]["content"][0]["text"], "840000");
+}
+
+#[test]
+fn test_tool_call_backend_error_is_reported() {
+    let base_url = spawn_mock_esplora(vec![]);
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+    client.initialize();
+
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": "get_bitcoin_tx", "arguments": { "txid": "0000000000000000000000000000000000000000000000000000000000000000" } }
+    }));
+    let response = client.recv();
+    assert_eq!(response["id"], 2);
+    assert!(response["error"].is_object(), "Should return an error");
+}
+
+#[test]
+fn test_metadata_envelope() {
+    let base_url = spawn_mock_esplora(vec![("/blocks/tip/height", "840000")]);
+    let mut client = McpClient::spawn(&[
+        ("ESPLORA_BITCOIN_MAINNET_URL", &base_url),
+        ("INCLUDE_METADATA", "1"),
+    ]);
+    client.initialize();
+
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": "get_bitcoin_tip_height", "arguments": {} }
+    }));
+    let response = client.recv();
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let envelope: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(envelope["data"], 840000);
+    let upstream = &envelope["metadata"]["upstream"][0];
+    assert_eq!(upstream["url"], format!("{base_url}/blocks/tip/height"));
+    assert_eq!(upstream["status"], 200);
+}
+
+#[test]
+fn test_falls_back_to_secondary_backend() {
+    let base_url = spawn_mock_esplora(vec![("/blocks/tip/height", "840000")]);
+    // Nothing listens on port 1, so the primary backend refuses connections
+    let backends = format!("http://127.0.0.1:1,{base_url}");
+    let mut client = McpClient::spawn(&[
+        ("ESPLORA_BITCOIN_MAINNET_URL", &backends),
+        ("INCLUDE_METADATA", "1"),
+    ]);
+    let response = call_tip_height(&mut client);
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let envelope: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(envelope["data"], 840000);
+    let upstream = envelope["metadata"]["upstream"].as_array().unwrap();
+    assert_eq!(upstream.len(), 2);
+    assert_eq!(upstream[0]["url"], "http://127.0.0.1:1/blocks/tip/height");
+    assert_eq!(upstream[1]["url"], format!("{base_url}/blocks/tip/height"));
+    assert_eq!(upstream[1]["status"], 200);
+}
+
+fn call_tip_height(client: &mut McpClient) -> Value {
+    client.initialize();
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": "get_bitcoin_tip_height", "arguments": {} }
+    }));
+    client.recv()
+}
+
+#[test]
+fn test_requests_go_through_http_proxy() {
+    // The backend host doesn't resolve, so the call only succeeds via the proxy
+    let proxy_url = spawn_mock_esplora(vec![("/blocks/tip/height", "840000")]);
+    let mut client = McpClient::spawn(&[
+        ("ESPLORA_BITCOIN_MAINNET_URL", "http://esplora.invalid"),
+        ("HTTP_PROXY", &proxy_url),
+    ]);
+    let response = call_tip_height(&mut client);
+    assert_eq!(response["result"]["content"][0]["text"], "840000");
+}
+
+#[test]
+fn test_ipv6_backend() {
+    let base_url = spawn_mock_esplora_on("[::1]:0", vec![("/blocks/tip/height", "840000")]);
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+    let response = call_tip_height(&mut client);
+    assert_eq!(response["result"]["content"][0]["text"], "840000");
+}
+
+fn ws_call(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, message: Value) -> Value {
+    socket
+        .send(Message::text(message.to_string()))
+        .expect("Failed to send frame");
+    loop {
+        match socket.read().expect("Failed to read frame") {
+            Message::Text(text) => return serde_json::from_str(text.as_str()).unwrap(),
+            Message::Ping(_) | Message::Pong(_) => continue,
+            other => panic!("Unexpected frame: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_websocket_transport() {
+    let base_url = spawn_mock_esplora(vec![("/blocks/tip/height", "840000")]);
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let bind_addr = format!("127.0.0.1:{port}");
+    let _server = McpClient::spawn(&[
+        ("ESPLORA_BITCOIN_MAINNET_URL", &base_url),
+        ("MCP_TRANSPORT", "websocket"),
+        ("MCP_BIND_ADDR", &bind_addr),
+    ]);
+
+    // Sessions are independent, a second client works after the first one leaves
+    for _ in 0..2 {
+        let mut socket = (0..50)
+            .find_map(
+                |_| match tungstenite::connect(format!("ws://{bind_addr}")) {
+                    Ok((socket, _)) => Some(socket),
+                    Err(_) => {
+                        thread::sleep(Duration::from_millis(100));
+                        None
+                    }
+                },
+            )
+            .expect("Failed to connect to the WebSocket server");
+        let response = ws_call(
+            &mut socket,
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {},
+                    "clientInfo": { "name": "test-client", "version": "1.0.0" }
+                }
+            }),
+        );
+        assert!(response["result"]["serverInfo"].is_object());
+        socket
+            .send(Message::text(
+                serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })
+                    .to_string(),
+            ))
+            .unwrap();
+        let response = ws_call(
+            &mut socket,
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": { "name": "get_bitcoin_tip_height", "arguments": {} }
+            }),
+        );
+        assert_eq!(response["result"]["content"][0]["text"], "840000");
+
+        // A frame that isn't JSON is answered with an error, and the session goes on
+        socket
+            .send(Message::text(r#"{"jsonrpc":"2.0","id":3,"meth"#))
+            .unwrap();
+        let response = loop {
+            match socket.read().unwrap() {
+                Message::Text(text) => break serde_json::from_str::<Value>(text.as_str()).unwrap(),
+                _ => continue,
+            }
+        };
+        assert_eq!(response["error"]["code"], -32700);
+        let response = ws_call(
+            &mut socket,
+            serde_json::json!({ "jsonrpc": "2.0", "id": 4, "method": "ping" }),
+        );
+        assert_eq!(response["id"], 4);
+        socket.close(None).unwrap();
+    }
+}
+
+#[test]
+fn test_html_response_is_reported() {
+    let base_url = spawn_mock_esplora(vec![(
+        "/blocks/tip/height",
+        "<html><body>Please log in</body></html>",
+    )]);
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+    let response = call_tip_height(&mut client);
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(
+        message.contains("Unexpected non-JSON response from backend"),
+        "unexpected error: {message}"
+    );
+    assert!(
+        message.contains("status code 200"),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn test_redirects_are_followed_within_the_origin() {
+    let base_url = spawn_mock_esplora(vec![
+        ("/blocks/tip/height", "redirect:/v2/blocks/tip/height"),
+        ("/v2/blocks/tip/height", "840000"),
+        (
+            "/loop/blocks/tip/height",
+            "redirect:/loop/blocks/tip/height",
+        ),
+    ]);
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+    let response = call_tip_height(&mut client);
+    assert_eq!(response["result"]["content"][0]["text"], "840000");
+
+    let looping_url = format!("{base_url}/loop");
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &looping_url)]);
+    let response = call_tip_height(&mut client);
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(
+        message.contains("more than 3 redirects"),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn test_insecure_tls_accepts_self_signed_certificate() {
+    let base_url = spawn_self_signed_esplora("840000");
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+    let response = call_tip_height(&mut client);
+    assert!(
+        response["error"].is_object(),
+        "Self-signed certificate should be rejected: {response}"
+    );
+
+    let mut client = McpClient::spawn(&[
+        ("ESPLORA_BITCOIN_MAINNET_URL", &base_url),
+        ("ESPLORA_INSECURE_TLS", "1"),
+    ]);
+    let response = call_tip_height(&mut client);
+    assert_eq!(response["result"]["content"][0]["text"], "840000");
+}
+
+#[test]
+fn test_cross_origin_redirect_is_rejected() {
+    let other_url = spawn_mock_esplora(vec![("/blocks/tip/height", "840000")]);
+    let location: &'static str =
+        Box::leak(format!("redirect:{other_url}/blocks/tip/height").into_boxed_str());
+    let base_url = spawn_mock_esplora(vec![("/blocks/tip/height", location)]);
+
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+    let response = call_tip_height(&mut client);
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(
+        message.contains("another origin"),
+        "unexpected error: {message}"
+    );
+
+    let mut client = McpClient::spawn(&[
+        ("ESPLORA_BITCOIN_MAINNET_URL", &base_url),
+        ("ALLOW_CROSS_ORIGIN_REDIRECTS", "1"),
+    ]);
+    let response = call_tip_height(&mut client);
+    assert_eq!(response["result"]["content"][0]["text"], "840000");
+}
+
+#[test]
+fn test_gzip_response_is_decompressed() {
+    const HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+    const PATH: &str = "/block/0000000000000000000000000000000000000000000000000000000000000000";
+    const BLOCK: &str =
+        r#"{"id":"0000000000000000000000000000000000000000000000000000000000000000","height":0}"#;
+    let plain_url = spawn_mock_esplora(vec![(PATH, BLOCK)]);
+    let gzip_url = spawn_mock_esplora(vec![(
+        PATH,
+        concat!(
+            "gzip:",
+            r#"{"id":"0000000000000000000000000000000000000000000000000000000000000000","height":0}"#
+        ),
+    )]);
+    let mut results = Vec::new();
+    for base_url in [plain_url, gzip_url] {
+        let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+        client.initialize();
+        client.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": { "name": "get_bitcoin_block", "arguments": { "hash": HASH } }
+        }));
+        let response = client.recv();
+        results.push(response["result"]["content"][0]["text"].clone());
+    }
+    assert_eq!(results[0], BLOCK);
+    assert_eq!(results[0], results[1]);
+}
+
+#[test]
+fn test_rate_limit_reports_retry_after() {
+    let base_url = spawn_mock_esplora(vec![("/blocks/tip/height", "ratelimit:30")]);
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", &base_url)]);
+    client.initialize();
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": "get_bitcoin_tip_height", "arguments": {} }
+    }));
+    let response = client.recv();
+    let error = &response["error"];
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .ends_with("status code 429, retry after 30s"),
+        "unexpected error: {error}"
+    );
+    assert_eq!(error["data"]["status"], 429);
+    assert_eq!(error["data"]["retry_after_secs"], 30);
+}
+
+// The genesis block coinbase transaction
+const GENESIS_TX_HEX: &str = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+const GENESIS_TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+fn call_broadcast(base_url: &str) -> Value {
+    let mut client = McpClient::spawn(&[("ESPLORA_BITCOIN_MAINNET_URL", base_url)]);
+    client.initialize();
+    client.send(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": "broadcast_bitcoin_tx", "arguments": { "hex": GENESIS_TX_HEX } }
+    }));
+    client.recv()
+}
+
+#[test]
+fn test_broadcast_is_idempotent() {
+    let base_url = spawn_mock_esplora(vec![("/tx", GENESIS_TXID)]);
+    let response = call_broadcast(&base_url);
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let result: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(result["txid"], GENESIS_TXID);
+    assert_eq!(result["already_known"], false);
+
+    let base_url = spawn_mock_esplora(vec![(
+        "/tx",
+        r#"reject:sendrawtransaction RPC error: {"code":-27,"message":"Transaction already in block chain"}"#,
+    )]);
+    let response = call_broadcast(&base_url);
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let result: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(result["txid"], GENESIS_TXID);
+    assert_eq!(result["already_known"], true);
+
+    let base_url = spawn_mock_esplora(vec![(
+        "/tx",
+        r#"reject:sendrawtransaction RPC error: {"code":-25,"message":"bad-txns-inputs-missingorspent"}"#,
+    )]);
+    let response = call_broadcast(&base_url);
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(
+        message.ends_with("rejected: bad-txns-inputs-missingorspent"),
+        "unexpected error: {message}"
+    );
+    assert_eq!(
+        response["error"]["data"]["category"],
+        "inputs_missing_or_spent"
+    );
+}